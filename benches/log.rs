@@ -0,0 +1,52 @@
+#[macro_use]
+extern crate criterion;
+
+extern crate toymath;
+
+use std::time::Duration;
+use std::f64::consts::{E, PI};
+
+use criterion::{Criterion, ParameterizedBenchmark};
+
+use toymath::{log2, log2_precise, ln, ln_precise};
+
+fn bench_log2(c: &mut Criterion) {
+    c.bench(
+        "log2",
+        ParameterizedBenchmark::new(
+            "stdlib",
+            |b, &f| b.iter(|| f.log2()),
+            vec![0.001, 0.75, 1.0 + 1e-9, 2.0, E, PI, 1000.0, 1e300]
+        ).with_function(
+            "toymath",
+            |b, &f| b.iter(|| log2(f))
+        ).with_function(
+            "toymath_precise",
+            |b, &f| b.iter(|| log2_precise(f))
+        )
+            .warm_up_time(Duration::from_millis(1000))
+            .measurement_time(Duration::from_millis(1000))
+    );
+}
+
+fn bench_ln(c: &mut Criterion) {
+    c.bench(
+        "ln",
+        ParameterizedBenchmark::new(
+            "stdlib",
+            |b, &f| b.iter(|| f.ln()),
+            vec![0.001, 0.75, 1.0 + 1e-9, 2.0, E, PI, 1000.0, 1e300]
+        ).with_function(
+            "toymath",
+            |b, &f| b.iter(|| ln(f))
+        ).with_function(
+            "toymath_precise",
+            |b, &f| b.iter(|| ln_precise(f))
+        )
+            .warm_up_time(Duration::from_millis(1000))
+            .measurement_time(Duration::from_millis(1000))
+    );
+}
+
+criterion_group!(benches, bench_log2, bench_ln);
+criterion_main!(benches);
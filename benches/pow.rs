@@ -0,0 +1,30 @@
+#[macro_use]
+extern crate criterion;
+
+extern crate toymath;
+
+use std::time::Duration;
+use std::f64::consts::{E, PI};
+
+use criterion::{Criterion, ParameterizedBenchmark};
+
+use toymath::pow;
+
+fn bench_pow(c: &mut Criterion) {
+    c.bench(
+        "pow",
+        ParameterizedBenchmark::new(
+            "stdlib",
+            |b, &(base, power): &(f64, f64)| b.iter(|| base.powf(power)),
+            vec![(2.0, 0.5), (E, PI), (10.0, -3.0), (0.999999, 1e6), (1.5, 1000.5), (-8.0, 3.0)]
+        ).with_function(
+            "toymath",
+            |b, &(base, power): &(f64, f64)| b.iter(|| pow(base, power))
+        )
+            .warm_up_time(Duration::from_millis(1000))
+            .measurement_time(Duration::from_millis(1000))
+    );
+}
+
+criterion_group!(benches, bench_pow);
+criterion_main!(benches);
@@ -9,7 +9,7 @@ use std::f64::consts::{PI, FRAC_PI_6, FRAC_PI_4, FRAC_PI_3, FRAC_PI_2};
 
 use criterion::{Criterion, ParameterizedBenchmark};
 
-use toymath::sin_cos;
+use toymath::{sin_cos, tan, asin, acos, atan, atan2};
 
 fn bench_trig(c: &mut Criterion) {
     c.bench(
@@ -29,5 +29,77 @@ fn bench_trig(c: &mut Criterion) {
 
 
 
-criterion_group!(benches, bench_trig);
+fn bench_tan(c: &mut Criterion) {
+    c.bench(
+        "tan",
+        ParameterizedBenchmark::new(
+            "stdlib",
+            |b, &f| b.iter(|| f.tan()),
+            vec![0.0, 0.42398409132841234, 1.48978987, FRAC_PI_6, FRAC_PI_4, FRAC_PI_3, PI, PI + FRAC_PI_4]
+        ).with_function(
+            "toymath",
+            |b, &f| b.iter(|| tan(f))
+        )
+            .warm_up_time(Duration::from_millis(1000))
+            .measurement_time(Duration::from_millis(1000))
+    );
+}
+
+fn bench_inverse_trig(c: &mut Criterion) {
+    let inputs = vec![0.0, 0.1, 0.42398409132841234, 0.5, 0.75, 0.99];
+    c.bench(
+        "asin",
+        ParameterizedBenchmark::new(
+            "stdlib",
+            |b, &f| b.iter(|| f.asin()),
+            inputs.clone()
+        ).with_function(
+            "toymath",
+            |b, &f| b.iter(|| asin(f))
+        )
+            .warm_up_time(Duration::from_millis(1000))
+            .measurement_time(Duration::from_millis(1000))
+    );
+    c.bench(
+        "acos",
+        ParameterizedBenchmark::new(
+            "stdlib",
+            |b, &f| b.iter(|| f.acos()),
+            inputs.clone()
+        ).with_function(
+            "toymath",
+            |b, &f| b.iter(|| acos(f))
+        )
+            .warm_up_time(Duration::from_millis(1000))
+            .measurement_time(Duration::from_millis(1000))
+    );
+    c.bench(
+        "atan",
+        ParameterizedBenchmark::new(
+            "stdlib",
+            |b, &f| b.iter(|| f.atan()),
+            vec![0.0, 0.1, 0.42398409132841234, 1.0, 2.5, 100.0]
+        ).with_function(
+            "toymath",
+            |b, &f| b.iter(|| atan(f))
+        )
+            .warm_up_time(Duration::from_millis(1000))
+            .measurement_time(Duration::from_millis(1000))
+    );
+    c.bench(
+        "atan2",
+        ParameterizedBenchmark::new(
+            "stdlib",
+            |b, &(y, x): &(f64, f64)| b.iter(|| y.atan2(x)),
+            vec![(1.0, 1.0), (0.5, -2.0), (-3.0, -0.25), (-1.0, 100.0)]
+        ).with_function(
+            "toymath",
+            |b, &(y, x): &(f64, f64)| b.iter(|| atan2(y, x))
+        )
+            .warm_up_time(Duration::from_millis(1000))
+            .measurement_time(Duration::from_millis(1000))
+    );
+}
+
+criterion_group!(benches, bench_trig, bench_tan, bench_inverse_trig);
 criterion_main!(benches);
@@ -0,0 +1,176 @@
+//! Big unsigned integers, which are just big enough to convert between extended precision values and text exactly.
+use core::cmp::Ordering;
+
+/// The number of limbs for the largest integer a conversion needs,
+/// which is `36^16464` shifted left by 66 bits when parsing (with a few limbs to spare).
+const LIMBS: usize = 2670;
+
+/// Gives the largest power of the radix that fits in a limb, along with its exponent
+pub fn chunk_size(radix: u32) -> (u32, u32) {
+    let mut power = radix;
+    let mut exponent = 1;
+    while let Some(next) = power.checked_mul(radix) {
+        power = next;
+        exponent += 1;
+    }
+    (power, exponent)
+}
+
+/// An unsigned integer with a fixed capacity, which is stored as little-endian limbs
+pub struct Big {
+    limbs: [u32; LIMBS],
+    /// The number of limbs in use, which never includes leading zeros
+    length: usize,
+}
+impl Big {
+    pub fn new(value: u32) -> Big {
+        let mut limbs = [0; LIMBS];
+        limbs[0] = value;
+        Big { limbs, length: if value != 0 { 1 } else { 0 } }
+    }
+    pub fn from_u64(value: u64) -> Big {
+        let mut result = Big::new(value as u32);
+        result.limbs[1] = (value >> 32) as u32;
+        result.length = 2;
+        result.trim();
+        result
+    }
+    pub fn from_u128(value: u128) -> Big {
+        let mut result = Big::new(0);
+        for (index, limb) in result.limbs[..4].iter_mut().enumerate() {
+            *limb = (value >> (32 * index)) as u32;
+        }
+        result.length = 4;
+        result.trim();
+        result
+    }
+    #[inline]
+    pub fn is_zero(&self) -> bool {
+        self.length == 0
+    }
+    pub fn bit_length(&self) -> u32 {
+        if self.length == 0 {
+            0
+        } else {
+            32 * self.length as u32 - self.limbs[self.length - 1].leading_zeros()
+        }
+    }
+    fn trim(&mut self) {
+        while self.length > 0 && self.limbs[self.length - 1] == 0 {
+            self.length -= 1;
+        }
+    }
+    /// Multiplies by the factor and then adds the addend
+    pub fn mul_add(&mut self, factor: u32, addend: u32) {
+        let mut carry = addend as u64;
+        for limb in &mut self.limbs[..self.length] {
+            let product = *limb as u64 * factor as u64 + carry;
+            *limb = product as u32;
+            carry = product >> 32;
+        }
+        if carry != 0 {
+            self.limbs[self.length] = carry as u32;
+            self.length += 1;
+        }
+    }
+    pub fn mul_pow(&mut self, radix: u32, mut exponent: u32) {
+        let (chunk, chunk_digits) = chunk_size(radix);
+        while exponent >= chunk_digits {
+            self.mul_add(chunk, 0);
+            exponent -= chunk_digits;
+        }
+        self.mul_add(radix.pow(exponent), 0);
+    }
+    pub fn shl(&mut self, amount: u32) {
+        if self.is_zero() {
+            return
+        }
+        let limbs = (amount / 32) as usize;
+        let bits = amount % 32;
+        if bits == 0 {
+            for index in (0..self.length).rev() {
+                self.limbs[index + limbs] = self.limbs[index];
+            }
+            self.length += limbs;
+        } else {
+            self.limbs[self.length + limbs] = self.limbs[self.length - 1] >> (32 - bits);
+            for index in (1..self.length).rev() {
+                self.limbs[index + limbs] = (self.limbs[index] << bits) | (self.limbs[index - 1] >> (32 - bits));
+            }
+            self.limbs[limbs] = self.limbs[0] << bits;
+            self.length += limbs + 1;
+        }
+        for limb in &mut self.limbs[..limbs] {
+            *limb = 0;
+        }
+        self.trim();
+    }
+    /// Shifts right by a single bit
+    pub fn shr1(&mut self) {
+        for index in 0..self.length {
+            let carry = if index + 1 < self.length { self.limbs[index + 1] << 31 } else { 0 };
+            self.limbs[index] = (self.limbs[index] >> 1) | carry;
+        }
+        self.trim();
+    }
+    pub fn compare(&self, other: &Big) -> Ordering {
+        self.length.cmp(&other.length).then_with(|| {
+            self.limbs[..self.length].iter().rev().cmp(other.limbs[..other.length].iter().rev())
+        })
+    }
+    /// Sets this to the sum of the two integers
+    pub fn assign_sum(&mut self, first: &Big, second: &Big) {
+        let length = first.length.max(second.length);
+        let get = |big: &Big, index: usize| big.limbs[..big.length].get(index).map_or(0, |&limb| limb as u64);
+        let mut carry = 0;
+        for index in 0..length {
+            let sum = get(first, index) + get(second, index) + carry;
+            self.limbs[index] = sum as u32;
+            carry = sum >> 32;
+        }
+        self.limbs[length] = carry as u32;
+        self.length = length + 1;
+        self.trim();
+    }
+    /// Divides by the other integer and keeps the remainder, where the quotient has to be a single decimal digit
+    pub fn div_rem_digit(&mut self, divisor: &Big) -> u8 {
+        let mut digit = 0;
+        while self.compare(divisor) != Ordering::Less {
+            self.sub_assign(divisor);
+            digit += 1;
+        }
+        debug_assert!(digit < 10);
+        digit
+    }
+    /// Subtracts the other integer, which can't be any larger than this one
+    pub fn sub_assign(&mut self, other: &Big) {
+        debug_assert_ne!(self.compare(other), Ordering::Less);
+        let mut borrow = false;
+        for index in 0..self.length {
+            let subtrahend = other.limbs[..other.length].get(index).map_or(0, |&limb| limb as u64) + borrow as u64;
+            let (difference, overflowed) = (self.limbs[index] as u64).overflowing_sub(subtrahend);
+            self.limbs[index] = difference as u32;
+            borrow = overflowed;
+        }
+        debug_assert!(!borrow);
+        self.trim();
+    }
+    /// Gives the top 128 bits, the exponent of the lowest one, and whether any of the bits below it are set
+    pub fn top_bits(&self) -> (u128, u32, bool) {
+        let start = self.bit_length().saturating_sub(128);
+        let limb = (start / 32) as usize;
+        let offset = start % 32;
+        let get = |index: usize| self.limbs[..self.length].get(index).map_or(0, |&limb| limb as u128);
+        let mut top = 0u128;
+        for index in 0..4 {
+            top |= get(limb + index) << (32 * index);
+        }
+        top >>= offset;
+        if offset != 0 {
+            top |= get(limb + 4) << (128 - offset);
+        }
+        let rest = self.limbs[..limb].iter().any(|&limb| limb != 0) ||
+            get(limb) & ((1 << offset) - 1) != 0;
+        (top, start, rest)
+    }
+}
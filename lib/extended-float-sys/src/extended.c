@@ -47,6 +47,7 @@ INPLACE_BINARY_OP(extended_min, fminl(first, second));
 INPLACE_BINARY_OP(extended_max, fmaxl(first, second));
 INPLACE_BINARY_OP(extended_pow, powl(first, second));
 INPLACE_BINARY_OP(extended_hypot, hypotl(first, second));
+INPLACE_BINARY_OP(extended_atan2, atan2l(first, second));
 
 
 INPLACE_UNARY_OP(extended_sqrt, sqrtl(first));
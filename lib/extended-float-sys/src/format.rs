@@ -0,0 +1,184 @@
+//! Converting extended precision values to decimal digits, which is exact since it uses big integers.
+//!
+//! Like the formatting of the primitive floats in `core`, both conversions give digits `d1 d2 ... dn`
+//! and an exponent `k` for the value `0.d1d2...dn * 10^k`, where the first digit is never zero.
+//! They only handle positive finite values, so the sign and the special values are up to the caller.
+use core::cmp::Ordering;
+
+use ExtendedFloat;
+use big::Big;
+use soft::{split, EXPONENT_BIAS};
+
+/// The most digits the shortest representation can need, which is enough to tell apart any two 64-bit significands
+pub const SHORTEST_DIGITS: usize = 21;
+/// The most significant digits any value can have when it's written out exactly.
+///
+/// The most digits come from the largest subnormal, since `m * 2^-16445` has as many as `m * 5^16445`,
+/// so beyond this there are only ever zeros.
+pub const MAX_DIGITS: usize = 11514;
+
+/// Splits a positive finite value into `significand * 2^exponent`,
+/// along with whether the next smaller value is only half as far away as the next larger one.
+///
+/// That happens at the powers of two, except for the smallest normal (where the subnormals have the same spacing).
+fn decode(value: ExtendedFloat) -> (u64, i32, bool) {
+    let (_, exponent, significand) = split(value);
+    debug_assert!(significand != 0 && exponent != 0x7FFF);
+    let lower_closer = significand == 1 << 63 && exponent > 1;
+    (significand, exponent.max(1) as i32 - EXPONENT_BIAS - 63, lower_closer)
+}
+
+/// Estimates the decimal exponent of `significand * 2^exponent` from its highest bit,
+/// which is never too large but can be one too small.
+fn estimate(significand: u64, exponent: i32) -> i32 {
+    let log2 = exponent + 63 - significand.leading_zeros() as i32;
+    // This is `floor(log10(2^log2))`, since the constant is `2^32 * log10(2)` and that's close enough for every exponent we have
+    ((log2 as i64 * 1_292_913_986) >> 32) as i32 + 1
+}
+
+/// Scales the fraction `numerator / denominator` by `10^-exponent`
+fn scale(numerators: &mut [&mut Big], denominator: &mut Big, exponent: i32) {
+    if exponent >= 0 {
+        denominator.mul_pow(10, exponent as u32);
+    } else {
+        for numerator in numerators {
+            numerator.mul_pow(10, (-exponent) as u32);
+        }
+    }
+}
+
+/// Gives the shortest digits that parse back to the same value, using the algorithm from
+/// "Printing Floating-Point Numbers Quickly and Accurately" by Burger and Dybvig.
+///
+/// When more than one is the shortest this picks the closest, and the buffer needs room for `SHORTEST_DIGITS`.
+pub fn shortest(value: ExtendedFloat, digits: &mut [u8]) -> (usize, i32) {
+    let (significand, exponent, lower_closer) = decode(value);
+    // Ties round to even, so the halfway points themselves parse back to this value when the significand is even
+    let inclusive = significand & 1 == 0;
+    let beyond = |ordering: Ordering| ordering == Ordering::Greater || (inclusive && ordering == Ordering::Equal);
+    /*
+     * The value is `remainder / scale`, and the halfway points to the values next to it
+     * are `high / scale` above it and `low / scale` below it.
+     * Everything is doubled (or quadrupled) so the halfway points are integers.
+     */
+    let mut remainder = Big::from_u64(significand);
+    let mut scale_factor = Big::new(1);
+    let mut high = Big::new(1);
+    let mut low = Big::new(1);
+    let shift = if lower_closer { 2 } else { 1 };
+    remainder.shl(shift);
+    scale_factor.shl(shift);
+    if lower_closer {
+        high.shl(1);
+    }
+    if exponent >= 0 {
+        remainder.shl(exponent as u32);
+        high.shl(exponent as u32);
+        low.shl(exponent as u32);
+    } else {
+        scale_factor.shl((-exponent) as u32);
+    }
+    let mut decimal_exponent = estimate(significand, exponent);
+    scale(&mut [&mut remainder, &mut high, &mut low], &mut scale_factor, decimal_exponent);
+    // Fix the estimate, so the upper halfway point is less than one
+    let mut sum = Big::new(0);
+    loop {
+        sum.assign_sum(&remainder, &high);
+        if !beyond(sum.compare(&scale_factor)) {
+            break
+        }
+        scale_factor.mul_add(10, 0);
+        decimal_exponent += 1;
+    }
+    let mut length = 0;
+    loop {
+        remainder.mul_add(10, 0);
+        high.mul_add(10, 0);
+        low.mul_add(10, 0);
+        let digit = remainder.div_rem_digit(&scale_factor);
+        let low_ordering = remainder.compare(&low);
+        let below_low = low_ordering == Ordering::Less || (inclusive && low_ordering == Ordering::Equal);
+        sum.assign_sum(&remainder, &high);
+        let above_high = beyond(sum.compare(&scale_factor));
+        let last = match (below_low, above_high) {
+            (false, false) => {
+                digits[length] = digit;
+                length += 1;
+                continue
+            },
+            (true, false) => digit,
+            (false, true) => digit + 1,
+            // Both are close enough, so pick the closer one
+            (true, true) => {
+                remainder.shl(1);
+                if remainder.compare(&scale_factor) == Ordering::Less { digit } else { digit + 1 }
+            }
+        };
+        digits[length] = last;
+        return (length + 1, decimal_exponent)
+    }
+}
+
+/// Gives the digits correctly rounded (with ties to even), stopping at either the digit for `10^limit`
+/// or the length of the buffer, whichever comes first.
+///
+/// The trailing zeros are left out, so the digits can also stop early when the rest are zeros.
+/// If the value rounds to zero at the limit, there aren't any digits at all.
+pub fn exact(value: ExtendedFloat, digits: &mut [u8], limit: i32) -> (usize, i32) {
+    let (significand, exponent, _) = decode(value);
+    let mut remainder = Big::from_u64(significand);
+    let mut scale_factor = Big::new(1);
+    if exponent >= 0 {
+        remainder.shl(exponent as u32);
+    } else {
+        scale_factor.shl((-exponent) as u32);
+    }
+    let mut decimal_exponent = estimate(significand, exponent);
+    scale(&mut [&mut remainder], &mut scale_factor, decimal_exponent);
+    while remainder.compare(&scale_factor) != Ordering::Less {
+        scale_factor.mul_add(10, 0);
+        decimal_exponent += 1;
+    }
+    let count = (decimal_exponent as i64 - limit as i64).min(digits.len() as i64);
+    if count <= 0 {
+        // The whole value is less than one unit at the limit, so it only rounds up when it's more than half of one
+        remainder.shl(1);
+        if count == 0 && remainder.compare(&scale_factor) == Ordering::Greater {
+            digits[0] = 1;
+            return (1, decimal_exponent + 1)
+        }
+        return (0, decimal_exponent)
+    }
+    let count = count as usize;
+    for index in 0..count {
+        remainder.mul_add(10, 0);
+        digits[index] = remainder.div_rem_digit(&scale_factor);
+        if remainder.is_zero() {
+            return (trim_zeros(&digits[..index + 1]), decimal_exponent)
+        }
+    }
+    remainder.shl(1);
+    let round_up = match remainder.compare(&scale_factor) {
+        Ordering::Less => false,
+        Ordering::Equal => digits[count - 1] % 2 != 0,
+        Ordering::Greater => true,
+    };
+    if !round_up {
+        return (trim_zeros(&digits[..count]), decimal_exponent)
+    }
+    // Carry into the previous digits, and if they're all nines this becomes the next power of ten
+    match digits[..count].iter().rposition(|&digit| digit != 9) {
+        Some(index) => {
+            digits[index] += 1;
+            (index + 1, decimal_exponent)
+        },
+        None => {
+            digits[0] = 1;
+            (1, decimal_exponent + 1)
+        }
+    }
+}
+
+fn trim_zeros(digits: &[u8]) -> usize {
+    digits.iter().rposition(|&digit| digit != 0).map_or(0, |index| index + 1)
+}
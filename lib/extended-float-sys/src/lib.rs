@@ -1,8 +1,23 @@
+//! Low-level bindings for extended precision floats, which are 80-bit x87 values.
+//!
+//! By default these call into C, which only works where the C `long double` is the x87 format.
+//! The `soft-float` feature swaps in a pure Rust implementation (with the same signatures),
+//! which works anywhere and doesn't need the standard library.
+//! The basic arithmetic, comparisons and conversions in `soft` are bit-identical to the x87,
+//! while the transcendental functions in `math` are computed to 128 bits and then rounded.
+//! Both modules are always available, so they can be tested against the C versions.
 #![feature(const_fn)]
+#![no_std]
 extern crate libc;
 
 use libc::{c_int, c_uint, c_char};
 
+pub mod soft;
+pub mod math;
+mod big;
+mod format;
+mod parse;
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 #[repr(C)]
 pub struct ExtendedFloat(pub [u8; 10]);
@@ -21,6 +36,7 @@ impl ExtendedFloat {
     }
 }
 
+#[cfg(not(feature = "soft-float"))]
 extern "C" {
     pub fn extended_add(first: *mut ExtendedFloat, second: *const ExtendedFloat);
     pub fn extended_sub(first: *mut ExtendedFloat, second: *const ExtendedFloat);
@@ -84,4 +100,177 @@ extern "C" {
     pub fn extended_convert_into_f32(first: *const ExtendedFloat) -> f32;
     pub fn extended_convert_into_i64(first: *const ExtendedFloat) -> i64;
     pub fn extended_convert_into_u64(first: *const ExtendedFloat) -> u64;
-}
\ No newline at end of file
+}
+
+/*
+ * With the `soft-float` feature, the basic operations are implemented in Rust instead.
+ * These keep the signatures of the C functions, so the two are interchangeable.
+ */
+
+#[cfg(feature = "soft-float")]
+macro_rules! soft_binary_ops {
+    ($($name:ident => $func:path),*) => {$(
+        #[inline]
+        pub unsafe fn $name(first: *mut ExtendedFloat, second: *const ExtendedFloat) {
+            *first = $func(*first, *second);
+        }
+    )*};
+}
+#[cfg(feature = "soft-float")]
+macro_rules! soft_unary_ops {
+    ($($name:ident => $func:path),*) => {$(
+        #[inline]
+        pub unsafe fn $name(first: *mut ExtendedFloat) {
+            *first = $func(*first);
+        }
+    )*};
+}
+#[cfg(feature = "soft-float")]
+macro_rules! soft_predicates {
+    ($($name:ident => $func:path),*) => {$(
+        #[inline]
+        pub unsafe fn $name(first: *const ExtendedFloat) -> bool {
+            $func(*first)
+        }
+    )*};
+}
+#[cfg(feature = "soft-float")]
+soft_binary_ops!(
+    extended_add => soft::add,
+    extended_sub => soft::sub,
+    extended_mul => soft::mul,
+    extended_div => soft::div,
+    extended_mod => soft::rem,
+    extended_min => soft::min,
+    extended_max => soft::max,
+    extended_pow => math::pow,
+    extended_atan2 => math::atan2,
+    extended_hypot => math::hypot
+);
+#[cfg(feature = "soft-float")]
+soft_unary_ops!(
+    extended_sqrt => soft::sqrt,
+    extended_abs => soft::abs,
+    extended_ceil => soft::ceil,
+    extended_floor => soft::floor,
+    extended_round => soft::round_nearest,
+    extended_trunc => soft::trunc,
+    extended_neg => soft::neg,
+    extended_exp => math::exp,
+    extended_exp_m1 => math::exp_m1,
+    extended_exp2 => math::exp2,
+    extended_ln => math::ln,
+    extended_ln_1p => math::ln_1p,
+    extended_log2 => math::log2,
+    extended_log10 => math::log10,
+    extended_cbrt => math::cbrt,
+    extended_sin => math::sin,
+    extended_cos => math::cos,
+    extended_tan => math::tan,
+    extended_asin => math::asin,
+    extended_acos => math::acos,
+    extended_atan => math::atan,
+    extended_sinh => math::sinh,
+    extended_cosh => math::cosh,
+    extended_tanh => math::tanh,
+    extended_asinh => math::asinh,
+    extended_acosh => math::acosh,
+    extended_atanh => math::atanh
+);
+#[cfg(feature = "soft-float")]
+soft_predicates!(
+    extended_isfinite => soft::is_finite,
+    extended_isnan => soft::is_nan,
+    extended_isinf => soft::is_infinite,
+    extended_isnormal => soft::is_normal
+);
+
+#[cfg(feature = "soft-float")]
+#[inline]
+pub unsafe fn extended_signbit(first: *const ExtendedFloat) -> c_uint {
+    soft::is_sign_negative(*first) as c_uint
+}
+
+#[cfg(feature = "soft-float")]
+#[inline]
+pub unsafe fn extended_eq(first: *const ExtendedFloat, second: *const ExtendedFloat) -> bool {
+    soft::eq(*first, *second)
+}
+
+#[cfg(feature = "soft-float")]
+pub unsafe fn extended_cmp(first: *const ExtendedFloat, second: *const ExtendedFloat) -> c_int {
+    match soft::compare(*first, *second) {
+        Some(::core::cmp::Ordering::Less) => -1,
+        Some(::core::cmp::Ordering::Equal) => 0,
+        Some(::core::cmp::Ordering::Greater) => 1,
+        None => 2
+    }
+}
+
+#[cfg(feature = "soft-float")]
+#[inline]
+pub unsafe fn extended_mul_add(first: *mut ExtendedFloat, second: *const ExtendedFloat, third: *const ExtendedFloat) {
+    *first = soft::mul_add(*first, *second, *third);
+}
+
+#[cfg(feature = "soft-float")]
+pub unsafe fn extended_modf(first: *mut ExtendedFloat, iptr: *mut ExtendedFloat) {
+    let (integer, fraction) = soft::modf(*first);
+    *iptr = integer;
+    *first = fraction;
+}
+
+/// Parses a null-terminated string, skipping leading whitespace just like `strtold`
+#[cfg(feature = "soft-float")]
+pub unsafe fn extended_parse(out: *mut ExtendedFloat, data: *const c_char, end: *mut *mut c_char) {
+    let mut length = 0;
+    while *data.add(length) != 0 {
+        length += 1;
+    }
+    let text = ::core::slice::from_raw_parts(data as *const u8, length);
+    let whitespace = text.iter().take_while(|&&c| c == b' ' || c >= b'\t' && c <= b'\r').count();
+    let (value, consumed) = soft::parse(&text[whitespace..]);
+    *out = value;
+    *end = data.add(if consumed == 0 { 0 } else { whitespace + consumed }) as *mut c_char;
+}
+
+#[cfg(feature = "soft-float")]
+#[inline]
+pub unsafe fn extended_convert_from_f64(out: *mut ExtendedFloat, data: f64) {
+    *out = soft::from_f64(data);
+}
+#[cfg(feature = "soft-float")]
+#[inline]
+pub unsafe fn extended_convert_from_f32(out: *mut ExtendedFloat, data: f32) {
+    *out = soft::from_f32(data);
+}
+#[cfg(feature = "soft-float")]
+#[inline]
+pub unsafe fn extended_convert_from_i64(out: *mut ExtendedFloat, data: i64) {
+    *out = soft::from_i64(data);
+}
+#[cfg(feature = "soft-float")]
+#[inline]
+pub unsafe fn extended_convert_from_u64(out: *mut ExtendedFloat, data: u64) {
+    *out = soft::from_u64(data);
+}
+#[cfg(feature = "soft-float")]
+#[inline]
+pub unsafe fn extended_convert_into_f64(first: *const ExtendedFloat) -> f64 {
+    soft::to_f64(*first)
+}
+#[cfg(feature = "soft-float")]
+#[inline]
+pub unsafe fn extended_convert_into_f32(first: *const ExtendedFloat) -> f32 {
+    soft::to_f32(*first)
+}
+#[cfg(feature = "soft-float")]
+#[inline]
+pub unsafe fn extended_convert_into_i64(first: *const ExtendedFloat) -> i64 {
+    soft::to_i64(*first)
+}
+#[cfg(feature = "soft-float")]
+#[inline]
+pub unsafe fn extended_convert_into_u64(first: *const ExtendedFloat) -> u64 {
+    soft::to_u64(*first)
+}
@@ -0,0 +1,834 @@
+//! The transcendental functions in pure Rust, for when there's no C library to call.
+//!
+//! Unlike the basic arithmetic in `soft`, these aren't bit-identical to the C library
+//! (which isn't correctly rounded either).
+//! Instead everything is computed with 128 bits of precision and only rounded once at the end,
+//! so the results are almost always correctly rounded.
+//! The special cases follow C99, just like the C library does.
+use core::cmp::Ordering;
+use core::ops::{Add, Div, Mul, Neg, Sub};
+
+use ExtendedFloat;
+use soft::{self, decode, infinity, operands, quiet, zero, Value, INDEFINITE};
+
+/// An intermediate result with 128 bits of precision and a practically unlimited exponent range.
+///
+/// The value is `significand * 2^exponent`, where the top bit of the significand is set unless it's zero.
+/// Every operation truncates instead of rounding, which is still far more precise than the final result needs.
+#[derive(Copy, Clone, Debug)]
+struct Wide {
+    negative: bool,
+    exponent: i32,
+    significand: u128,
+}
+
+const ZERO: Wide = Wide { negative: false, exponent: 0, significand: 0 };
+const ONE: Wide = Wide { negative: false, exponent: -127, significand: 1 << 127 };
+const TWO: Wide = Wide { negative: false, exponent: -126, significand: 1 << 127 };
+const LN_2: Wide = Wide { negative: false, exponent: -128, significand: 0xb17217f7d1cf79abc9e3b39803f2f6af };
+const LOG2_E: Wide = Wide { negative: false, exponent: -127, significand: 0xb8aa3b295c17f0bbbe87fed0691d3e89 };
+const LOG10_E: Wide = Wide { negative: false, exponent: -129, significand: 0xde5bd8a937287195355baaafad33dc32 };
+const FRAC_PI_2: Wide = Wide { negative: false, exponent: -127, significand: 0xc90fdaa22168c234c4c6628b80dc1cd1 };
+const PI: Wide = Wide { negative: false, exponent: -126, significand: 0xc90fdaa22168c234c4c6628b80dc1cd1 };
+const SQRT_2: Wide = Wide { negative: false, exponent: -127, significand: 0xb504f333f9de6484597d89b3754abe9f };
+
+impl Wide {
+    fn new(negative: bool, exponent: i32, significand: u128) -> Wide {
+        if significand == 0 {
+            return Wide { negative, exponent: 0, significand: 0 }
+        }
+        let shift = significand.leading_zeros();
+        Wide { negative, exponent: exponent - shift as i32, significand: significand << shift }
+    }
+    fn from_int(value: i64) -> Wide {
+        Wide::new(value < 0, 0, (value as i128).abs() as u128)
+    }
+    /// Converts a finite value exactly
+    fn from_extended(value: ExtendedFloat) -> Wide {
+        match decode(value) {
+            (negative, Value::Zero) => Wide { negative, ..ZERO },
+            (negative, Value::Finite { exponent, significand }) => Wide::new(negative, exponent, significand as u128),
+            (_, value) => unreachable!("Unexpected value: {:?}", value)
+        }
+    }
+    /// Rounds to the nearest extended precision value, overflowing to infinity and underflowing to zero
+    fn round(self) -> ExtendedFloat {
+        if self.is_zero() {
+            zero(self.negative)
+        } else {
+            soft::round(self.negative, self.exponent, self.significand, false)
+        }
+    }
+    #[inline]
+    fn is_zero(self) -> bool {
+        self.significand == 0
+    }
+    fn abs(self) -> Wide {
+        Wide { negative: false, ..self }
+    }
+    /// Multiplies by `2^power`, which is exact
+    fn scale(self, power: i32) -> Wide {
+        if self.is_zero() { self } else { Wide { exponent: self.exponent + power, ..self } }
+    }
+    /// The exponent of the leading bit, so the magnitude is in `[2^n, 2^(n + 1))`
+    fn magnitude(self) -> i32 {
+        debug_assert!(!self.is_zero());
+        self.exponent + 127
+    }
+    /// Orders the magnitudes, putting zero below everything else
+    fn key(self) -> (i32, u128) {
+        if self.is_zero() { (i32::min_value(), 0) } else { (self.exponent, self.significand) }
+    }
+    fn sqrt(self) -> Wide {
+        debug_assert!(!self.negative || self.is_zero());
+        if self.is_zero() {
+            return self
+        }
+        // Make the exponent even, so the estimate's exponent is exact
+        let (exponent, significand) = if self.exponent & 1 == 0 {
+            (self.exponent, self.significand)
+        } else {
+            (self.exponent + 1, self.significand >> 1)
+        };
+        let estimate = Wide::new(false, exponent / 2, soft::integer_sqrt(significand));
+        // The estimate has 64 correct bits, so a single Newton step gives all of them
+        (estimate + self / estimate).scale(-1)
+    }
+    /// Rounds to the nearest integer (with ties going away from zero), which has to be less than `2^62`
+    fn nearest(self) -> i64 {
+        if self.is_zero() || self.magnitude() < -1 {
+            return 0
+        }
+        debug_assert!(self.magnitude() < 62);
+        let fraction_bits = (-self.exponent) as u32;
+        let integer = if fraction_bits >= 128 { 0 } else { (self.significand >> fraction_bits) as i64 };
+        let half = ((self.significand >> (fraction_bits - 1)) & 1) as i64;
+        if self.negative { -(integer + half) } else { integer + half }
+    }
+}
+impl Neg for Wide {
+    type Output = Wide;
+    fn neg(self) -> Wide {
+        Wide { negative: !self.negative, ..self }
+    }
+}
+impl Add for Wide {
+    type Output = Wide;
+    fn add(self, other: Wide) -> Wide {
+        if other.is_zero() {
+            return self
+        } else if self.is_zero() {
+            return other
+        }
+        // Order by magnitude, so the difference is never negative
+        let (large, small) = if self.key() >= other.key() { (self, other) } else { (other, self) };
+        let shift = (large.exponent - small.exponent) as u32;
+        let aligned = if shift >= 128 { 0 } else { small.significand >> shift };
+        if large.negative == small.negative {
+            match large.significand.overflowing_add(aligned) {
+                (sum, false) => Wide { significand: sum, ..large },
+                (sum, true) => Wide {
+                    negative: large.negative,
+                    exponent: large.exponent + 1,
+                    significand: (sum >> 1) | (1 << 127)
+                }
+            }
+        } else {
+            Wide::new(large.negative, large.exponent, large.significand - aligned)
+        }
+    }
+}
+impl Sub for Wide {
+    type Output = Wide;
+    fn sub(self, other: Wide) -> Wide {
+        self + -other
+    }
+}
+impl Mul for Wide {
+    type Output = Wide;
+    fn mul(self, other: Wide) -> Wide {
+        let negative = self.negative != other.negative;
+        if self.is_zero() || other.is_zero() {
+            return Wide { negative, ..ZERO }
+        }
+        // The top half of the full 256-bit product, a 64-bit limb at a time
+        let mask = (1u128 << 64) - 1;
+        let (first_high, first_low) = (self.significand >> 64, self.significand & mask);
+        let (second_high, second_low) = (other.significand >> 64, other.significand & mask);
+        let (middle, first_carry) = (first_high * second_low).overflowing_add(first_low * second_high);
+        let (middle, second_carry) = middle.overflowing_add((first_low * second_low) >> 64);
+        let carries = (first_carry as u128 + second_carry as u128) << 64;
+        let high = first_high * second_high + (middle >> 64) + carries;
+        Wide::new(negative, self.exponent + other.exponent + 128, high)
+    }
+}
+impl Div for Wide {
+    type Output = Wide;
+    fn div(self, other: Wide) -> Wide {
+        debug_assert!(!other.is_zero());
+        let negative = self.negative != other.negative;
+        if self.is_zero() {
+            return Wide { negative, ..ZERO }
+        }
+        // Long division a bit at a time, where the first quotient bit is always set
+        let divisor = other.significand;
+        let (mut remainder, exponent) = if self.significand >= divisor {
+            (self.significand - divisor, self.exponent - other.exponent - 127)
+        } else {
+            // This is `2a - b`, which fits even though `2a` doesn't
+            ((self.significand << 1).wrapping_sub(divisor), self.exponent - other.exponent - 128)
+        };
+        let mut quotient = 1u128;
+        for _ in 0..127 {
+            // The remainder can briefly need a 129th bit
+            let carry = remainder >> 127 != 0;
+            remainder <<= 1;
+            quotient <<= 1;
+            if carry || remainder >= divisor {
+                remainder = remainder.wrapping_sub(divisor);
+                quotient |= 1;
+            }
+        }
+        Wide::new(negative, exponent, quotient)
+    }
+}
+
+/// The number of times `expm1_reduced` halves its argument
+const SQUARINGS: i32 = 8;
+
+/// Computes `e^x - 1` for `|x| <= ln(2)/2`, keeping all of its relative precision even when `x` is tiny
+fn expm1_reduced(x: Wide) -> Wide {
+    // After shrinking the argument to less than 2^-9, twelve terms of the Taylor series are plenty
+    let small = x.scale(-SQUARINGS);
+    let mut series = ONE;
+    for n in (2..13).rev() {
+        series = ONE + small * series / Wide::from_int(n);
+    }
+    // Then undo the shrinking with `e^2x - 1 = (e^x - 1)(e^x + 1)`
+    let mut result = small * series;
+    for _ in 0..SQUARINGS {
+        result = result * (result + TWO);
+    }
+    result
+}
+
+/// Computes `2^x` for `|x| < 2^20`
+fn exp2_wide(x: Wide) -> Wide {
+    let integer = x.nearest();
+    let fraction = x - Wide::from_int(integer);
+    (ONE + expm1_reduced(fraction * LN_2)).scale(integer as i32)
+}
+
+/// Splits `e^x` into `2^k * (1 + m)` for `|x| < 2^20`, where `m` is small
+fn exp_parts(x: Wide) -> (i32, Wide) {
+    let integer = (x * LOG2_E).nearest();
+    (integer as i32, expm1_reduced(x - Wide::from_int(integer) * LN_2))
+}
+
+fn exp_wide(x: Wide) -> Wide {
+    let (power, fraction) = exp_parts(x);
+    (ONE + fraction).scale(power)
+}
+
+fn expm1_wide(x: Wide) -> Wide {
+    match exp_parts(x) {
+        (0, fraction) => fraction,
+        (power, fraction) => (ONE + fraction).scale(power) - ONE
+    }
+}
+
+/// Splits `ln(x)` for a positive `x` into `n * ln(2) + ln(m)`, giving `n` and `ln(m)`
+fn ln_parts(x: Wide) -> (i32, Wide) {
+    debug_assert!(!x.negative && !x.is_zero());
+    // Take the mantissa in `[sqrt(1/2), sqrt(2))`, so its logarithm is as small as possible
+    let mut power = x.magnitude();
+    let mut mantissa = Wide { exponent: -127, ..x };
+    if mantissa.significand > SQRT_2.significand {
+        mantissa = mantissa.scale(-1);
+        power += 1;
+    }
+    // Then `ln(m) = 2 atanh(t)` where `t = (m - 1) / (m + 1)`, which converges quickly since `|t| < 0.172`
+    let t = (mantissa - ONE) / (mantissa + ONE);
+    let square = t * t;
+    let mut series = ZERO;
+    for n in (0..27).rev() {
+        series = ONE / Wide::from_int(2 * n + 1) + square * series;
+    }
+    (power, (t * series).scale(1))
+}
+
+fn ln_wide(x: Wide) -> Wide {
+    let (power, ln) = ln_parts(x);
+    Wide::from_int(power as i64) * LN_2 + ln
+}
+
+fn log2_wide(x: Wide) -> Wide {
+    let (power, ln) = ln_parts(x);
+    Wide::from_int(power as i64) + ln * LOG2_E
+}
+
+/// Computes `ln(1 + x)` for `x > -1`, keeping all of its relative precision even when `x` is tiny
+fn ln1p_wide(x: Wide) -> Wide {
+    if x.is_zero() || x.magnitude() >= -20 {
+        return ln_wide(ONE + x)
+    }
+    // Otherwise `1 + x` would lose the low bits, but then a few terms of the Taylor series are enough
+    let mut series = ZERO;
+    for n in (0..8).rev() {
+        series = ONE / Wide::from_int(n + 1) - x * series;
+    }
+    x * series
+}
+
+/// Computes `sin(x)` for `|x| <= pi/4`
+fn sin_reduced(x: Wide) -> Wide {
+    let square = x * x;
+    let mut series = ONE;
+    for n in (1..19).rev() {
+        series = ONE - square * series / Wide::from_int(2 * n * (2 * n + 1));
+    }
+    x * series
+}
+
+/// Computes `cos(x)` for `|x| <= pi/4`
+fn cos_reduced(x: Wide) -> Wide {
+    let square = x * x;
+    let mut series = ONE;
+    for n in (1..19).rev() {
+        series = ONE - square * series / Wide::from_int((2 * n - 1) * (2 * n));
+    }
+    series
+}
+
+/// Reduces a finite value to `x - n * pi/2`, giving the quadrant `n` (modulo 4) and the remainder in `[-pi/4, pi/4]`.
+///
+/// This uses the Payne-Hanek algorithm, which multiplies the exact significand
+/// by just the bits of `2/pi` that matter for its exponent.
+fn rem_pio2(value: ExtendedFloat) -> (u32, Wide) {
+    let (negative, exponent, significand) = match decode(value) {
+        (_, Value::Zero) => return (0, Wide::from_extended(value)),
+        (negative, Value::Finite { exponent, significand }) => (negative, exponent, significand),
+        (_, value) => unreachable!("Unexpected value: {:?}", value)
+    };
+    if exponent + 63 < 0 {
+        // Anything below one is already less than pi/4 away from zero
+        let x = Wide::from_extended(value);
+        if x.abs().key() <= FRAC_PI_2.scale(-1).key() {
+            return (0, x)
+        }
+    }
+    /*
+     * We want the fractional part of `x * 2/pi` along with its integer part modulo 4.
+     * Each word `k` of the table contributes `significand * word * 2^(exponent - 64k)`,
+     * which is a multiple of four whenever `exponent - 64k >= 2`, so we start at the first word that isn't.
+     * The next four words then give more than 190 bits of fraction,
+     * which is plenty even with the worst case cancellation of a 64-bit significand.
+     */
+    let first_word = if exponent >= 2 { (exponent - 2) / 64 + 1 } else { -((1 - exponent) / 64) };
+    let mut product = [0u64; 6];
+    let mut carry = 0u128;
+    for (limb, offset) in product.iter_mut().zip((0..5).rev()) {
+        let partial = significand as u128 * two_over_pi_word(first_word + offset) as u128 + carry;
+        *limb = partial as u64;
+        carry = partial >> 64;
+    }
+    product[5] = carry as u64;
+    // The product is scaled by `2^(exponent - 64 * (first_word + 4))`, so that's where the binary point is
+    let point = (64 * (first_word + 4) - exponent) as usize;
+    debug_assert!(point >= 254 && point <= 318, "Invalid binary point {}", point);
+    let bits = |start: usize| -> u64 {
+        let (index, shift) = (start / 64, start % 64);
+        let low = product[index] >> shift;
+        if shift == 0 || index + 1 >= product.len() { low } else { low | (product[index + 1] << (64 - shift)) }
+    };
+    let mut quadrant = bits(point) as u32 & 3;
+    let mut high = (bits(point - 64) as u128) << 64 | bits(point - 128) as u128;
+    let mut low = bits(point - 192);
+    // Round to the nearest quadrant, so the fraction ends up in `[-1/2, 1/2)`
+    let round_up = high >> 127 != 0;
+    if round_up {
+        quadrant += 1;
+        let (negated, borrow) = 0u64.overflowing_sub(low);
+        low = negated;
+        high = (!high).wrapping_add(!borrow as u128);
+    }
+    // Normalize the 192-bit fraction into 128 bits
+    let fraction = if high != 0 {
+        let shift = high.leading_zeros();
+        let rest = if shift <= 64 { (low as u128) << shift >> 64 } else { (low as u128) << (shift - 64) };
+        let top = (high << shift) | rest;
+        Wide::new(round_up, -128 - shift as i32, top)
+    } else {
+        Wide::new(round_up, -192, low as u128)
+    };
+    let reduced = fraction * FRAC_PI_2;
+    if negative {
+        (quadrant.wrapping_neg() & 3, -reduced)
+    } else {
+        (quadrant & 3, reduced)
+    }
+}
+
+fn two_over_pi_word(index: i32) -> u64 {
+    // Word `k` holds the bits `2^(-64k)` through `2^(-64k - 63)`, so anything before that is zero
+    if index < 1 { 0 } else { TWO_OVER_PI[(index - 1) as usize] }
+}
+
+/// Computes `atan(x)` for `x >= 0`
+fn atan_wide(x: Wide) -> Wide {
+    if x.is_zero() {
+        return x
+    }
+    // Use `atan(x) = pi/2 - atan(1/x)` so the argument is at most one
+    let complement = x.key() > ONE.key();
+    let mut reduced = if complement { ONE / x } else { x };
+    // Halve the angle with `tan(a/2) = tan(a) / (1 + sqrt(1 + tan(a)^2))` until the series converges quickly
+    for _ in 0..3 {
+        reduced = reduced / (ONE + (ONE + reduced * reduced).sqrt());
+    }
+    let square = reduced * reduced;
+    let mut series = ZERO;
+    for n in (0..20).rev() {
+        series = ONE / Wide::from_int(2 * n + 1) - square * series;
+    }
+    let result = (reduced * series).scale(3);
+    if complement { FRAC_PI_2 - result } else { result }
+}
+
+/// The value of a function at infinity, or `None` to compute it from the magnitude
+type Special = Option<ExtendedFloat>;
+
+/// Handles NaN and the unsupported encodings, giving back the sign and magnitude of everything else
+fn unary(value: ExtendedFloat) -> Result<(bool, Value), ExtendedFloat> {
+    match decode(value) {
+        (_, Value::Unsupported) => Err(INDEFINITE),
+        (_, Value::Nan) => Err(quiet(value)),
+        decoded => Ok(decoded)
+    }
+}
+
+/// Applies a function that's odd, like `sin`, which always keeps the sign of zero.
+///
+/// The function gets the magnitude, which is finite, and the sign gets applied to its result.
+fn odd<F: Fn(Wide) -> Wide>(value: ExtendedFloat, at_infinity: Special, function: F) -> ExtendedFloat {
+    match unary(value) {
+        Err(nan) => nan,
+        Ok((_, Value::Zero)) => value,
+        Ok((negative, Value::Infinity)) => at_infinity.map_or(INDEFINITE, |result| {
+            if negative { soft::neg(result) } else { result }
+        }),
+        Ok((negative, _)) => {
+            let result = function(Wide::from_extended(value).abs());
+            if negative { (-result).round() } else { result.round() }
+        }
+    }
+}
+
+/// Compares the magnitude with one
+fn compare_one(value: Value) -> Ordering {
+    match value {
+        Value::Zero => Ordering::Less,
+        Value::Finite { exponent, significand } => (exponent + 63).cmp(&0).then(significand.cmp(&(1 << 63))),
+        _ => Ordering::Greater
+    }
+}
+
+/// Checks whether the finite magnitude is at least `2^power`
+fn at_least(value: Value, power: i32) -> bool {
+    match value {
+        Value::Finite { exponent, .. } => exponent + 63 >= power,
+        _ => false
+    }
+}
+
+pub fn exp(value: ExtendedFloat) -> ExtendedFloat {
+    match unary(value) {
+        Err(nan) => nan,
+        Ok((negative, Value::Infinity)) => if negative { zero(false) } else { value },
+        // Far past where the result overflows or underflows
+        Ok((negative, magnitude)) if at_least(magnitude, 15) => if negative { zero(false) } else { infinity(false) },
+        Ok(_) => exp_wide(Wide::from_extended(value)).round()
+    }
+}
+
+pub fn exp2(value: ExtendedFloat) -> ExtendedFloat {
+    match unary(value) {
+        Err(nan) => nan,
+        Ok((negative, Value::Infinity)) => if negative { zero(false) } else { value },
+        Ok((negative, magnitude)) if at_least(magnitude, 15) => if negative { zero(false) } else { infinity(false) },
+        Ok(_) => exp2_wide(Wide::from_extended(value)).round()
+    }
+}
+
+pub fn exp_m1(value: ExtendedFloat) -> ExtendedFloat {
+    match unary(value) {
+        Err(nan) => nan,
+        Ok((_, Value::Zero)) => value,
+        Ok((negative, Value::Infinity)) => if negative { soft::from_i64(-1) } else { value },
+        Ok((negative, magnitude)) if at_least(magnitude, 15) => {
+            if negative { soft::from_i64(-1) } else { infinity(false) }
+        },
+        Ok(_) => expm1_wide(Wide::from_extended(value)).round()
+    }
+}
+
+/// Handles the special cases of the logarithms, giving the finite positive argument for the rest
+fn logarithm(value: ExtendedFloat) -> Result<Wide, ExtendedFloat> {
+    match unary(value)? {
+        (_, Value::Zero) => Err(infinity(true)),
+        (true, _) => Err(INDEFINITE),
+        (false, Value::Infinity) => Err(value),
+        _ => Ok(Wide::from_extended(value))
+    }
+}
+
+pub fn ln(value: ExtendedFloat) -> ExtendedFloat {
+    logarithm(value).map_or_else(|result| result, |x| ln_wide(x).round())
+}
+
+pub fn log2(value: ExtendedFloat) -> ExtendedFloat {
+    logarithm(value).map_or_else(|result| result, |x| log2_wide(x).round())
+}
+
+pub fn log10(value: ExtendedFloat) -> ExtendedFloat {
+    logarithm(value).map_or_else(|result| result, |x| (ln_wide(x) * LOG10_E).round())
+}
+
+pub fn ln_1p(value: ExtendedFloat) -> ExtendedFloat {
+    match unary(value) {
+        Err(nan) => nan,
+        Ok((_, Value::Zero)) => value,
+        Ok((false, Value::Infinity)) => value,
+        Ok((true, Value::Infinity)) => INDEFINITE,
+        Ok(_) => {
+            let x = Wide::from_extended(value);
+            match (x.negative, x.key().cmp(&ONE.key())) {
+                (true, Ordering::Equal) => infinity(true),
+                (true, Ordering::Greater) => INDEFINITE,
+                _ => ln1p_wide(x).round()
+            }
+        }
+    }
+}
+
+pub fn cbrt(value: ExtendedFloat) -> ExtendedFloat {
+    odd(value, Some(infinity(false)), |x| exp2_wide(log2_wide(x) / Wide::from_int(3)))
+}
+
+/// Checks whether a finite value is an integer, giving whether it's odd
+fn parity(value: Value) -> Option<bool> {
+    match value {
+        Value::Zero => Some(false),
+        Value::Finite { exponent, significand } => {
+            if exponent > 0 {
+                Some(false)
+            } else if exponent == 0 {
+                Some(significand & 1 != 0)
+            } else if exponent <= -64 || significand & ((1 << -exponent) - 1) != 0 {
+                None
+            } else {
+                Some((significand >> -exponent) & 1 != 0)
+            }
+        },
+        _ => None
+    }
+}
+
+pub fn pow(first: ExtendedFloat, second: ExtendedFloat) -> ExtendedFloat {
+    let one = soft::from_u64(1);
+    // These hold even when the other operand is NaN
+    if soft::eq(first, one) || soft::eq(second, zero(false)) {
+        return one
+    }
+    let ((base_negative, base), (power_negative, power)) = match operands(first, second) {
+        Ok(operands) => operands,
+        Err(nan) => return nan
+    };
+    // Negative bases only keep their sign with odd integer powers
+    let negative = base_negative && parity(power) == Some(true);
+    match base {
+        Value::Finite { .. } => {},
+        Value::Zero => return if power_negative { infinity(negative) } else { zero(negative) },
+        Value::Infinity => return if power_negative { zero(negative) } else { infinity(negative) },
+        _ => unreachable!("Unexpected operand: {:?}", base)
+    }
+    if let Value::Infinity = power {
+        // Whether the result grows or shrinks depends on whether the base is inside the unit interval
+        return match (compare_one(base), power_negative) {
+            (Ordering::Equal, _) => one,
+            (Ordering::Less, false) | (Ordering::Greater, true) => zero(false),
+            _ => infinity(false)
+        }
+    }
+    if base_negative && parity(power).is_none() {
+        return INDEFINITE
+    }
+    let exponent = Wide::from_extended(second) * log2_wide(Wide::from_extended(first).abs());
+    let result = if exponent.is_zero() {
+        ONE
+    } else if exponent.magnitude() >= 15 {
+        // Far past where the result overflows or underflows
+        return if exponent.negative { zero(negative) } else { infinity(negative) }
+    } else {
+        exp2_wide(exponent)
+    };
+    Wide { negative, ..result }.round()
+}
+
+pub fn hypot(first: ExtendedFloat, second: ExtendedFloat) -> ExtendedFloat {
+    // An infinity wins even over NaN, since the result is infinite whatever the other value is
+    if soft::is_infinite(first) || soft::is_infinite(second) {
+        return infinity(false)
+    }
+    if let Err(nan) = operands(first, second) {
+        return nan
+    }
+    let first = Wide::from_extended(first);
+    let second = Wide::from_extended(second);
+    (first * first + second * second).sqrt().abs().round()
+}
+
+pub fn sin(value: ExtendedFloat) -> ExtendedFloat {
+    match unary(value) {
+        Err(nan) => nan,
+        Ok((_, Value::Zero)) => value,
+        Ok((_, Value::Infinity)) => INDEFINITE,
+        Ok(_) => match rem_pio2(value) {
+            (0, x) => sin_reduced(x),
+            (1, x) => cos_reduced(x),
+            (2, x) => -sin_reduced(x),
+            (_, x) => -cos_reduced(x),
+        }.round()
+    }
+}
+
+pub fn cos(value: ExtendedFloat) -> ExtendedFloat {
+    match unary(value) {
+        Err(nan) => nan,
+        Ok((_, Value::Infinity)) => INDEFINITE,
+        Ok(_) => match rem_pio2(value) {
+            (0, x) => cos_reduced(x),
+            (1, x) => -sin_reduced(x),
+            (2, x) => -cos_reduced(x),
+            (_, x) => sin_reduced(x),
+        }.round()
+    }
+}
+
+pub fn tan(value: ExtendedFloat) -> ExtendedFloat {
+    match unary(value) {
+        Err(nan) => nan,
+        Ok((_, Value::Zero)) => value,
+        Ok((_, Value::Infinity)) => INDEFINITE,
+        Ok(_) => {
+            let (quadrant, x) = rem_pio2(value);
+            if quadrant & 1 == 0 {
+                sin_reduced(x) / cos_reduced(x)
+            } else {
+                -cos_reduced(x) / sin_reduced(x)
+            }.round()
+        }
+    }
+}
+
+pub fn asin(value: ExtendedFloat) -> ExtendedFloat {
+    if compare_one(decode(value).1) == Ordering::Greater {
+        return unary(value).err().unwrap_or(INDEFINITE)
+    }
+    odd(value, None, |x| if x.key() == ONE.key() {
+        FRAC_PI_2
+    } else {
+        // Since `1 - x^2 = (1 - x)(1 + x)` is exact, this stays accurate near one
+        atan_wide(x / ((ONE - x) * (ONE + x)).sqrt())
+    })
+}
+
+pub fn acos(value: ExtendedFloat) -> ExtendedFloat {
+    match unary(value) {
+        Err(nan) => nan,
+        Ok((_, magnitude)) if compare_one(magnitude) == Ordering::Greater => INDEFINITE,
+        Ok((true, magnitude)) if compare_one(magnitude) == Ordering::Equal => PI.round(),
+        // `acos(x) = 2 atan(sqrt((1 - x) / (1 + x)))`, which doesn't lose anything near one
+        Ok(_) => {
+            let x = Wide::from_extended(value);
+            atan_wide(((ONE - x) / (ONE + x)).sqrt()).scale(1).round()
+        }
+    }
+}
+
+pub fn atan(value: ExtendedFloat) -> ExtendedFloat {
+    odd(value, Some(FRAC_PI_2.round()), atan_wide)
+}
+
+pub fn atan2(first: ExtendedFloat, second: ExtendedFloat) -> ExtendedFloat {
+    let ((negative, y), (x_negative, x)) = match operands(first, second) {
+        Ok(operands) => operands,
+        Err(nan) => return nan
+    };
+    let result = match (y, x) {
+        // Even a zero on the left gives pi when it's negative
+        (Value::Zero, _) | (Value::Finite { .. }, Value::Infinity) => if x_negative { PI } else { ZERO },
+        (Value::Infinity, Value::Infinity) => {
+            let quarter = FRAC_PI_2.scale(-1);
+            if x_negative { PI - quarter } else { quarter }
+        },
+        (Value::Infinity, _) | (_, Value::Zero) => FRAC_PI_2,
+        _ => {
+            let angle = atan_wide(Wide::from_extended(first).abs() / Wide::from_extended(second).abs());
+            if x_negative { PI - angle } else { angle }
+        }
+    };
+    Wide { negative, ..result }.round()
+}
+
+/// Rounds to infinity, for results that are far past overflowing
+const HUGE: Wide = Wide { negative: false, exponent: 1 << 20, significand: 1 << 127 };
+
+pub fn sinh(value: ExtendedFloat) -> ExtendedFloat {
+    odd(value, Some(infinity(false)), |x| if x.magnitude() >= 15 {
+        HUGE
+    } else {
+        // `sinh(x) = (m + m / (m + 1)) / 2` where `m = e^x - 1`, which keeps its precision for tiny values
+        let m = expm1_wide(x);
+        (m + m / (m + ONE)).scale(-1)
+    })
+}
+
+pub fn cosh(value: ExtendedFloat) -> ExtendedFloat {
+    match unary(value) {
+        Err(nan) => nan,
+        Ok((_, Value::Infinity)) => infinity(false),
+        Ok((_, magnitude)) if at_least(magnitude, 15) => infinity(false),
+        Ok(_) => {
+            let exponential = exp_wide(Wide::from_extended(value).abs());
+            (exponential + ONE / exponential).scale(-1).round()
+        }
+    }
+}
+
+pub fn tanh(value: ExtendedFloat) -> ExtendedFloat {
+    odd(value, Some(soft::from_u64(1)), |x| if x.magnitude() >= 6 {
+        // The difference from one is far too small to matter
+        ONE
+    } else {
+        // `tanh(x) = m / (m + 2)` where `m = e^2x - 1`
+        let m = expm1_wide(x.scale(1));
+        m / (m + TWO)
+    })
+}
+
+pub fn asinh(value: ExtendedFloat) -> ExtendedFloat {
+    odd(value, Some(infinity(false)), |x| {
+        // `asinh(x) = ln(1 + x + x^2 / (1 + sqrt(1 + x^2)))`, which keeps its precision for tiny values
+        let square = x * x;
+        ln1p_wide(x + square / (ONE + (ONE + square).sqrt()))
+    })
+}
+
+pub fn acosh(value: ExtendedFloat) -> ExtendedFloat {
+    match unary(value) {
+        Err(nan) => nan,
+        Ok((true, _)) => INDEFINITE,
+        Ok((false, magnitude)) => match compare_one(magnitude) {
+            Ordering::Less => INDEFINITE,
+            Ordering::Equal => zero(false),
+            Ordering::Greater if is_infinite(magnitude) => value,
+            // `acosh(x) = ln(1 + t + sqrt(t (t + 2)))` where `t = x - 1` is exact
+            Ordering::Greater => {
+                let t = Wide::from_extended(value) - ONE;
+                ln1p_wide(t + (t * (t + TWO)).sqrt()).round()
+            }
+        }
+    }
+}
+
+pub fn atanh(value: ExtendedFloat) -> ExtendedFloat {
+    match unary(value) {
+        Err(nan) => nan,
+        Ok((negative, magnitude)) => match compare_one(magnitude) {
+            Ordering::Greater => INDEFINITE,
+            Ordering::Equal => infinity(negative),
+            // `atanh(x) = ln(1 + 2x / (1 - x)) / 2`
+            Ordering::Less => odd(value, None, |x| ln1p_wide((x / (ONE - x)).scale(1)).scale(-1))
+        }
+    }
+}
+
+fn is_infinite(value: Value) -> bool {
+    match value {
+        Value::Infinity => true,
+        _ => false
+    }
+}
+
+/// The binary expansion of `2/pi`, 64 bits at a time.
+///
+/// The reduction only needs about 320 bits starting around the argument's exponent,
+/// so this covers the whole exponent range (16383 bits plus some slack).
+static TWO_OVER_PI: [u64; 260] = [
+    0xA2F9836E4E441529, 0xFC2757D1F534DDC0, 0xDB6295993C439041, 0xFE5163ABDEBBC561,
+    0xB7246E3A424DD2E0, 0x06492EEA09D1921C, 0xFE1DEB1CB129A73E, 0xE88235F52EBB4484,
+    0xE99C7026B45F7E41, 0x3991D639835339F4, 0x9C845F8BBDF9283B, 0x1FF897FFDE05980F,
+    0xEF2F118B5A0A6D1F, 0x6D367ECF27CB09B7, 0x4F463F669E5FEA2D, 0x7527BAC7EBE5F17B,
+    0x3D0739F78A5292EA, 0x6BFB5FB11F8D5D08, 0x56033046FC7B6BAB, 0xF0CFBC209AF4361D,
+    0xA9E391615EE61B08, 0x6599855F14A06840, 0x8DFFD8804D732731, 0x06061556CA73A8C9,
+    0x60E27BC08C6B47C4, 0x19C367CDDCE8092A, 0x8359C4768B961CA6, 0xDDAF44D15719053E,
+    0xA5FF07053F7E33E8, 0x32C2DE4F98327DBB, 0xC33D26EF6B1E5EF8, 0x9F3A1F35CAF27F1D,
+    0x87F121907C7C246A, 0xFA6ED5772D30433B, 0x15C614B59D19C3C2, 0xC4AD414D2C5D000C,
+    0x467D862D71E39AC6, 0x9B0062337CD2B497, 0xA7B4D55537F63ED7, 0x1810A3FC764D2A9D,
+    0x64ABD770F87C6357, 0xB07AE715175649C0, 0xD9D63B3884A7CB23, 0x24778AD623545AB9,
+    0x1F001B0AF1DFCE19, 0xFF319F6A1E666157, 0x9947FBACD87F7EB7, 0x652289E83260BFE6,
+    0xCDC4EF09366CD43F, 0x5DD7DE16DE3B5892, 0x9BDE2822D2E88628, 0x4D58E232CAC616E3,
+    0x08CB7DE050C017A7, 0x1DF35BE01834132E, 0x6212830148835B8E, 0xF57FB0ADF2E91E43,
+    0x4A48D36710D8DDAA, 0x425FAECE616AA428, 0x0AB499D3F2A6067F, 0x775C83C2A3883C61,
+    0x78738A5A8CAFBDD7, 0x6F63A62DCBBFF4EF, 0x818D67C12645CA55, 0x36D9CAD2A8288D61,
+    0xC277C9121426049B, 0x4612C459C444C5C8, 0x91B24DF31700AD43, 0xD4E5492910D5FDFC,
+    0xBE00CC941EEECE70, 0xF53E1380F1ECC3E7, 0xB328F8C79405933E, 0x71C1B3092EF3450B,
+    0x9C12887B20AB9FB5, 0x2EC292472F327B6D, 0x550C90A7721FE76B, 0x96CB314A1679E279,
+    0x4189DFF49794E884, 0xE6E29731996BED88, 0x365F5F0EFDBBB49A, 0x486CA46742727132,
+    0x5D8DB8159F09E5BC, 0x25318D3974F71C05, 0x30010C0D68084B58, 0xEE2C90AA4702E774,
+    0x24D6BDA67DF77248, 0x6EEF169FA6948EF6, 0x91B45153D1F20ACF, 0x3398207E4BF56863,
+    0xB25F3EDD035D407F, 0x8985295255C06437, 0x10D86D324832754C, 0x5BD4714E6E5445C1,
+    0x090B69F52AD56614, 0x9D072750045DDB3B, 0xB4C576EA17F9877D, 0x6B49BA271D296996,
+    0xACCCC65414AD6AE2, 0x9089D98850722CBE, 0xA4049407777030F3, 0x27FC00A871EA49C2,
+    0x663DE06483DD9797, 0x3FA3FD94438C860D, 0xDE41319D39928C70, 0xDDE7B7173BDF082B,
+    0x3715A0805C93805A, 0x921110D8E80FAF80, 0x6C4BFFDB0F903876, 0x185915A562BBCB61,
+    0xB989C7BD401004F2, 0xD2277549F6B6EBBB, 0x22DBAA140A2F2689, 0x768364333B091A94,
+    0x0EAA3A51C2A31DAE, 0xEDAF12265C4DC26D, 0x9C7A2D9756C0833F, 0x03F6F0098C402B99,
+    0x316D07B43915200C, 0x5BC3D8C492F54BAD, 0xC6A5CA4ECD37A736, 0xA9E69492AB6842DD,
+    0xDE6319EF8C76528B, 0x6837DBFCABA1AE31, 0x15DFA1AE00DAFB0C, 0x664D64B705ED3065,
+    0x29BF56573AFF47B9, 0xF96AF3BE75DF9328, 0x3080ABF68C6615CB, 0x040622FA1DE4D9A4,
+    0xB33D8F1B5709CD36, 0xE9424EA4BE13B523, 0x331AAAF0A8654FA5, 0xC1D20F3F0BCD785B,
+    0x76F923048B7B7217, 0x8953A6C6E26E6F00, 0xEBEF584A9BB7DAC4, 0xBA66AACFCF761D02,
+    0xD12DF1B1C1998C77, 0xADC3DA4886A05DF7, 0xF480C62FF0AC9AEC, 0xDDBC5C3F6DDED01F,
+    0xC790B6DB2A3A25A3, 0x9AAF009353AD0457, 0xB6B42D297E804BA7, 0x07DA0EAA76A1597B,
+    0x2A12162DB7DCFDE5, 0xFAFEDB89FDBE896C, 0x76E4FCA90670803E, 0x156E85FF87FD073E,
+    0x2833676186182AEA, 0xBD4DAFE7B36E6D8F, 0x3967955BBF3148D7, 0x8416DF30432DC735,
+    0x6125CE70C9B8CB30, 0xFD6CBFA200A4E46C, 0x05A0DD5A476F21D2, 0x1262845CB9496170,
+    0xE0566B0152993755, 0x50B7D51EC4F1335F, 0x6E13E4305DA92E85, 0xC3B21D3632A1A4B7,
+    0x08D4B1EA21F716E4, 0x698F77FF2780030C, 0x2D408DA0CD4F99A5, 0x20D3A2B30A5D2F42,
+    0xF9B4CBDA11D0BE7D, 0xC1DB9BBD17AB81A2, 0xCA5C6A0817552E55, 0x0027F0147F8607E1,
+    0x640B148D4196DEBE, 0x872AFDDAB6256B34, 0x897BFEF3059EBFB9, 0x4F6A68A82A4A5AC4,
+    0x4FBCF82D985AD795, 0xC7F48D4D0DA63A20, 0x5F57A4B13F149538, 0x800120CC86DD71B6,
+    0xDEC9F560BF11654D, 0x6B0701ACB08CD0C0, 0xB24855510EFB1EC3, 0x72953B06A33540C0,
+    0x7BDC06CC45E0FA29, 0x4EC8CAD641F3E8DE, 0x647CD8649B31BED9, 0xC397A4D45877C5E3,
+    0x6913DAF03C3ABA46, 0x18465F7555F5BDD2, 0xC6926E5D2EACED44, 0x0E423E1C87C461E9,
+    0xFD29F3D6E7CA7C22, 0x35916FC5E0088DD7, 0xFFE26A6EC6FDB0C1, 0x0893745D7CB2AD6B,
+    0x9D6ECD7B723E6A11, 0xC6A9CFF7DF7329BA, 0xC9B55100B70DB2E2, 0x24BA74607DE58AD8,
+    0x742C150D0C188194, 0x667E162901767A9F, 0xBEFDFDEF4556367E, 0xD913D9ECB9BA8BFC,
+    0x97C427A831C36EF1, 0x36C59456A8D8B5A8, 0xB40ECCCF2D891234, 0x576F89562CE3CE99,
+    0xB920D6AA5E6B9C2A, 0x3ECC5F114A0BFDFB, 0xF4E16D3B8E2C86E2, 0x84D4E9A9B4FCD1EE,
+    0xEFC9352E61392F44, 0x2138C8D91B0AFC81, 0x6A4AFBD81C2F84B4, 0x538C994ECC2254DC,
+    0x552AD6C6C096190B, 0xB8701A649569605A, 0x26EE523F0F117F11, 0xB5F4F5CBFC2DBC34,
+    0xEEBC34CC5DE8605E, 0xDD9B8E67EF3392B8, 0x17C99B5861BC57E1, 0xC68351103ED84871,
+    0xDDDD1C2DA118AF46, 0x2C21D7F359987AD9, 0xC0549EFA864FFC06, 0x56AE79E536228922,
+    0xAD38DC9367AAE855, 0x3826829BE7CAA40D, 0x51B133990ED7A948, 0x0569F0B265A7887F,
+    0x974C8836D1F9B392, 0x214A827B21CF98DC, 0x9F405547DC3A74E1, 0x42EB67DF9DFE5FD4,
+    0x5EA4677B7AACBAA2, 0xF65523882B55BA41, 0x086E59862A218347, 0x39E6E389D49EE540,
+    0xFB49E956FFCA0F1C, 0x8A59C52BFA94C5C1, 0xD3CFC50FAE5ADB86, 0xC5476243853B8621,
+    0x94792C8761107B4C, 0x2A1A2C8012BF4390, 0x2688893C78E4C4A8, 0x7BDBE5C23AC4EAF4,
+    0x268A67F7BF920D2B, 0xA365B1933D0B7CBD, 0xDC51A463DD27DDE1, 0x6919949A9529A828,
+    0xCE68B4ED09209F44, 0xCA984E638270237C, 0x7E32B90F8EF5A7E7, 0x561408F1212A9DB5,
+    0x4D7E6F5119A5ABF9, 0xB5D6DF8261DD9602, 0x36169F3AC4A1A283, 0x6DED727A8D39A9B8,
+    0x825C326B5B2746ED, 0x34007700D255F4FC, 0x4D59018071E0E13F, 0x89B295F364A8F1AE,
+];
@@ -0,0 +1,305 @@
+//! Parsing strings into extended precision values, following the syntax of C's `strtold`.
+//!
+//! Decimals (and numbers in other radices) are converted exactly with big integers,
+//! so they're always correctly rounded.
+use core::cmp::Ordering;
+
+use ExtendedFloat;
+use big::{Big, chunk_size};
+use soft::{round, zero, infinity, nan, QUIET_BIT};
+
+/// The limits for converting numbers in each radix (starting from two),
+/// as the maximum number of significant digits and then the minimum and maximum magnitudes.
+///
+/// When the radix is even, every halfway point between two extended precision values
+/// has fewer significant digits than the maximum (decimals need at most 11,516),
+/// so the digits past it can only ever make the value slightly larger.
+/// Halfway points never terminate in odd radices, so there it's only exact for numbers that are shorter than that.
+///
+/// A number with a magnitude of `m` is at least `radix^(m - 1)` but less than `radix^m`.
+/// Numbers with larger magnitudes always overflow, since the largest value is just below `2^16384`,
+/// and ones with smaller magnitudes are no more than half of the smallest subnormal (`2^-16445`),
+/// so they always underflow to zero.
+/// Powers of two don't need these, since they're converted directly.
+const LIMITS: [(usize, i64, i64); 35] = [
+    (70, -16445, 16384), (6116, -10376, 10338), (8261, -8222, 8192), (9397, -7082, 7057), (10114, -6362, 6339),
+    (10616, -5858, 5837), (10991, -5481, 5462), (11284, -5188, 5169), (11520, -4950, 4933), (11716, -4753, 4737),
+    (11882, -4587, 4571), (12025, -4444, 4428), (12149, -4319, 4304), (12259, -4209, 4194), (12356, -4111, 4096),
+    (12444, -4023, 4009), (12523, -3943, 3930), (12595, -3871, 3857), (12661, -3805, 3791), (12722, -3744, 3731),
+    (12778, -3687, 3675), (12830, -3635, 3622), (12879, -3586, 3574), (12924, -3541, 3529), (12967, -3498, 3486),
+    (13006, -3458, 3446), (13044, -3421, 3409), (13080, -3385, 3373), (13113, -3351, 3339), (13145, -3319, 3308),
+    (13175, -3289, 3277), (13204, -3260, 3248), (13232, -3232, 3221), (13258, -3206, 3195), (13283, -3181, 3170),
+];
+/// Larger exponents are just as out of range, so they're clamped to avoid overflow
+const MAX_EXPONENT: i64 = 1 << 40;
+
+/// Parses the longest prefix of the text that's a valid number, like `strtold` (except it doesn't skip whitespace).
+///
+/// This gives the value and the number of bytes it used, which is zero if there isn't a number at all.
+/// Along with decimals, this accepts hexadecimal floats like `0x1.8p3`,
+/// infinities and NaNs (with an optional payload like `nan(0x123)`), all with an optional sign.
+pub fn parse(text: &[u8]) -> (ExtendedFloat, usize) {
+    let (negative, start) = match text.first() {
+        Some(&b'-') => (true, 1),
+        Some(&b'+') => (false, 1),
+        _ => (false, 0)
+    };
+    let rest = &text[start..];
+    let result = if let Some(length) = keyword(rest, b"infinity").or_else(|| keyword(rest, b"inf")) {
+        Some((infinity(negative), length))
+    } else if let Some(length) = keyword(rest, b"nan") {
+        Some(parse_nan(negative, rest, length))
+    } else if rest.len() >= 2 && rest[0] == b'0' && (rest[1] == b'x' || rest[1] == b'X') {
+        // Without any hex digits, this is just a zero followed by an `x`
+        parse_binary(negative, &rest[2..], 16).map(|(value, length)| (value, length + 2))
+            .or_else(|| parse_digits(negative, rest, 10))
+    } else {
+        parse_digits(negative, rest, 10)
+    };
+    match result {
+        Some((value, length)) => (value, start + length),
+        None => (zero(false), 0)
+    }
+}
+
+/// Parses the longest prefix of the text that's a valid number in the radix, which must be from 2 to 36.
+///
+/// This gives the value and the number of bytes it used, just like `parse`.
+/// There are no prefixes or special values, but the number can have a sign and a fractional part.
+/// Just like `num_traits`, only decimals can have an exponent (with `e`),
+/// and hexadecimal numbers can have a binary one (with `p`), since the letters are digits in larger radices.
+pub fn parse_radix(text: &[u8], radix: u32) -> (ExtendedFloat, usize) {
+    assert!(radix >= 2 && radix <= 36, "Invalid radix: {}", radix);
+    let (negative, start) = match text.first() {
+        Some(&b'-') => (true, 1),
+        Some(&b'+') => (false, 1),
+        _ => (false, 0)
+    };
+    let result = if radix.is_power_of_two() {
+        parse_binary(negative, &text[start..], radix)
+    } else {
+        parse_digits(negative, &text[start..], radix)
+    };
+    match result {
+        Some((value, length)) => (value, start + length),
+        None => (zero(false), 0)
+    }
+}
+
+/// Checks if the text starts with the keyword, ignoring case
+fn keyword(text: &[u8], keyword: &[u8]) -> Option<usize> {
+    if text.len() >= keyword.len() && text[..keyword.len()].eq_ignore_ascii_case(keyword) {
+        Some(keyword.len())
+    } else {
+        None
+    }
+}
+
+/// Parses the payload of a NaN, which is an integer in parentheses after the keyword.
+///
+/// Just like glibc, anything else in the parentheses is accepted but gives the default NaN.
+fn parse_nan(negative: bool, text: &[u8], length: usize) -> (ExtendedFloat, usize) {
+    if text.get(length) == Some(&b'(') {
+        let sequence = &text[length + 1..];
+        let end = sequence.iter()
+            .position(|&c| !(c.is_ascii_alphanumeric() || c == b'_'))
+            .unwrap_or(sequence.len());
+        if sequence.get(end) == Some(&b')') {
+            let payload = parse_integer(&sequence[..end]).unwrap_or(0);
+            return (nan(negative, payload & (QUIET_BIT - 1)), length + end + 2)
+        }
+    }
+    (nan(negative, 0), length)
+}
+
+/// Parses an entire integer like `strtoull` does with a base of zero,
+/// where a prefix of `0x` means hexadecimal and a leading zero means octal.
+///
+/// Overflow saturates, and an empty string counts as zero.
+fn parse_integer(text: &[u8]) -> Option<u64> {
+    let (radix, digits) = if text.len() > 2 && (text.starts_with(b"0x") || text.starts_with(b"0X")) {
+        (16, &text[2..])
+    } else if text.len() > 1 && text[0] == b'0' {
+        (8, &text[1..])
+    } else {
+        (10, text)
+    };
+    let mut value = 0u64;
+    for &c in digits {
+        let digit = (c as char).to_digit(radix)?;
+        value = value.saturating_mul(radix as u64).saturating_add(digit as u64);
+    }
+    Some(value)
+}
+
+/// Parses an exponent that starts with the marker, giving its value and length.
+///
+/// If there isn't a valid exponent this doesn't use anything, so `1e` is just one.
+fn parse_exponent(text: &[u8], marker: u8) -> (i64, usize) {
+    match text.first() {
+        Some(&c) if c.eq_ignore_ascii_case(&marker) => {},
+        _ => return (0, 0)
+    }
+    let (negative, start) = match text.get(1) {
+        Some(&b'-') => (true, 2),
+        Some(&b'+') => (false, 2),
+        _ => (false, 1)
+    };
+    let digits = text[start..].iter().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 {
+        return (0, 0)
+    }
+    let mut value = 0i64;
+    for &c in &text[start..start + digits] {
+        value = (value * 10 + (c - b'0') as i64).min(MAX_EXPONENT);
+    }
+    (if negative { -value } else { value }, start + digits)
+}
+
+/// Parses a number in a radix that's a power of two, like a hexadecimal float after the `0x`.
+///
+/// This converts exactly apart from the final rounding, since each digit is just a few more bits.
+fn parse_binary(negative: bool, text: &[u8], radix: u32) -> Option<(ExtendedFloat, usize)> {
+    let bits = radix.trailing_zeros();
+    let mut significand = 0u128;
+    let mut exponent = 0i64;
+    let mut sticky = false;
+    let mut any_digits = false;
+    let mut seen_point = false;
+    let mut index = 0;
+    loop {
+        match text.get(index) {
+            Some(&b'.') if !seen_point => seen_point = true,
+            Some(&c) if (c as char).is_digit(radix) => {
+                any_digits = true;
+                let digit = (c as char).to_digit(radix).unwrap();
+                if significand >> (128 - bits) == 0 {
+                    significand = (significand << bits) | digit as u128;
+                    if seen_point {
+                        exponent -= bits as i64;
+                    }
+                } else {
+                    // We already have far more bits than we need
+                    sticky |= digit != 0;
+                    if !seen_point {
+                        exponent += bits as i64;
+                    }
+                }
+            },
+            _ => break
+        }
+        index += 1;
+    }
+    if !any_digits {
+        return None
+    }
+    let (power, length) = if radix == 16 { parse_exponent(&text[index..], b'p') } else { (0, 0) };
+    // Anything past a million is way out of range anyway
+    let exponent = (exponent + power).max(-1_000_000).min(1_000_000) as i32;
+    Some((round(negative, exponent, significand, sticky), index + length))
+}
+
+/// Parses a decimal (or a number in another radix), which is converted exactly apart from the final rounding
+fn parse_digits(negative: bool, text: &[u8], radix: u32) -> Option<(ExtendedFloat, usize)> {
+    let (max_digits, _, _) = LIMITS[radix as usize - 2];
+    let mut digits = Big::new(0);
+    // The number of significant digits we're keeping
+    let mut count = 0;
+    // The power of the radix to multiply the kept digits by
+    let mut exponent = 0i64;
+    let mut sticky = false;
+    let mut any_digits = false;
+    let mut seen_point = false;
+    // Add the digits in chunks, which is much faster than one at a time
+    let (chunk_power, chunk_digits) = chunk_size(radix);
+    let mut chunk = 0u32;
+    let mut chunk_length = 0;
+    let mut index = 0;
+    loop {
+        match text.get(index) {
+            Some(&b'.') if !seen_point => seen_point = true,
+            Some(&c) if (c as char).is_digit(radix) => {
+                any_digits = true;
+                let digit = (c as char).to_digit(radix).unwrap();
+                if count == 0 && digit == 0 {
+                    // Leading zeros aren't significant, but they still move the point
+                    if seen_point {
+                        exponent -= 1;
+                    }
+                } else if count < max_digits {
+                    count += 1;
+                    chunk = chunk * radix + digit;
+                    chunk_length += 1;
+                    if chunk_length == chunk_digits {
+                        digits.mul_add(chunk_power, chunk);
+                        chunk = 0;
+                        chunk_length = 0;
+                    }
+                    if seen_point {
+                        exponent -= 1;
+                    }
+                } else {
+                    sticky |= digit != 0;
+                    if !seen_point {
+                        exponent += 1;
+                    }
+                }
+            },
+            _ => break
+        }
+        index += 1;
+    }
+    if !any_digits {
+        return None
+    }
+    digits.mul_add(radix.pow(chunk_length), chunk);
+    let (power, length) = if radix == 10 { parse_exponent(&text[index..], b'e') } else { (0, 0) };
+    let value = convert(negative, digits, count as i64, exponent + power, sticky, radix);
+    Some((value, index + length))
+}
+
+/// Converts `digits * radix^exponent` to the nearest extended precision value,
+/// where the exact value is slightly larger if the `sticky` flag is set.
+fn convert(negative: bool, mut digits: Big, count: i64, exponent: i64, sticky: bool, radix: u32) -> ExtendedFloat {
+    if digits.is_zero() {
+        return zero(negative)
+    }
+    let (_, min_magnitude, max_magnitude) = LIMITS[radix as usize - 2];
+    // The value is at least `radix^(magnitude - 1)` but less than `radix^magnitude`
+    let magnitude = count + exponent;
+    if magnitude > max_magnitude {
+        return infinity(negative)
+    } else if magnitude < min_magnitude {
+        return zero(negative)
+    }
+    if exponent >= 0 {
+        digits.mul_pow(radix, exponent as u32);
+        let (top, shift, rest) = digits.top_bits();
+        round(negative, shift as i32, top, sticky || rest)
+    } else {
+        /*
+         * Divide by the power of the radix with enough bits in the quotient to round correctly,
+         * which are the 64 bits of the result along with a couple more,
+         * and the remainder says whether it's exact.
+         */
+        let mut scale = Big::new(1);
+        scale.mul_pow(radix, (-exponent) as u32);
+        let shift = 66 + scale.bit_length() as i32 - digits.bit_length() as i32;
+        if shift > 0 {
+            digits.shl(shift as u32);
+        } else {
+            scale.shl((-shift) as u32);
+        }
+        // The quotient is now less than 2^67, so start from the top bit
+        scale.shl(66);
+        let mut quotient = 0u128;
+        for bit in (0..67).rev() {
+            if digits.compare(&scale) != Ordering::Less {
+                digits.sub_assign(&scale);
+                quotient |= 1 << bit;
+            }
+            scale.shr1();
+        }
+        round(negative, -shift, quotient, sticky || !digits.is_zero())
+    }
+}
@@ -0,0 +1,782 @@
+//! A pure Rust implementation of the x87 80-bit extended precision format.
+//!
+//! The results are bit-identical to the x87 in its default configuration
+//! (full 64-bit precision and rounding to nearest), right down to which NaN gets propagated.
+//! Unnormals, pseudo-infinities and pseudo-NaNs are unsupported encodings that the x87 refuses to operate on,
+//! so arithmetic turns them into the default NaN just like it does.
+use core::cmp::Ordering;
+use core::mem;
+
+use ExtendedFloat;
+use big::Big;
+pub use parse::{parse, parse_radix};
+pub use format::{shortest, exact, SHORTEST_DIGITS, MAX_DIGITS};
+
+pub(crate) const EXPONENT_BIAS: i32 = 16383;
+const MAX_EXPONENT: u16 = 0x7FFF;
+/// The explicit integer bit, which is set in every normal significand
+const INTEGER_BIT: u64 = 1 << 63;
+pub(crate) const QUIET_BIT: u64 = 1 << 62;
+/// The default NaN, which the x87 calls the "real indefinite"
+pub(crate) const INDEFINITE: ExtendedFloat = ExtendedFloat([0, 0, 0, 0, 0, 0, 0, 0xC0, 0xFF, 0xFF]);
+
+/// The magnitude of an extended precision value
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Value {
+    Zero,
+    /// The value `significand * 2^exponent`, where the top bit of the significand is always set
+    Finite { exponent: i32, significand: u64 },
+    Infinity,
+    Nan,
+    /// Unnormals, pseudo-infinities and pseudo-NaNs
+    Unsupported,
+}
+
+/// Splits the value into its sign, biased exponent and significand (including the integer bit)
+#[inline]
+pub fn split(value: ExtendedFloat) -> (bool, u16, u64) {
+    let bytes = value.0;
+    let mut significand = 0u64;
+    for (index, &byte) in bytes[..8].iter().enumerate() {
+        significand |= (byte as u64) << (index * 8);
+    }
+    let top = (bytes[8] as u16) | ((bytes[9] as u16) << 8);
+    (top & 0x8000 != 0, top & MAX_EXPONENT, significand)
+}
+
+/// Joins the sign, biased exponent and significand (including the integer bit) back into a value
+#[inline]
+pub fn join(negative: bool, exponent: u16, significand: u64) -> ExtendedFloat {
+    debug_assert!(exponent <= MAX_EXPONENT);
+    let mut bytes = [0u8; 10];
+    for (index, byte) in bytes[..8].iter_mut().enumerate() {
+        *byte = (significand >> (index * 8)) as u8;
+    }
+    let top = exponent | if negative { 0x8000 } else { 0 };
+    bytes[8] = top as u8;
+    bytes[9] = (top >> 8) as u8;
+    ExtendedFloat(bytes)
+}
+
+pub(crate) fn decode(value: ExtendedFloat) -> (bool, Value) {
+    let (negative, exponent, significand) = split(value);
+    let decoded = if exponent == MAX_EXPONENT {
+        if significand & INTEGER_BIT == 0 {
+            Value::Unsupported
+        } else if significand == INTEGER_BIT {
+            Value::Infinity
+        } else {
+            Value::Nan
+        }
+    } else if exponent == 0 {
+        if significand == 0 {
+            Value::Zero
+        } else {
+            // Subnormals have the same scale as the smallest normal exponent, just without the integer bit
+            let shift = significand.leading_zeros();
+            Value::Finite {
+                exponent: 1 - EXPONENT_BIAS - 63 - shift as i32,
+                significand: significand << shift
+            }
+        }
+    } else if significand & INTEGER_BIT == 0 {
+        Value::Unsupported
+    } else {
+        Value::Finite { exponent: exponent as i32 - EXPONENT_BIAS - 63, significand }
+    };
+    (negative, decoded)
+}
+
+#[inline]
+pub(crate) fn zero(negative: bool) -> ExtendedFloat {
+    join(negative, 0, 0)
+}
+
+#[inline]
+pub(crate) fn infinity(negative: bool) -> ExtendedFloat {
+    join(negative, MAX_EXPONENT, INTEGER_BIT)
+}
+
+#[inline]
+pub(crate) fn quiet(nan: ExtendedFloat) -> ExtendedFloat {
+    let (negative, exponent, significand) = split(nan);
+    join(negative, exponent, significand | QUIET_BIT)
+}
+
+#[inline]
+fn is_signaling(value: ExtendedFloat) -> bool {
+    match decode(value) {
+        (_, Value::Nan) => split(value).2 & QUIET_BIT == 0,
+        _ => false
+    }
+}
+
+/// Shifts right by any amount, also returning whether any nonzero bits were shifted out
+#[inline]
+fn shift_right(value: u128, amount: u32) -> (u128, bool) {
+    if amount == 0 {
+        (value, false)
+    } else if amount >= 128 {
+        (0, value != 0)
+    } else {
+        (value >> amount, value << (128 - amount) != 0)
+    }
+}
+
+/// Rounds `significand * 2^exponent` to `precision` bits, breaking ties to even.
+///
+/// The `sticky` flag says whether nonzero bits were already discarded below the significand,
+/// so the exact value is slightly larger than it.
+/// This gives the rounded significand and its biased exponent in the IEEE style,
+/// where subnormals have an exponent of zero, or `None` if the result overflows.
+pub(crate) fn round_bits(
+    exponent: i32, significand: u128, sticky: bool,
+    precision: u32, bias: i32, max_exponent: i32
+) -> Option<(u64, i32)> {
+    debug_assert!(precision <= 64);
+    if significand == 0 {
+        debug_assert!(!sticky);
+        return Some((0, 0))
+    }
+    let shift = significand.leading_zeros();
+    let mut significand = significand << shift;
+    let mut sticky = sticky;
+    // The biased exponent of the leading bit
+    let mut biased = exponent.saturating_add(127 - shift as i32).saturating_add(bias);
+    if biased < 1 {
+        let (shifted, lost) = shift_right(significand, (1i64 - biased as i64).min(128) as u32);
+        significand = shifted;
+        sticky |= lost;
+        biased = 0;
+    }
+    let mut result = significand >> (128 - precision);
+    let rest = significand << precision;
+    let half = 1u128 << 127;
+    if rest > half || rest == half && (sticky || result & 1 != 0) {
+        result += 1;
+        if result == 1 << precision {
+            // Carried past the top bit
+            result >>= 1;
+            biased += 1;
+        } else if biased == 0 && result & (1 << (precision - 1)) != 0 {
+            // Rounded up from the largest subnormal to the smallest normal
+            biased = 1;
+        }
+    }
+    if biased >= max_exponent {
+        None
+    } else {
+        Some((result as u64, biased))
+    }
+}
+
+/// Rounds `significand * 2^exponent` to the nearest extended precision value
+#[inline]
+pub(crate) fn round(negative: bool, exponent: i32, significand: u128, sticky: bool) -> ExtendedFloat {
+    match round_bits(exponent, significand, sticky, 64, EXPONENT_BIAS, MAX_EXPONENT as i32) {
+        Some((significand, exponent)) => join(negative, exponent as u16, significand),
+        None => infinity(negative)
+    }
+}
+
+#[inline]
+pub(crate) fn nan(negative: bool, payload: u64) -> ExtendedFloat {
+    join(negative, MAX_EXPONENT, INTEGER_BIT | QUIET_BIT | payload)
+}
+
+/// Picks the NaN that the x87 propagates when either operand is one.
+///
+/// Quiet NaNs win over signaling ones, then the larger significand wins
+/// (with ties going to the positive one), and the result is always quiet.
+fn propagate(first: ExtendedFloat, second: ExtendedFloat) -> ExtendedFloat {
+    let first_nan = is_nan(first);
+    let second_nan = is_nan(second);
+    if first_nan && !second_nan {
+        return quiet(first)
+    } else if second_nan && !first_nan {
+        return quiet(second)
+    }
+    let (first_negative, _, first_significand) = split(first);
+    let (_, _, second_significand) = split(second);
+    let first_quiet = first_significand & QUIET_BIT != 0;
+    let second_quiet = second_significand & QUIET_BIT != 0;
+    if first_quiet != second_quiet {
+        return quiet(if first_quiet { first } else { second })
+    }
+    quiet(match first_significand.cmp(&second_significand) {
+        Ordering::Greater => first,
+        Ordering::Less => second,
+        Ordering::Equal => if first_negative { second } else { first },
+    })
+}
+
+/// Decodes both operands, or gives the result if either one isn't a number
+pub(crate) fn operands(first: ExtendedFloat, second: ExtendedFloat) -> Result<((bool, Value), (bool, Value)), ExtendedFloat> {
+    let (first_negative, first_value) = decode(first);
+    let (second_negative, second_value) = decode(second);
+    match (first_value, second_value) {
+        (Value::Unsupported, _) | (_, Value::Unsupported) => Err(INDEFINITE),
+        (Value::Nan, _) | (_, Value::Nan) => Err(propagate(first, second)),
+        _ => Ok(((first_negative, first_value), (second_negative, second_value)))
+    }
+}
+
+pub fn add(first: ExtendedFloat, second: ExtendedFloat) -> ExtendedFloat {
+    match operands(first, second) {
+        Ok(((first_negative, first), (second_negative, second))) => {
+            sum(first_negative, first, second_negative, second)
+        },
+        Err(nan) => nan
+    }
+}
+
+pub fn sub(first: ExtendedFloat, second: ExtendedFloat) -> ExtendedFloat {
+    match operands(first, second) {
+        Ok(((first_negative, first), (second_negative, second))) => {
+            sum(first_negative, first, !second_negative, second)
+        },
+        Err(nan) => nan
+    }
+}
+
+fn sum(first_negative: bool, first: Value, second_negative: bool, second: Value) -> ExtendedFloat {
+    match (first, second) {
+        (Value::Infinity, Value::Infinity) => {
+            if first_negative == second_negative { infinity(first_negative) } else { INDEFINITE }
+        },
+        (Value::Infinity, _) => infinity(first_negative),
+        (_, Value::Infinity) => infinity(second_negative),
+        // Zeros only sum to negative zero when both of them are negative
+        (Value::Zero, Value::Zero) => zero(first_negative && second_negative),
+        (Value::Finite { exponent, significand }, Value::Zero) => {
+            round(first_negative, exponent, significand as u128, false)
+        },
+        (Value::Zero, Value::Finite { exponent, significand }) => {
+            round(second_negative, exponent, significand as u128, false)
+        },
+        (
+            Value::Finite { exponent: first_exponent, significand: first_significand },
+            Value::Finite { exponent: second_exponent, significand: second_significand }
+        ) => {
+            // Order by magnitude, so the difference is never negative
+            let (
+                (large_negative, large_exponent, large_significand),
+                (small_negative, small_exponent, small_significand)
+            ) = if (first_exponent, first_significand) >= (second_exponent, second_significand) {
+                ((first_negative, first_exponent, first_significand), (second_negative, second_exponent, second_significand))
+            } else {
+                ((second_negative, second_exponent, second_significand), (first_negative, first_exponent, first_significand))
+            };
+            // Leave room for a carry, with plenty of guard bits below the significand
+            let large = (large_significand as u128) << 63;
+            let (small, sticky) = shift_right(
+                (small_significand as u128) << 63,
+                (large_exponent - small_exponent) as u32
+            );
+            let exponent = large_exponent - 63;
+            if large_negative == small_negative {
+                round(large_negative, exponent, large + small, sticky)
+            } else if sticky {
+                // The discarded bits make the difference slightly smaller
+                round(large_negative, exponent, large - small - 1, true)
+            } else if large == small {
+                // Exact cancellation gives positive zero when rounding to nearest
+                zero(false)
+            } else {
+                round(large_negative, exponent, large - small, false)
+            }
+        },
+        _ => unreachable!("Unexpected operands: {:?} and {:?}", first, second)
+    }
+}
+
+pub fn mul(first: ExtendedFloat, second: ExtendedFloat) -> ExtendedFloat {
+    let ((first_negative, first), (second_negative, second)) = match operands(first, second) {
+        Ok(operands) => operands,
+        Err(nan) => return nan
+    };
+    let negative = first_negative != second_negative;
+    match (first, second) {
+        (Value::Infinity, Value::Zero) | (Value::Zero, Value::Infinity) => INDEFINITE,
+        (Value::Infinity, _) | (_, Value::Infinity) => infinity(negative),
+        (Value::Zero, _) | (_, Value::Zero) => zero(negative),
+        (
+            Value::Finite { exponent: first_exponent, significand: first_significand },
+            Value::Finite { exponent: second_exponent, significand: second_significand }
+        ) => {
+            // The full product always fits, so only the final rounding is inexact
+            let product = first_significand as u128 * second_significand as u128;
+            round(negative, first_exponent + second_exponent, product, false)
+        },
+        _ => unreachable!("Unexpected operands: {:?} and {:?}", first, second)
+    }
+}
+
+pub fn div(first: ExtendedFloat, second: ExtendedFloat) -> ExtendedFloat {
+    let ((first_negative, first), (second_negative, second)) = match operands(first, second) {
+        Ok(operands) => operands,
+        Err(nan) => return nan
+    };
+    let negative = first_negative != second_negative;
+    match (first, second) {
+        (Value::Infinity, Value::Infinity) | (Value::Zero, Value::Zero) => INDEFINITE,
+        (Value::Infinity, _) | (_, Value::Zero) => infinity(negative),
+        (Value::Zero, _) | (_, Value::Infinity) => zero(negative),
+        (
+            Value::Finite { exponent: first_exponent, significand: first_significand },
+            Value::Finite { exponent: second_exponent, significand: second_significand }
+        ) => {
+            // At least 64 quotient bits, plus two more from the remainder, are enough to round correctly
+            let numerator = (first_significand as u128) << 64;
+            let divisor = second_significand as u128;
+            let extra = (numerator % divisor) << 2;
+            let quotient = ((numerator / divisor) << 2) | (extra / divisor);
+            round(negative, first_exponent - second_exponent - 66, quotient, extra % divisor != 0)
+        },
+        _ => unreachable!("Unexpected operands: {:?} and {:?}", first, second)
+    }
+}
+
+/// Computes `first * second + third` with a single rounding, like `fma`
+pub fn mul_add(first: ExtendedFloat, second: ExtendedFloat, third: ExtendedFloat) -> ExtendedFloat {
+    let (first_negative, first_value) = decode(first);
+    let (second_negative, second_value) = decode(second);
+    let (third_negative, third_value) = decode(third);
+    let (product_exponent, product) = match (first_value, second_value) {
+        (
+            Value::Finite { exponent: first_exponent, significand: first_significand },
+            Value::Finite { exponent: second_exponent, significand: second_significand }
+        ) => (first_exponent + second_exponent, first_significand as u128 * second_significand as u128),
+        // A zero product is exact, so the sum gets the signs of zeros right
+        (Value::Zero, Value::Zero) | (Value::Zero, Value::Finite { .. }) | (Value::Finite { .. }, Value::Zero) => {
+            return add(zero(first_negative != second_negative), third)
+        },
+        // Infinities and NaNs in the product go through it just like they would separately
+        _ => return add(mul(first, second), third)
+    };
+    let product_negative = first_negative != second_negative;
+    let (addend_exponent, addend) = match third_value {
+        Value::Finite { exponent, significand } => (exponent, significand as u128),
+        Value::Zero => return round(product_negative, product_exponent, product, false),
+        Value::Infinity => return third,
+        Value::Nan => return quiet(third),
+        Value::Unsupported => return INDEFINITE,
+    };
+    /*
+     * When one operand is far too small to reach the rounding bits, it only matters as a nudge.
+     * Replacing it with a single bit well below them keeps the shifts short without changing the result.
+     */
+    let product_top = product_exponent + 128 - product.leading_zeros() as i32;
+    let addend_top = addend_exponent + 64;
+    let ((product_exponent, product), (addend_exponent, addend)) = if product_top - addend_top > 200 {
+        ((product_exponent, product), (product_top - 200, 1))
+    } else if addend_top - product_top > 200 {
+        ((addend_top - 200, 1), (addend_exponent, addend))
+    } else {
+        ((product_exponent, product), (addend_exponent, addend))
+    };
+    // Align both exactly at the lower of the two exponents
+    let exponent = product_exponent.min(addend_exponent);
+    let mut product = Big::from_u128(product);
+    product.shl((product_exponent - exponent) as u32);
+    let mut addend = Big::from_u128(addend);
+    addend.shl((addend_exponent - exponent) as u32);
+    let (negative, result) = if product_negative == third_negative {
+        let mut sum = Big::new(0);
+        sum.assign_sum(&product, &addend);
+        (product_negative, sum)
+    } else {
+        match product.compare(&addend) {
+            // Exact cancellation gives positive zero when rounding to nearest
+            Ordering::Equal => return zero(false),
+            Ordering::Greater => {
+                product.sub_assign(&addend);
+                (product_negative, product)
+            },
+            Ordering::Less => {
+                addend.sub_assign(&product);
+                (third_negative, addend)
+            }
+        }
+    };
+    let (top, start, sticky) = result.top_bits();
+    round(negative, exponent + start as i32, top, sticky)
+}
+
+/// Computes the remainder of truncated division, like `fmod` (or the x87 `fprem`).
+///
+/// This is always exact and has the same sign as the first operand.
+pub fn rem(first: ExtendedFloat, second: ExtendedFloat) -> ExtendedFloat {
+    let ((negative, first), (_, second)) = match operands(first, second) {
+        Ok(operands) => operands,
+        Err(nan) => return nan
+    };
+    match (first, second) {
+        (Value::Infinity, _) | (_, Value::Zero) => INDEFINITE,
+        (Value::Zero, _) => zero(negative),
+        (Value::Finite { exponent, significand }, Value::Infinity) => {
+            round(negative, exponent, significand as u128, false)
+        },
+        (
+            Value::Finite { exponent: first_exponent, significand: first_significand },
+            Value::Finite { exponent: second_exponent, significand: second_significand }
+        ) => {
+            if first_exponent < second_exponent {
+                // The first operand is already smaller than the second
+                return round(negative, first_exponent, first_significand as u128, false)
+            }
+            // Reduce `first_significand * 2^(first_exponent - second_exponent)` a chunk of bits at a time
+            let modulus = second_significand as u128;
+            let mut remainder = first_significand as u128 % modulus;
+            let mut shift = (first_exponent - second_exponent) as u32;
+            while shift > 0 {
+                let step = shift.min(64);
+                remainder = (remainder << step) % modulus;
+                shift -= step;
+            }
+            round(negative, second_exponent, remainder, false)
+        },
+        _ => unreachable!("Unexpected operands: {:?} and {:?}", first, second)
+    }
+}
+
+pub fn sqrt(value: ExtendedFloat) -> ExtendedFloat {
+    match decode(value) {
+        (_, Value::Unsupported) => INDEFINITE,
+        (_, Value::Nan) => quiet(value),
+        // This keeps the sign of negative zero
+        (negative, Value::Zero) => zero(negative),
+        (true, _) => INDEFINITE,
+        (false, Value::Infinity) => value,
+        (false, Value::Finite { exponent, significand }) => {
+            // Make the exponent even, leaving at least 126 bits so the root has all 64
+            let shift = if exponent & 1 == 0 { 64 } else { 63 };
+            let target = (significand as u128) << shift;
+            let root = integer_sqrt(target);
+            let remainder = target - root * root;
+            /*
+             * The root of an integer is never exactly halfway between two integers,
+             * so it's above the halfway point exactly when the remainder is larger than the root.
+             */
+            let half = (remainder > root) as u128;
+            round(false, (exponent - shift) / 2 - 1, (root << 1) | half, remainder != 0)
+        }
+    }
+}
+
+/// Computes the integer square root, rounded down
+pub(crate) fn integer_sqrt(target: u128) -> u128 {
+    let mut remainder = target;
+    let mut root = 0u128;
+    // The largest power of four that isn't larger than the target
+    let mut bit = 1u128 << 126;
+    while bit > target {
+        bit >>= 2;
+    }
+    while bit != 0 {
+        if remainder >= root + bit {
+            remainder -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+    root
+}
+
+#[inline]
+pub fn neg(value: ExtendedFloat) -> ExtendedFloat {
+    let (negative, exponent, significand) = split(value);
+    join(!negative, exponent, significand)
+}
+
+#[inline]
+pub fn abs(value: ExtendedFloat) -> ExtendedFloat {
+    let (_, exponent, significand) = split(value);
+    join(false, exponent, significand)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Direction {
+    Floor,
+    Ceil,
+    Trunc,
+    /// Round to nearest, with ties going away from zero
+    Nearest
+}
+
+fn round_integer(value: ExtendedFloat, direction: Direction) -> ExtendedFloat {
+    let (negative, exponent, significand) = match decode(value) {
+        (_, Value::Unsupported) => return INDEFINITE,
+        (_, Value::Nan) => return quiet(value),
+        (_, Value::Zero) | (_, Value::Infinity) => return value,
+        (negative, Value::Finite { exponent, significand }) => (negative, exponent, significand)
+    };
+    if exponent >= 0 {
+        // Already an integer
+        return value
+    }
+    let fraction_bits = (-exponent) as u32;
+    let (integer, has_fraction) = shift_right(significand as u128, fraction_bits);
+    let round_up = match direction {
+        Direction::Floor => negative && has_fraction,
+        Direction::Ceil => !negative && has_fraction,
+        Direction::Trunc => false,
+        Direction::Nearest => fraction_bits <= 64 && (significand >> (fraction_bits - 1)) & 1 != 0,
+    };
+    // This keeps the sign when the result is zero
+    round(negative, 0, integer + round_up as u128, false)
+}
+
+#[inline]
+pub fn floor(value: ExtendedFloat) -> ExtendedFloat {
+    round_integer(value, Direction::Floor)
+}
+
+#[inline]
+pub fn ceil(value: ExtendedFloat) -> ExtendedFloat {
+    round_integer(value, Direction::Ceil)
+}
+
+#[inline]
+pub fn trunc(value: ExtendedFloat) -> ExtendedFloat {
+    round_integer(value, Direction::Trunc)
+}
+
+/// Rounds to the nearest integer, with ties going away from zero
+#[inline]
+pub fn round_nearest(value: ExtendedFloat) -> ExtendedFloat {
+    round_integer(value, Direction::Nearest)
+}
+
+/// Splits the value into its integer and fractional parts, which both keep its sign
+pub fn modf(value: ExtendedFloat) -> (ExtendedFloat, ExtendedFloat) {
+    match decode(value) {
+        (_, Value::Unsupported) => (INDEFINITE, INDEFINITE),
+        (_, Value::Nan) => (quiet(value), quiet(value)),
+        (negative, Value::Infinity) => (value, zero(negative)),
+        (negative, _) => {
+            let integer = trunc(value);
+            if eq(integer, value) {
+                (integer, zero(negative))
+            } else {
+                (integer, sub(value, integer))
+            }
+        }
+    }
+}
+
+/// Gives the smaller value, ignoring a single quiet NaN like `fmin`.
+///
+/// Values that compare equal (like zeros with different signs) give the first one, just like glibc.
+#[inline]
+pub fn min(first: ExtendedFloat, second: ExtendedFloat) -> ExtendedFloat {
+    select(first, second, Ordering::Less)
+}
+
+/// Gives the larger value, ignoring a single quiet NaN like `fmax`.
+///
+/// Values that compare equal (like zeros with different signs) give the second one, just like glibc.
+#[inline]
+pub fn max(first: ExtendedFloat, second: ExtendedFloat) -> ExtendedFloat {
+    select(first, second, Ordering::Greater)
+}
+
+fn select(first: ExtendedFloat, second: ExtendedFloat, preferred: Ordering) -> ExtendedFloat {
+    match compare(first, second) {
+        // glibc's x87 versions keep the first for `fmin` and the second for `fmax` when they're equal
+        Some(Ordering::Equal) => if preferred == Ordering::Less { first } else { second },
+        Some(ordering) => if ordering == preferred { first } else { second },
+        None => {
+            if !is_nan(second) && !is_signaling(first) {
+                second
+            } else if !is_nan(first) && !is_signaling(second) {
+                first
+            } else {
+                add(first, second)
+            }
+        }
+    }
+}
+
+/// Checks for NaN, which includes the unsupported encodings since they never compare equal
+#[inline]
+pub fn is_nan(value: ExtendedFloat) -> bool {
+    match decode(value).1 {
+        Value::Nan | Value::Unsupported => true,
+        _ => false
+    }
+}
+
+#[inline]
+pub fn is_infinite(value: ExtendedFloat) -> bool {
+    match decode(value).1 {
+        Value::Infinity => true,
+        _ => false
+    }
+}
+
+#[inline]
+pub fn is_finite(value: ExtendedFloat) -> bool {
+    match decode(value).1 {
+        Value::Zero | Value::Finite { .. } => true,
+        _ => false
+    }
+}
+
+/// Checks for a normal value, which includes the pseudo-denormals since they have the same magnitude
+#[inline]
+pub fn is_normal(value: ExtendedFloat) -> bool {
+    match decode(value).1 {
+        Value::Finite { exponent, .. } => exponent >= 1 - EXPONENT_BIAS - 63,
+        _ => false
+    }
+}
+
+#[inline]
+pub fn is_sign_negative(value: ExtendedFloat) -> bool {
+    split(value).0
+}
+
+/// Gives the ordering of the magnitude, or `None` if the value isn't a number
+fn magnitude(value: Value) -> Option<(u8, i32, u64)> {
+    match value {
+        Value::Zero => Some((0, 0, 0)),
+        Value::Finite { exponent, significand } => Some((1, exponent, significand)),
+        Value::Infinity => Some((2, 0, 0)),
+        Value::Nan | Value::Unsupported => None
+    }
+}
+
+pub fn compare(first: ExtendedFloat, second: ExtendedFloat) -> Option<Ordering> {
+    let (first_negative, first) = decode(first);
+    let (second_negative, second) = decode(second);
+    let first = magnitude(first)?;
+    let second = magnitude(second)?;
+    let zero = (0, 0, 0);
+    if first == zero && second == zero {
+        // Zeros are equal regardless of their sign
+        return Some(Ordering::Equal)
+    }
+    Some(match (first_negative, second_negative) {
+        (false, false) => first.cmp(&second),
+        (true, true) => second.cmp(&first),
+        (false, true) => Ordering::Greater,
+        (true, false) => Ordering::Less,
+    })
+}
+
+#[inline]
+pub fn eq(first: ExtendedFloat, second: ExtendedFloat) -> bool {
+    compare(first, second) == Some(Ordering::Equal)
+}
+
+/// Converts from a smaller IEEE binary format, which is always exact
+fn widen(bits: u64, fraction_bits: u32, exponent_bits: u32) -> ExtendedFloat {
+    let negative = (bits >> (fraction_bits + exponent_bits)) & 1 != 0;
+    let max_exponent = (1 << exponent_bits) - 1;
+    let exponent = (bits >> fraction_bits) & max_exponent;
+    let fraction = bits & ((1 << fraction_bits) - 1);
+    let bias = (max_exponent >> 1) as i32;
+    if exponent == max_exponent {
+        // NaNs keep their payload, but loading them makes signaling NaNs quiet
+        let significand = INTEGER_BIT | (fraction << (63 - fraction_bits));
+        join(negative, MAX_EXPONENT, if fraction != 0 { significand | QUIET_BIT } else { significand })
+    } else if exponent == 0 {
+        // Zeros and subnormals don't have an implicit bit
+        round(negative, 1 - bias - fraction_bits as i32, fraction as u128, false)
+    } else {
+        let significand = fraction | (1 << fraction_bits);
+        round(negative, exponent as i32 - bias - fraction_bits as i32, significand as u128, false)
+    }
+}
+
+/// Rounds to a smaller IEEE binary format, giving its bits
+fn narrow(value: ExtendedFloat, fraction_bits: u32, exponent_bits: u32) -> u64 {
+    let (negative, decoded) = decode(value);
+    let sign = (negative as u64) << (fraction_bits + exponent_bits);
+    let max_exponent = (1u64 << exponent_bits) - 1;
+    let infinity = max_exponent << fraction_bits;
+    let quiet = 1 << (fraction_bits - 1);
+    let fraction_mask = (1 << fraction_bits) - 1;
+    match decoded {
+        Value::Zero => sign,
+        Value::Infinity => sign | infinity,
+        // The payload keeps its top bits, and the result is always quiet
+        Value::Nan => sign | infinity | quiet | ((split(value).2 >> (63 - fraction_bits)) & fraction_mask),
+        // This is the default NaN of the smaller format, which is negative just like the x87's
+        Value::Unsupported => (1 << (fraction_bits + exponent_bits)) | infinity | quiet,
+        Value::Finite { exponent, significand } => {
+            let bias = (max_exponent >> 1) as i32;
+            match round_bits(exponent, significand as u128, false, fraction_bits + 1, bias, max_exponent as i32) {
+                Some((significand, exponent)) => sign | ((exponent as u64) << fraction_bits) | (significand & fraction_mask),
+                None => sign | infinity
+            }
+        }
+    }
+}
+
+// NOTE: The `to_bits` and `from_bits` methods aren't available without the standard library
+
+#[inline]
+pub fn from_f64(value: f64) -> ExtendedFloat {
+    widen(unsafe { mem::transmute::<f64, u64>(value) }, 52, 11)
+}
+
+#[inline]
+pub fn from_f32(value: f32) -> ExtendedFloat {
+    widen(unsafe { mem::transmute::<f32, u32>(value) } as u64, 23, 8)
+}
+
+#[inline]
+pub fn from_i64(value: i64) -> ExtendedFloat {
+    round(value < 0, 0, value.wrapping_abs() as u64 as u128, false)
+}
+
+#[inline]
+pub fn from_u64(value: u64) -> ExtendedFloat {
+    round(false, 0, value as u128, false)
+}
+
+#[inline]
+pub fn to_f64(value: ExtendedFloat) -> f64 {
+    unsafe { mem::transmute::<u64, f64>(narrow(value, 52, 11)) }
+}
+
+#[inline]
+pub fn to_f32(value: ExtendedFloat) -> f32 {
+    unsafe { mem::transmute::<u32, f32>(narrow(value, 23, 8) as u32) }
+}
+
+/// Truncates to an integer like a C cast does.
+///
+/// Anything out of range (including NaN) gives the x87 "integer indefinite" of `i64::min_value()`.
+pub fn to_i64(value: ExtendedFloat) -> i64 {
+    match decode(value) {
+        (_, Value::Zero) => 0,
+        (negative, Value::Finite { exponent, significand }) => {
+            if exponent >= 0 {
+                // The magnitude is at least 2^63, which only fits as the minimum (that's indefinite anyway)
+                return i64::min_value()
+            }
+            let magnitude = shift_right(significand as u128, (-exponent) as u32).0 as i64;
+            if negative { -magnitude } else { magnitude }
+        },
+        _ => i64::min_value()
+    }
+}
+
+/// Truncates to an unsigned integer like a C cast does on x86.
+///
+/// That goes through the signed conversion, so negative values wrap around
+/// and anything too large gives whatever falls out of the integer indefinite.
+pub fn to_u64(value: ExtendedFloat) -> u64 {
+    let limit = from_u64(1 << 63);
+    match compare(value, limit) {
+        Some(Ordering::Greater) | Some(Ordering::Equal) => {
+            (to_i64(sub(value, limit)) as u64) ^ (1 << 63)
+        },
+        _ => to_i64(value) as u64
+    }
+}
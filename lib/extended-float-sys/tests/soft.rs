@@ -0,0 +1,339 @@
+//! Checks that the pure Rust implementation gives exactly the same bits as the x87
+#![cfg(all(target_arch = "x86_64", not(feature = "soft-float")))]
+extern crate extended_float_sys as sys;
+extern crate libc;
+#[macro_use]
+extern crate quickcheck;
+
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::{mem, ptr};
+
+use quickcheck::{Arbitrary, Gen};
+
+use sys::{soft, ExtendedFloat};
+
+/// An arbitrary extended precision value, which is biased towards the interesting ones
+#[derive(Copy, Clone, Debug)]
+struct Value(ExtendedFloat);
+impl Value {
+    /// Checks if this is one of the encodings the x87 doesn't support,
+    /// where the C library doesn't always go through the FPU and gives inconsistent results.
+    fn is_unsupported(self) -> bool {
+        let (_, exponent, significand) = soft::split(self.0);
+        exponent != 0 && significand >> 63 == 0
+    }
+    /// Checks if this is the same encoding the x87 would give after loading it
+    fn is_canonical(self) -> bool {
+        let (_, exponent, significand) = soft::split(self.0);
+        !self.is_unsupported() && !(exponent == 0 && significand >> 63 != 0)
+    }
+}
+/// Picks a random number in the range, which must not be empty
+fn pick<G: Gen>(g: &mut G, start: u32, end: u32) -> u32 {
+    start + g.next_u32() % (end - start)
+}
+
+impl Arbitrary for Value {
+    fn arbitrary<G: Gen>(g: &mut G) -> Value {
+        let negative = g.next_u32() & 1 != 0;
+        let bits = g.next_u64();
+        let shift = pick(g, 1, 64);
+        let (exponent, significand) = match pick(g, 0, 16) {
+            0 => (0, 0),
+            // Infinity and NaNs (both quiet and signaling)
+            1 => (0x7FFF, 1 << 63),
+            2 => (0x7FFF, (1 << 63) | (bits >> shift).max(1)),
+            // Subnormals, including the pseudo-denormals that have the integer bit set
+            3 => (0, bits >> (shift - 1)),
+            // Unnormals, pseudo-infinities and pseudo-NaNs
+            4 => (pick(g, 1, 0x8000), bits >> shift),
+            // Close to overflow and underflow
+            5 => (pick(g, 1, 70), (1 << 63) | bits),
+            6 => (pick(g, 0x7FFF - 70, 0x7FFF), (1 << 63) | bits),
+            // Only a few bits set, which makes for exact results and ties
+            7 | 8 => (pick(g, 0x3FFF - 70, 0x3FFF + 70), (1 << 63) | (bits >> shift << 32)),
+            _ => (pick(g, 0x3FFF - 100, 0x3FFF + 100), (1 << 63) | bits),
+        };
+        Value(soft::join(negative, exponent as u16, significand))
+    }
+}
+
+/// A pair of values, which often have similar magnitudes so they interact in interesting ways
+#[derive(Copy, Clone, Debug)]
+struct Pair(Value, Value);
+impl Arbitrary for Pair {
+    fn arbitrary<G: Gen>(g: &mut G) -> Pair {
+        let first = Value::arbitrary(g);
+        let second = if g.next_u32() % 3 == 0 {
+            let (_, exponent, significand) = soft::split(first.0);
+            let exponent = (exponent as i32 + pick(g, 0, 140) as i32 - 70).max(1).min(0x7FFE) as u16;
+            let significand = if g.next_u32() & 1 != 0 { significand ^ (1 << pick(g, 0, 64)) } else { significand };
+            Value(soft::join(g.next_u32() & 1 != 0, exponent, significand | (1 << 63)))
+        } else {
+            Value::arbitrary(g)
+        };
+        Pair(first, second)
+    }
+}
+
+fn binary(func: unsafe extern "C" fn(*mut ExtendedFloat, *const ExtendedFloat), mut first: ExtendedFloat, second: ExtendedFloat) -> ExtendedFloat {
+    unsafe { func(&mut first, &second) };
+    first
+}
+
+fn unary(func: unsafe extern "C" fn(*mut ExtendedFloat), mut value: ExtendedFloat) -> ExtendedFloat {
+    unsafe { func(&mut value) };
+    value
+}
+
+fn c_parse(text: &str) -> (ExtendedFloat, usize) {
+    let native = CString::new(text).unwrap();
+    let mut end = ptr::null_mut();
+    unsafe {
+        let mut out: ExtendedFloat = mem::uninitialized();
+        sys::extended_parse(&mut out, native.as_ptr(), &mut end);
+        (out, end as usize - native.as_ptr() as usize)
+    }
+}
+
+fn c_print(value: ExtendedFloat, precision: i32) -> String {
+    let mut out: *mut c_char = ptr::null_mut();
+    unsafe {
+        assert!(sys::extended_print(&value, 0, precision, &mut out) >= 0);
+        let result = CStr::from_ptr(out).to_str().unwrap().to_owned();
+        libc::free(out as *mut libc::c_void);
+        result
+    }
+}
+
+/// Splits what `printf` gives into the significant digits and the exponent `k` of `0.d1d2...dn * 10^k`,
+/// leaving out the trailing zeros just like `soft::exact` does
+fn c_digits(value: ExtendedFloat, precision: i32) -> (Vec<u8>, i32) {
+    let text = c_print(value, precision);
+    let text = text.trim_left_matches('-');
+    let (mantissa, exponent) = match text.find('e') {
+        Some(index) => (&text[..index], text[index + 1..].trim_left_matches('+').parse::<i32>().unwrap()),
+        None => (text, 0)
+    };
+    let point = mantissa.find('.').unwrap_or(mantissa.len());
+    let digits: Vec<u8> = mantissa.bytes().filter(|&c| c != b'.').map(|c| c - b'0').collect();
+    let zeros = digits.iter().take_while(|&&digit| digit == 0).count();
+    let end = digits.iter().rposition(|&digit| digit != 0).map_or(zeros, |index| index + 1);
+    (digits[zeros..end].to_vec(), point as i32 - zeros as i32 + exponent)
+}
+
+quickcheck! {
+    fn arithmetic(pair: Pair) -> bool {
+        let Pair(Value(first), Value(second)) = pair;
+        soft::add(first, second) == binary(sys::extended_add, first, second) &&
+            soft::sub(first, second) == binary(sys::extended_sub, first, second) &&
+            soft::mul(first, second) == binary(sys::extended_mul, first, second) &&
+            soft::div(first, second) == binary(sys::extended_div, first, second)
+    }
+
+    fn remainder(pair: Pair) -> bool {
+        let Pair(Value(first), Value(second)) = pair;
+        soft::rem(first, second) == binary(sys::extended_mod, first, second) &&
+            soft::rem(second, first) == binary(sys::extended_mod, second, first)
+    }
+
+    fn mul_add(pair: Pair, third: Value, cancel: bool) -> bool {
+        let Pair(Value(first), Value(second)) = pair;
+        if !pair.0.is_canonical() || !pair.1.is_canonical() || !third.is_canonical() {
+            // glibc's `fmal` is done in software, so it doesn't treat these like the x87 does
+            return true
+        }
+        // Cancelling most of the product is where rounding it separately goes wrong
+        let third = if cancel { soft::neg(soft::mul(first, second)) } else { third.0 };
+        let mut expected = first;
+        unsafe { sys::extended_mul_add(&mut expected, &second, &third) };
+        let actual = soft::mul_add(first, second, third);
+        // It doesn't pick the same NaN either
+        actual == expected || soft::is_nan(actual) && soft::is_nan(expected)
+    }
+
+    fn sqrt(value: Value) -> bool {
+        let value = value.0;
+        soft::sqrt(value) == unary(sys::extended_sqrt, value) &&
+            soft::sqrt(soft::abs(value)) == unary(sys::extended_sqrt, soft::abs(value))
+    }
+
+    fn compare(pair: Pair) -> bool {
+        let Pair(Value(first), Value(second)) = pair;
+        let expected = match unsafe { sys::extended_cmp(&first, &second) } {
+            -1 => Some(std::cmp::Ordering::Less),
+            0 => Some(std::cmp::Ordering::Equal),
+            1 => Some(std::cmp::Ordering::Greater),
+            _ => None
+        };
+        soft::compare(first, second) == expected &&
+            soft::eq(first, second) == unsafe { sys::extended_eq(&first, &second) } &&
+            soft::eq(first, first) == unsafe { sys::extended_eq(&first, &first) }
+    }
+
+    fn classify(value: Value) -> bool {
+        let value = value.0;
+        unsafe {
+            soft::is_nan(value) == sys::extended_isnan(&value) &&
+                soft::is_infinite(value) == sys::extended_isinf(&value) &&
+                soft::is_finite(value) == sys::extended_isfinite(&value) &&
+                soft::is_normal(value) == sys::extended_isnormal(&value) &&
+                soft::is_sign_negative(value) == (sys::extended_signbit(&value) != 0)
+        }
+    }
+
+    fn bit_operations(value: Value) -> bool {
+        let value = value.0;
+        soft::neg(value) == unary(sys::extended_neg, value) &&
+            soft::abs(value) == unary(sys::extended_abs, value)
+    }
+
+    fn round_integers(value: Value) -> bool {
+        if !value.is_canonical() {
+            return true
+        }
+        let value = value.0;
+        let (integer, fraction) = soft::modf(value);
+        let mut expected_fraction = value;
+        let mut expected_integer = value;
+        unsafe { sys::extended_modf(&mut expected_fraction, &mut expected_integer) };
+        soft::floor(value) == unary(sys::extended_floor, value) &&
+            soft::ceil(value) == unary(sys::extended_ceil, value) &&
+            soft::trunc(value) == unary(sys::extended_trunc, value) &&
+            soft::round_nearest(value) == unary(sys::extended_round, value) &&
+            integer == expected_integer && fraction == expected_fraction
+    }
+
+    fn min_max(pair: Pair) -> bool {
+        if !pair.0.is_canonical() || !pair.1.is_canonical() {
+            return true
+        }
+        let Pair(Value(first), Value(second)) = pair;
+        soft::min(first, second) == binary(sys::extended_min, first, second) &&
+            soft::max(first, second) == binary(sys::extended_max, first, second)
+    }
+
+    fn convert_to(value: Value) -> bool {
+        let value = value.0;
+        unsafe {
+            soft::to_f64(value).to_bits() == sys::extended_convert_into_f64(&value).to_bits() &&
+                soft::to_f32(value).to_bits() == sys::extended_convert_into_f32(&value).to_bits() &&
+                soft::to_i64(value) == sys::extended_convert_into_i64(&value) &&
+                soft::to_u64(value) == sys::extended_convert_into_u64(&value)
+        }
+    }
+
+    fn convert_from(bits: u64, integer: i64) -> bool {
+        let double = f64::from_bits(bits);
+        let single = f32::from_bits(bits as u32);
+        unsafe {
+            let mut expected: ExtendedFloat = mem::uninitialized();
+            sys::extended_convert_from_f64(&mut expected, double);
+            let mut result = soft::from_f64(double) == expected;
+            sys::extended_convert_from_f32(&mut expected, single);
+            result &= soft::from_f32(single) == expected;
+            sys::extended_convert_from_i64(&mut expected, integer);
+            result &= soft::from_i64(integer) == expected;
+            sys::extended_convert_from_u64(&mut expected, bits);
+            result && soft::from_u64(bits) == expected
+        }
+    }
+
+    fn parse_printed(value: Value, precision: u8) -> bool {
+        let text = c_print(value.0, (precision % 25) as i32);
+        soft::parse(text.as_bytes()) == c_parse(&text)
+    }
+
+    fn parse_digits(digits: Vec<u8>, point: usize, exponent: i16) -> bool {
+        let mut text: String = digits.iter().map(|&digit| (b'0' + digit % 10) as char).collect();
+        text.insert(point % (text.len() + 1), '.');
+        let text = format!("{}e{}", text, exponent);
+        soft::parse(text.as_bytes()) == c_parse(&text)
+    }
+
+    fn parse_hex(significand: u64, extra: u64, exponent: i16) -> bool {
+        let text = format!("-0x{:x}.{:016x}p{}", significand, extra, exponent);
+        soft::parse(text.as_bytes()) == c_parse(&text)
+    }
+
+    fn exact_digits(value: Value, precision: u8) -> bool {
+        let precision = precision as usize % 40 + 1;
+        // The C library decodes the pseudo-denormals differently from the x87
+        if !value.is_canonical() || !soft::is_finite(value.0) || soft::split(value.0).2 == 0 {
+            return true
+        }
+        let mut digits = [0; 40];
+        let (length, exponent) = soft::exact(soft::abs(value.0), &mut digits[..precision], i32::min_value());
+        (digits[..length].to_vec(), exponent) == c_digits(value.0, precision as i32)
+    }
+
+    /// Checks the shortest digits parse back to the same value, and that one digit fewer wouldn't be enough
+    fn shortest_digits(value: Value) -> bool {
+        let (_, _, significand) = soft::split(value.0);
+        if !value.is_canonical() || !soft::is_finite(value.0) || significand == 0 {
+            return true
+        }
+        let value = soft::abs(value.0);
+        let mut digits = [0; soft::SHORTEST_DIGITS];
+        let (length, exponent) = soft::shortest(value, &mut digits);
+        let text: String = digits[..length].iter().map(|&digit| (b'0' + digit) as char).collect();
+        let round_trips = soft::parse(format!("0.{}e{}", text, exponent).as_bytes()).0 == value;
+        // Below the powers of two the values are closer together, so the nearest shorter digits aren't always the best
+        if length == 1 || significand == 1 << 63 {
+            return round_trips
+        }
+        let (shorter, shorter_exponent) = soft::exact(value, &mut digits[..length - 1], i32::min_value());
+        let text: String = digits[..shorter].iter().map(|&digit| (b'0' + digit) as char).collect();
+        round_trips && soft::parse(format!("0.{}e{}", text, shorter_exponent).as_bytes()).0 != value
+    }
+}
+
+#[test]
+fn parse_special() {
+    for &text in &[
+        "", "-", "+.", ".5", "5.", "1e", "1e+", "1e-5000", "-1e-5000", "1e5000", "0x", "0x.", "0x.8p1", "0X1P-16445",
+        "inf", "-Infinity", "infinit", "nan", "-NaN", "nan(", "nan()", "nan(123)", "nan(0x7ff)", "nan(017)",
+        "nan(abc)", "nan(99999999999999999999999)", "1.e3", "1_000", "00000000000000012345e-12",
+        "1.18973149535723176508575932662800701e4932", "1.18973149535723176508575932662800702e4932",
+        "3.64519953188247460252840593361941982e-4951", "1.8225997659412373012642029668097099e-4951",
+        "1.8225997659412373012642029668097100e-4951",
+    ] {
+        assert_eq!(soft::parse(text.as_bytes()), c_parse(text), "{:?}", text);
+    }
+}
+
+/// Checks the exact decimal expansions, which can have thousands of digits
+#[test]
+fn parse_exact() {
+    for &(exponent, significand) in &[(0, 1), (0, 3), (0, !0 >> 1), (1, 1 << 63), (0x3FFF, 1 << 63 | 1), (0x7FFE, !0)] {
+        let text = c_print(soft::join(false, exponent, significand), 12000);
+        assert_eq!(soft::parse(text.as_bytes()), c_parse(&text), "{}", text);
+    }
+}
+
+/// Checks the longest expansions, where the largest subnormal has exactly `MAX_DIGITS` digits
+#[test]
+fn exact_long() {
+    let mut digits = vec![0; soft::MAX_DIGITS];
+    for &(exponent, significand) in &[(0, 1), (0, 3), (0, !0 >> 1), (1, 1 << 63), (0x3FFF, 1 << 63 | 1), (0x7FFE, !0)] {
+        let value = soft::join(false, exponent, significand);
+        let (length, decimal_exponent) = soft::exact(value, &mut digits, i32::min_value());
+        assert_eq!((digits[..length].to_vec(), decimal_exponent), c_digits(value, soft::MAX_DIGITS as i32 + 10));
+        if (exponent, significand) == (0, !0 >> 1) {
+            assert_eq!(length, soft::MAX_DIGITS);
+        }
+    }
+}
+
+quickcheck! {
+    /// Checks decimals on and right next to the halfway point between two values, where ties go to even
+    fn parse_halfway(integer: u64) -> bool {
+        // At this magnitude the values are exactly the integers
+        let integer = integer | (1 << 63);
+        [".5", ".5000000000000000000000000000001", ".4999999999999999999999999999999"].iter().all(|suffix| {
+            let text = format!("{}{}", integer, suffix);
+            soft::parse(text.as_bytes()) == c_parse(&text)
+        })
+    }
+}
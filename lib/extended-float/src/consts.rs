@@ -5,6 +5,14 @@ use super::ExtendedFloat;
 pub const NAN: ExtendedFloat = extended_float!("NAN");
 pub const INFINITY: ExtendedFloat = extended_float!("inf");
 pub const NEG_INFINITY: ExtendedFloat = extended_float!("-inf");
+/// The smallest finite value, which is about `-1.19e4932`
+pub const MIN: ExtendedFloat = extended_float!("-0xf.fffffffffffffffp+16380");
+/// The largest finite value, which is about `1.19e4932`
+pub const MAX: ExtendedFloat = extended_float!("0xf.fffffffffffffffp+16380");
+/// The smallest positive normal value, which is about `3.36e-4932`
+pub const MIN_POSITIVE: ExtendedFloat = extended_float!("0x8p-16385");
+/// The difference between one and the next larger value, which is `2^-63`
+pub const EPSILON: ExtendedFloat = extended_float!("0x8p-66");
 pub const PI: ExtendedFloat = extended_float!("3.14159265358979323846264338327950288419716939937510582097494459230781640628620899862");
 
 // Pi fractions
@@ -20,3 +28,13 @@ pub const FRAC_PI_6: ExtendedFloat = extended_float!("0.523598775598298873077107
 // Sqrt constants
 /// the value of `1/sqrt(2)`
 pub const FRAC_1_SQRT_2: ExtendedFloat = extended_float!("0.7071067811865475244008443621048490392848359376884740365883398689953662");
+
+// Logarithm constants
+/// The value of Euler's number `e`
+pub const E: ExtendedFloat = extended_float!("2.718281828459045235360287471352662497757247093699959574966967627724077");
+/// The value of `ln(2)`
+pub const LN_2: ExtendedFloat = extended_float!("0.6931471805599453094172321214581765680755001343602552541206800094933936");
+/// The value of `ln(10)`
+pub const LN_10: ExtendedFloat = extended_float!("2.302585092994045684017991454684364207601101488628772976033327900967573");
+/// The value of `log2(e)`
+pub const LOG2_E: ExtendedFloat = extended_float!("1.442695040888963407359924681001892137426645954152985934135449406931109");
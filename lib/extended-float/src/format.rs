@@ -0,0 +1,289 @@
+//! Formatting in pure Rust, with the same layout as the primitive floats.
+//!
+//! The digits come from `sys::soft::shortest` and `sys::soft::exact`,
+//! which work the same way with either backend and don't allocate.
+//! Since a value can need thousands of digits (mostly zeros) the output is written in parts,
+//! and the padding is worked out from their length instead of going through a buffer.
+use std::fmt::{self, Formatter, Write};
+use std::{i32, str};
+
+use sys::soft::{self, MAX_DIGITS, SHORTEST_DIGITS};
+
+use ExtendedFloat;
+
+/// A piece of the output, so the runs of zeros never need to be buffered
+#[derive(Copy, Clone)]
+enum Part<'a> {
+    Text(&'a str),
+    Zeros(usize),
+}
+impl<'a> Part<'a> {
+    fn len(self) -> usize {
+        match self {
+            Part::Text(text) => text.len(),
+            Part::Zeros(count) => count,
+        }
+    }
+    fn write(self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Part::Text(text) => f.write_str(text),
+            Part::Zeros(mut count) => {
+                const ZEROS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+                while count > 0 {
+                    let chunk = count.min(ZEROS.len());
+                    f.write_str(&ZEROS[..chunk])?;
+                    count -= chunk;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A short piece of text on the stack, which is plenty for an exponent or the hex digits
+struct Small {
+    bytes: [u8; 24],
+    length: usize,
+}
+impl Small {
+    fn new() -> Small {
+        Small { bytes: [0; 24], length: 0 }
+    }
+    fn as_str(&self) -> &str {
+        str::from_utf8(&self.bytes[..self.length]).unwrap()
+    }
+}
+impl Write for Small {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        let end = self.length + text.len();
+        if end > self.bytes.len() {
+            return Err(fmt::Error)
+        }
+        self.bytes[self.length..end].copy_from_slice(text.as_bytes());
+        self.length = end;
+        Ok(())
+    }
+}
+
+/// Turns the raw digits into text in place
+fn ascii(digits: &mut [u8]) -> &str {
+    for digit in digits.iter_mut() {
+        debug_assert!(*digit < 10);
+        *digit += b'0';
+    }
+    str::from_utf8(digits).unwrap()
+}
+
+/// Writes the sign, the prefix and then the parts, padded out to the width.
+///
+/// Just like the primitive numbers, the default is to align to the right,
+/// and padding with zeros goes between the prefix and the digits (which only makes sense for finite values).
+fn pad(f: &mut Formatter, sign: &str, prefix: &str, parts: &[Part], finite: bool) -> fmt::Result {
+    let length = sign.len() + prefix.len() + parts.iter().map(|part| part.len()).sum::<usize>();
+    let padding = match f.width() {
+        Some(width) if width > length => width - length,
+        _ => 0
+    };
+    if f.sign_aware_zero_pad() && finite {
+        f.write_str(sign)?;
+        f.write_str(prefix)?;
+        Part::Zeros(padding).write(f)?;
+        for &part in parts {
+            part.write(f)?;
+        }
+        return Ok(())
+    }
+    let (before, after) = match f.align() {
+        Some(fmt::Alignment::Left) => (0, padding),
+        Some(fmt::Alignment::Center) => (padding / 2, (padding + 1) / 2),
+        Some(fmt::Alignment::Right) | None => (padding, 0),
+    };
+    let fill = f.fill();
+    for _ in 0..before {
+        f.write_char(fill)?;
+    }
+    f.write_str(sign)?;
+    f.write_str(prefix)?;
+    for &part in parts {
+        part.write(f)?;
+    }
+    for _ in 0..after {
+        f.write_char(fill)?;
+    }
+    Ok(())
+}
+
+/// Writes NaN and the infinities, giving back the sign and the significand for everything else
+fn special(value: ExtendedFloat, f: &mut Formatter) -> Result<(&'static str, u64), fmt::Result> {
+    let value = ::sys::ExtendedFloat(value.0);
+    let (negative, _, significand) = soft::split(value);
+    let sign = if negative { "-" } else if f.sign_plus() { "+" } else { "" };
+    if soft::is_nan(value) {
+        Err(pad(f, "", "", &[Part::Text("NaN")], false))
+    } else if soft::is_infinite(value) {
+        Err(pad(f, sign, "", &[Part::Text("inf")], false))
+    } else {
+        Ok((sign, significand))
+    }
+}
+
+/// Writes the digits of `0.d1d2...dn * 10^exponent` without an exponent,
+/// adding zeros until there are at least `fraction` digits after the point.
+fn fixed(f: &mut Formatter, sign: &str, digits: &str, exponent: i32, fraction: usize) -> fmt::Result {
+    let point = if fraction > 0 { "." } else { "" };
+    if digits.is_empty() {
+        pad(f, sign, "", &[Part::Text("0"), Part::Text(point), Part::Zeros(fraction)], true)
+    } else if exponent <= 0 {
+        let zeros = (-exponent) as usize;
+        let padding = fraction.saturating_sub(zeros + digits.len());
+        pad(f, sign, "", &[
+            Part::Text("0."),
+            Part::Zeros(zeros),
+            Part::Text(digits),
+            Part::Zeros(padding),
+        ], true)
+    } else if (exponent as usize) < digits.len() {
+        let (integer, rest) = digits.split_at(exponent as usize);
+        pad(f, sign, "", &[
+            Part::Text(integer),
+            Part::Text("."),
+            Part::Text(rest),
+            Part::Zeros(fraction.saturating_sub(rest.len())),
+        ], true)
+    } else {
+        pad(f, sign, "", &[
+            Part::Text(digits),
+            Part::Zeros(exponent as usize - digits.len()),
+            Part::Text(point),
+            Part::Zeros(fraction),
+        ], true)
+    }
+}
+
+/// Writes the value without an exponent, like `Display` and `Debug` do.
+///
+/// Without a precision these give the shortest digits that parse back to the same value,
+/// and `Debug` always has a fractional part so it looks like a float.
+/// With a precision the value is rounded to that many places (with ties to even).
+pub fn decimal(value: ExtendedFloat, f: &mut Formatter, debug: bool) -> fmt::Result {
+    let (sign, significand) = match special(value, f) {
+        Ok(decoded) => decoded,
+        Err(result) => return result
+    };
+    match f.precision() {
+        Some(precision) => {
+            let mut buffer = [0u8; MAX_DIGITS];
+            let (length, exponent) = if significand == 0 {
+                (0, 0)
+            } else {
+                let limit = -(precision.min(i32::MAX as usize) as i32);
+                soft::exact(::sys::ExtendedFloat(value.0), &mut buffer, limit)
+            };
+            fixed(f, sign, ascii(&mut buffer[..length]), exponent, precision)
+        },
+        None => {
+            let mut buffer = [0u8; SHORTEST_DIGITS];
+            let (length, exponent) = if significand == 0 {
+                (0, 0)
+            } else {
+                soft::shortest(::sys::ExtendedFloat(value.0), &mut buffer)
+            };
+            fixed(f, sign, ascii(&mut buffer[..length]), exponent, if debug { 1 } else { 0 })
+        }
+    }
+}
+
+/// Writes the value in scientific notation like `1.5e3`, which is what `LowerExp` and `UpperExp` do.
+///
+/// Without a precision this gives the shortest digits that parse back to the same value,
+/// otherwise it has exactly that many digits after the point (rounded with ties to even).
+pub fn exponent(value: ExtendedFloat, f: &mut Formatter, upper: bool) -> fmt::Result {
+    let (sign, significand) = match special(value, f) {
+        Ok(decoded) => decoded,
+        Err(result) => return result
+    };
+    let mut buffer = [0u8; MAX_DIGITS];
+    let (length, exponent) = if significand == 0 {
+        (1, 1)
+    } else {
+        match f.precision() {
+            Some(precision) => {
+                let length = precision.saturating_add(1).min(MAX_DIGITS);
+                soft::exact(::sys::ExtendedFloat(value.0), &mut buffer[..length], i32::MIN)
+            },
+            None => soft::shortest(::sys::ExtendedFloat(value.0), &mut buffer[..SHORTEST_DIGITS])
+        }
+    };
+    let (first, rest) = ascii(&mut buffer[..length]).split_at(1);
+    let fraction = f.precision().unwrap_or(0);
+    let padding = fraction.saturating_sub(rest.len());
+    let point = if rest.is_empty() && padding == 0 { "" } else { "." };
+    let mut suffix = Small::new();
+    write!(suffix, "{}{}", if upper { 'E' } else { 'e' }, exponent - 1)?;
+    pad(f, sign, "", &[
+        Part::Text(first),
+        Part::Text(point),
+        Part::Text(rest),
+        Part::Zeros(padding),
+        Part::Text(suffix.as_str()),
+    ], true)
+}
+
+/// Writes the exact value in hex, just like `printf("%La")` does on x86 (so `1` is `0x8p-3`).
+///
+/// The first hex digit holds the integer bit, and the precision rounds to that many hex digits
+/// after the point (with ties to even). Unlike the integers, this always has the `0x` prefix.
+pub fn hex(value: ExtendedFloat, f: &mut Formatter, upper: bool) -> fmt::Result {
+    let (sign, significand) = match special(value, f) {
+        Ok(decoded) => decoded,
+        Err(result) => return result
+    };
+    let (_, biased_exponent, _) = soft::split(::sys::ExtendedFloat(value.0));
+    // The first hex digit holds the integer bit, so the exponent is three less
+    let mut exponent = if significand == 0 { 0 } else { biased_exponent.max(1) as i32 - 16383 - 3 };
+    let mut leading = significand >> 60;
+    let mut fraction = significand & ((1 << 60) - 1);
+    let mut digits = 15;
+    if let Some(precision) = f.precision() {
+        if precision < 15 {
+            let dropped = 4 * (15 - precision) as u32;
+            let remainder = significand & ((1 << dropped) - 1);
+            let half = 1 << (dropped - 1);
+            let mut kept = significand >> dropped;
+            if remainder > half || (remainder == half && kept & 1 != 0) {
+                kept += 1;
+            }
+            leading = kept >> (4 * precision);
+            fraction = kept & ((1 << (4 * precision)) - 1);
+            // Rounding up from `0xf.f` carries into another digit
+            if leading == 0x10 {
+                leading = 1;
+                exponent += 4;
+            }
+            digits = precision;
+        }
+    } else if fraction == 0 {
+        digits = 0;
+    } else {
+        while fraction & 0xF == 0 {
+            fraction >>= 4;
+            digits -= 1;
+        }
+    }
+    let mut body = Small::new();
+    match (upper, digits) {
+        (false, 0) => write!(body, "{:x}", leading)?,
+        (true, 0) => write!(body, "{:X}", leading)?,
+        (false, _) => write!(body, "{:x}.{:02$x}", leading, fraction, digits)?,
+        (true, _) => write!(body, "{:X}.{:02$X}", leading, fraction, digits)?,
+    }
+    let padding = f.precision().map_or(0, |precision| precision.saturating_sub(digits));
+    let mut suffix = Small::new();
+    write!(suffix, "{}{:+}", if upper { 'P' } else { 'p' }, exponent)?;
+    pad(f, sign, if upper { "0X" } else { "0x" }, &[
+        Part::Text(body.as_str()),
+        Part::Text(if padding > 0 && digits == 0 { "." } else { "" }),
+        Part::Zeros(padding),
+        Part::Text(suffix.as_str()),
+    ], true)
+}
@@ -1,6 +1,13 @@
 //! Extended precision floating point
-#![feature(const_fn, proc_macro, proc_macro_non_items, ptr_offset_from)]
-extern crate libc;
+//!
+//! By default this calls into C, so it only works on x86 where the C `long double` is 80 bits.
+//! The `soft-float` feature implements everything in pure Rust instead,
+//! which works anywhere (even without `std`) and has exactly the same API.
+//! The `Float` impl needs `std` either way, since that's the only way `num-traits` has the trait.
+#![feature(const_fn, proc_macro, proc_macro_non_items)]
+#![cfg_attr(not(feature = "std"), no_std)]
+#[cfg(not(feature = "std"))]
+extern crate core as std;
 extern crate num_traits;
 
 extern crate extended_float_sys as sys;
@@ -8,23 +15,29 @@ extern crate extended_float_macros;
 
 use std::num::FpCategory;
 use std::ops::{Add, AddAssign, Sub, SubAssign, Mul, MulAssign, Div, Neg, DivAssign, Rem, RemAssign};
-use std::os::raw::{c_char};
-use std::{hint, ptr, slice, mem};
-use std::ffi::{CStr, CString, NulError};
-use std::fmt::{self, Write, Debug, Display, Formatter};
+use std::{hint, mem};
+use std::fmt::{self, Debug, Display, LowerExp, UpperExp, LowerHex, UpperHex, Formatter};
 use std::str::FromStr;
 use std::cmp::Ordering;
 
-use num_traits::{Num, Float, One, Zero, ToPrimitive};
+use num_traits::{Num, One, Zero, ToPrimitive};
+#[cfg(feature = "std")]
+use num_traits::Float;
 
 use extended_float_macros::extended_float;
 
+#[cfg(not(any(feature = "std", feature = "soft-float")))]
+compile_error!("The C implementation needs the standard library, so either enable `std` or `soft-float`");
+
 pub mod consts;
+mod format;
+mod parse;
 
 /// An extended precision floating point value.
 ///
 /// This guarantees "at least" 80 bits of precision.
-/// Currently this only works on x86 and maps directly to a 80-bit floating point value.
+/// This maps directly to a 80-bit x87 floating point value,
+/// which only works on x86 unless the `soft-float` feature is enabled.
 #[repr(C)]
 #[derive(Copy, Clone)]
 #[allow(dead_code)]
@@ -40,34 +53,6 @@ impl ExtendedFloat {
     pub const fn from_bits(bits: [u8; 10]) -> ExtendedFloat {
         ExtendedFloat(bits)
     }
-    fn write<W: Write>(&self, width: Option<usize>, precision: Option<usize>, mut out: W) -> fmt::Result {
-        self.print_with(width, precision, |data| {
-            out.write_str(data.to_str().unwrap())
-        })
-    }
-    fn print_with<R, F: FnOnce(&CStr) -> R>(&self, width: Option<usize>, precision: Option<usize>, func: F) -> R {
-        let mut ptr: *mut c_char = ptr::null_mut();
-        let size = unsafe { ::sys::extended_print(
-            self.as_ptr(),
-            width.map_or(0, |w| w as i32),
-            precision.map_or(0, |w| w as i32),
-            &mut ptr
-        ) };
-        assert!(size >= 0, "Out of memory");
-        let data = unsafe {
-            debug_assert_eq!(ptr.add(size as usize).read(), b'\0' as i8);
-            CStr::from_bytes_with_nul_unchecked(slice::from_raw_parts(
-                ptr as *const u8,
-                (size as usize) + 1
-            ))
-        };
-        let result = func(data);
-        // Now free the memory with the `free` function
-        unsafe {
-            ::libc::free(ptr as *mut ::libc::c_void);
-        }
-        result
-    }
     #[inline]
     fn as_ptr(&self) -> *const sys::ExtendedFloat {
         self as *const ExtendedFloat as *const sys::ExtendedFloat
@@ -86,6 +71,94 @@ impl ExtendedFloat {
         }
     }
 }
+/*
+ * These are available even without the standard library,
+ * unlike the `Float` impl since `num-traits` only has the trait with `std`.
+ */
+impl ExtendedFloat {
+    #[inline]
+    pub fn is_nan(self) -> bool {
+        unsafe { sys::extended_isnan(self.as_ptr()) }
+    }
+    #[inline]
+    pub fn is_infinite(self) -> bool {
+        unsafe { sys::extended_isinf(self.as_ptr()) }
+    }
+    #[inline]
+    pub fn is_finite(self) -> bool {
+        unsafe { sys::extended_isfinite(self.as_ptr()) }
+    }
+    #[inline]
+    pub fn is_normal(self) -> bool {
+        unsafe { sys::extended_isnormal(self.as_ptr()) }
+    }
+    pub fn classify(self) -> FpCategory {
+        if self.is_nan() {
+            FpCategory::Nan
+        } else if self.is_infinite() {
+            FpCategory::Infinite
+        } else if self.is_normal() {
+            FpCategory::Normal
+        } else if self == extended_float!(0.0) || self == extended_float!(-0.0) {
+            FpCategory::Zero
+        } else {
+            FpCategory::Subnormal
+        }
+    }
+    #[inline]
+    pub fn is_sign_positive(self) -> bool {
+        unsafe { sys::extended_signbit(self.as_ptr()) == 0 }
+    }
+    #[inline]
+    pub fn is_sign_negative(self) -> bool {
+        !self.is_sign_positive()
+    }
+    #[inline]
+    pub fn floor(mut self) -> ExtendedFloat {
+        unsafe { sys::extended_floor(self.as_mut_ptr()) }
+        self
+    }
+    #[inline]
+    pub fn ceil(mut self) -> ExtendedFloat {
+        unsafe { sys::extended_ceil(self.as_mut_ptr()) }
+        self
+    }
+    /// Rounds to the nearest integer, with ties away from zero
+    #[inline]
+    pub fn round(mut self) -> ExtendedFloat {
+        unsafe { sys::extended_round(self.as_mut_ptr()) }
+        self
+    }
+    #[inline]
+    pub fn trunc(mut self) -> ExtendedFloat {
+        unsafe { sys::extended_trunc(self.as_mut_ptr()) }
+        self
+    }
+    #[inline]
+    pub fn fract(self) -> ExtendedFloat {
+        self.modf().1
+    }
+    #[inline]
+    pub fn abs(mut self) -> ExtendedFloat {
+        unsafe { sys::extended_abs(self.as_mut_ptr()) }
+        self
+    }
+    #[inline]
+    pub fn sqrt(mut self) -> ExtendedFloat {
+        unsafe { sys::extended_sqrt(self.as_mut_ptr()) };
+        self
+    }
+    #[inline]
+    pub fn max(mut self, other: ExtendedFloat) -> ExtendedFloat {
+        unsafe { sys::extended_max(self.as_mut_ptr(), other.as_ptr()) };
+        self
+    }
+    #[inline]
+    pub fn min(mut self, other: ExtendedFloat) -> ExtendedFloat {
+        unsafe { sys::extended_min(self.as_mut_ptr(), other.as_ptr()) };
+        self
+    }
+}
 impl ToPrimitive for ExtendedFloat {
     fn to_i64(&self) -> Option<i64> {
         if self.is_normal() {
@@ -111,8 +184,8 @@ impl ToPrimitive for ExtendedFloat {
 
     fn to_f32(&self) -> Option<f32> {
         if self.is_finite() {
-            let min: ExtendedFloat = f32::min_value().into();
-            let max: ExtendedFloat = f32::max_value().into();
+            let min: ExtendedFloat = ::std::f32::MIN.into();
+            let max: ExtendedFloat = ::std::f32::MAX.into();
             if *self >= min && *self <= max {
                 Some(f32::from(*self))
             } else {
@@ -125,8 +198,8 @@ impl ToPrimitive for ExtendedFloat {
 
     fn to_f64(&self) -> Option<f64> {
         if self.is_finite() {
-            let min: ExtendedFloat = f64::min_value().into();
-            let max: ExtendedFloat = f64::max_value().into();
+            let min: ExtendedFloat = ::std::f64::MIN.into();
+            let max: ExtendedFloat = ::std::f64::MAX.into();
             if *self >= min && *self <= max {
                 Some(f64::from(*self))
             } else {
@@ -170,13 +243,33 @@ impl ::num_traits::NumCast for ExtendedFloat {
     }
 }
 impl Num for ExtendedFloat {
-    type FromStrRadixErr = ();
-
-    #[inline]
-    fn from_str_radix(_str: &str, _radix: u32) -> Result<Self, ()> {
-        unimplemented!()
+    type FromStrRadixErr = ExtendedFloatParseError;
+
+    /// Parses a number in any radix from 2 to 36, which is always correctly rounded.
+    ///
+    /// Just like the primitive floats this also accepts `inf`, `-inf` and `NaN`,
+    /// and only decimal and hexadecimal numbers can have exponents (with `e` and `p` respectively).
+    /// This panics if the radix is out of range.
+    fn from_str_radix(s: &str, radix: u32) -> Result<Self, ExtendedFloatParseError> {
+        match s {
+            "" => return Err(ExtendedFloatParseError::Empty),
+            "inf" => return Ok(consts::INFINITY),
+            "-inf" => return Ok(consts::NEG_INFINITY),
+            "NaN" => return Ok(consts::NAN),
+            _ => {}
+        }
+        let sign = ExtendedFloatParseError::sign_length(s);
+        let end = parse::scan_radix(&s.as_bytes()[sign..], radix)
+            .map_err(|offset| ExtendedFloatParseError::InvalidFloat(sign + offset))?;
+        if sign + end != s.len() {
+            return Err(ExtendedFloatParseError::TrailingChars(s.len() - sign - end))
+        }
+        let (value, consumed_bytes) = sys::soft::parse_radix(s.as_bytes(), radix);
+        debug_assert_eq!(consumed_bytes, s.len());
+        Ok(ExtendedFloat(value.0))
     }
 }
+#[cfg(feature = "std")]
 impl Float for ExtendedFloat {
     #[inline]
     fn nan() -> Self {
@@ -195,82 +288,73 @@ impl Float for ExtendedFloat {
     fn neg_zero() -> Self {
         extended_float!(-0.0)
     }
+    #[inline]
     fn min_value() -> Self {
-        unimplemented!()
+        consts::MIN
     }
+    #[inline]
     fn min_positive_value() -> Self {
-        unimplemented!()
+        consts::MIN_POSITIVE
+    }
+    #[inline]
+    fn epsilon() -> Self {
+        consts::EPSILON
     }
+    #[inline]
     fn max_value() -> Self {
-        unimplemented!()
+        consts::MAX
     }
 
     #[inline]
     fn is_nan(self) -> bool {
-        unsafe { sys::extended_isnan(self.as_ptr()) }
+        ExtendedFloat::is_nan(self)
     }
 
     #[inline]
     fn is_infinite(self) -> bool {
-        unsafe { sys::extended_isinf(self.as_ptr()) }
+        ExtendedFloat::is_infinite(self)
     }
     #[inline]
     fn is_finite(self) -> bool {
-        unsafe { sys::extended_isfinite(self.as_ptr()) }
+        ExtendedFloat::is_finite(self)
     }
 
     #[inline]
     fn is_normal(self) -> bool {
-        unsafe { sys::extended_isnormal(self.as_ptr()) }
+        ExtendedFloat::is_normal(self)
     }
+    #[inline]
     fn classify(self) -> FpCategory {
-        if self.is_nan() {
-            FpCategory::Nan
-        } else if self.is_infinite() {
-            FpCategory::Infinite
-        } else if self.is_normal() {
-            FpCategory::Normal
-        } else if self == extended_float!(0.0) || self == extended_float!(-0.0) {
-            FpCategory::Zero
-        } else {
-            FpCategory::Subnormal
-        }
+        ExtendedFloat::classify(self)
     }
     #[inline]
-    fn floor(mut self) -> ExtendedFloat {
-        unsafe { sys::extended_floor(self.as_mut_ptr()) }
-        self
+    fn floor(self) -> Self {
+        ExtendedFloat::floor(self)
     }
 
     #[inline]
-    fn ceil(mut self) -> ExtendedFloat {
-        unsafe { sys::extended_ceil(self.as_mut_ptr()) }
-        self
+    fn ceil(self) -> Self {
+        ExtendedFloat::ceil(self)
     }
 
     #[inline]
-    fn round(mut self) -> ExtendedFloat {
-        unsafe { sys::extended_round(self.as_mut_ptr()) }
-        self
+    fn round(self) -> Self {
+        ExtendedFloat::round(self)
     }
 
     #[inline]
-    fn trunc(mut self) -> Self {
-        unsafe {
-            sys::extended_trunc(self.as_mut_ptr());
-            self
-        }
+    fn trunc(self) -> Self {
+        ExtendedFloat::trunc(self)
     }
 
     #[inline]
     fn fract(self) -> Self {
-        self.modf().1
+        ExtendedFloat::fract(self)
     }
 
     #[inline]
-    fn abs(mut self) -> ExtendedFloat {
-        unsafe { sys::extended_abs(self.as_mut_ptr()) }
-        self
+    fn abs(self) -> Self {
+        ExtendedFloat::abs(self)
     }
 
     #[inline]
@@ -280,18 +364,18 @@ impl Float for ExtendedFloat {
         } else if self.is_sign_positive() {
             extended_float!(1)
         } else {
-            extended_float!(0)
+            extended_float!(-1)
         }
     }
 
     #[inline]
     fn is_sign_positive(self) -> bool {
-        unsafe { sys::extended_signbit(self.as_ptr()) == 0 }
+        ExtendedFloat::is_sign_positive(self)
     }
 
     #[inline]
     fn is_sign_negative(self) -> bool {
-        !self.is_sign_positive()
+        ExtendedFloat::is_sign_negative(self)
     }
 
     #[inline]
@@ -321,9 +405,8 @@ impl Float for ExtendedFloat {
     }
 
     #[inline]
-    fn sqrt(mut self) -> ExtendedFloat {
-        unsafe { sys::extended_sqrt(self.as_mut_ptr()) };
-        self
+    fn sqrt(self) -> Self {
+        ExtendedFloat::sqrt(self)
     }
 
     #[inline]
@@ -362,15 +445,13 @@ impl Float for ExtendedFloat {
     }
 
     #[inline]
-    fn max(mut self, other: ExtendedFloat) -> ExtendedFloat {
-        unsafe { sys::extended_max(self.as_mut_ptr(), other.as_ptr()) };
-        self
+    fn max(self, other: Self) -> Self {
+        ExtendedFloat::max(self, other)
     }
 
     #[inline]
-    fn min(mut self, other: ExtendedFloat) -> ExtendedFloat {
-        unsafe { sys::extended_min(self.as_mut_ptr(), other.as_ptr()) };
-        self
+    fn min(self, other: Self) -> Self {
+        ExtendedFloat::min(self, other)
     }
 
     #[inline]
@@ -471,13 +552,13 @@ impl Float for ExtendedFloat {
 
     #[inline]
     fn asinh(mut self) -> Self {
-        unsafe { sys::extended_sinh(self.as_mut_ptr()) };
+        unsafe { sys::extended_asinh(self.as_mut_ptr()) };
         self
     }
 
     #[inline]
     fn acosh(mut self) -> Self {
-        unsafe { sys::extended_cosh(self.as_mut_ptr()) };
+        unsafe { sys::extended_acosh(self.as_mut_ptr()) };
         self
     }
 
@@ -487,8 +568,23 @@ impl Float for ExtendedFloat {
         self
     }
 
+    /// Multiplies by the rounded value of `180/pi`,
+    /// unlike the default which can overflow (and rounds twice) since it multiplies by 90 first.
+    #[inline]
+    fn to_degrees(self) -> Self {
+        self * extended_float!("57.295779513082320876798154814105170332405472466564321549160243861")
+    }
+
+    #[inline]
+    fn to_radians(self) -> Self {
+        self * extended_float!("0.017453292519943295769236907684886127134428718885417254560971914402")
+    }
+
     fn integer_decode(self) -> (u64, i16, i8) {
-        unimplemented!()
+        let (negative, exponent, significand) = sys::soft::split(sys::ExtendedFloat(self.0));
+        // Subnormals have the same scale as the smallest normal values, since the integer bit is explicit
+        let exponent = (exponent.max(1) as i16) - 16383 - 63;
+        (significand, exponent, if negative { -1 } else { 1 })
     }
 }
 impl From<f64> for ExtendedFloat {
@@ -664,7 +760,7 @@ impl PartialOrd for ExtendedFloat {
                 1 => Some(Ordering::Greater),
                 0 => Some(Ordering::Equal),
                 -1 => Some(Ordering::Less),
-                -2 => None,
+                2 => None,
                 code => {
                     if cfg!(debug_assertions) {
                         panic!("Unexpected code: {}", code)
@@ -687,55 +783,121 @@ impl Neg for ExtendedFloat {
     }
 }
 
+/// Writes the shortest decimal that parses back to the same value, without an exponent.
+///
+/// With a precision this rounds to that many decimal places instead (with ties to even).
+/// All the usual flags work, and NaN and the infinities are written as `NaN`, `inf` and `-inf`.
 impl Display for ExtendedFloat {
     #[inline]
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        self.write(f.width(), f.precision(), f)
+        format::decimal(*self, f, false)
     }
 }
+/// Just like `Display`, except whole numbers still get a `.0`.
+///
+/// This always parses back to exactly the same value (unless it's NaN), as long as there's no precision.
 impl Debug for ExtendedFloat {
     #[inline]
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        self.write(f.width(), f.precision(), f)
+        format::decimal(*self, f, true)
+    }
+}
+/// Writes the shortest decimal that parses back to the same value in scientific notation, like `1.5e3`.
+///
+/// With a precision this has that many digits after the point instead.
+impl LowerExp for ExtendedFloat {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        format::exponent(*self, f, false)
+    }
+}
+impl UpperExp for ExtendedFloat {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        format::exponent(*self, f, true)
+    }
+}
+/// Writes the exact value as a hex float like `0xc.ccccccccccccccdp-7`, which is how `printf("%La")` does it on x86.
+///
+/// The first hex digit holds the integer bit, and a precision gives the number of hex digits after the point.
+impl LowerHex for ExtendedFloat {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        format::hex(*self, f, false)
+    }
+}
+impl UpperHex for ExtendedFloat {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        format::hex(*self, f, true)
     }
 }
+/// Parses a decimal or a C99 hex float like `0x1.8p3`, which is correctly rounded.
+///
+/// This also accepts `inf`, `infinity` and `nan` (with an optional payload like `nan(0x123)`),
+/// ignoring case, and everything can have a sign.
 impl FromStr for ExtendedFloat {
     type Err = ExtendedFloatParseError;
 
     fn from_str(s: &str) -> Result<Self, ExtendedFloatParseError> {
-        if s.is_empty() {
-            Err(ExtendedFloatParseError::Empty)
-        } else if s.chars().next().unwrap().is_whitespace() {
-            Err(ExtendedFloatParseError::LeadingWhitespace(s.chars().next().unwrap()))
-        } else {
-            let data = CString::new(s.as_bytes())?;
-            let mut end = ptr::null_mut();
-            unsafe {
-                let mut out: ExtendedFloat = mem::uninitialized();
-                sys::extended_parse(out.as_mut_ptr(), data.as_ptr(), &mut end);
-                let consumed_bytes = end.offset_from(data.as_ptr()) as usize;
-                assert!(consumed_bytes <= s.len());
-                if consumed_bytes != s.len() {
-                    Err(ExtendedFloatParseError::TrailingChars(s.len() - consumed_bytes))
-                } else {
-                    Ok(out)
-                }
-            }
+        match s.chars().next() {
+            None => return Err(ExtendedFloatParseError::Empty),
+            Some(c) if c.is_whitespace() => return Err(ExtendedFloatParseError::LeadingWhitespace(c)),
+            Some(_) => {}
+        }
+        let sign = ExtendedFloatParseError::sign_length(s);
+        let end = parse::scan(&s.as_bytes()[sign..])
+            .map_err(|offset| ExtendedFloatParseError::InvalidFloat(sign + offset))?;
+        if sign + end != s.len() {
+            return Err(ExtendedFloatParseError::TrailingChars(s.len() - sign - end))
         }
+        let (value, consumed_bytes) = sys::soft::parse(s.as_bytes());
+        debug_assert_eq!(consumed_bytes, s.len());
+        Ok(ExtendedFloat(value.0))
     }
 }
 
-#[derive(Debug)]
+/// An error parsing an `ExtendedFloat`, from either `FromStr` or `Num::from_str_radix`
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ExtendedFloatParseError {
     Empty,
-    NullByte,
     LeadingWhitespace(char),
+    /// The text starts with a valid number, but there are this many bytes left over after it
     TrailingChars(usize),
-    InvalidFloat
+    /// The text isn't a valid number, where the byte at this offset is the first one that doesn't fit
+    ///
+    /// This is the length of the text if it ends too early (like `1e+`).
+    InvalidFloat(usize)
+}
+impl ExtendedFloatParseError {
+    /// The length of the optional sign at the start of the text
+    #[inline]
+    fn sign_length(s: &str) -> usize {
+        if s.starts_with('+') || s.starts_with('-') { 1 } else { 0 }
+    }
+    fn message(&self) -> &'static str {
+        match *self {
+            ExtendedFloatParseError::Empty => "cannot parse float from empty string",
+            ExtendedFloatParseError::LeadingWhitespace(_) => "unexpected whitespace before float",
+            ExtendedFloatParseError::TrailingChars(_) => "unexpected characters after float",
+            ExtendedFloatParseError::InvalidFloat(_) => "invalid float literal",
+        }
+    }
 }
-impl From<NulError> for ExtendedFloatParseError {
-    #[inline]
-    fn from(_: NulError) -> Self {
-        ExtendedFloatParseError::NullByte
+impl Display for ExtendedFloatParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(self.message())?;
+        match *self {
+            ExtendedFloatParseError::Empty => Ok(()),
+            ExtendedFloatParseError::LeadingWhitespace(c) => write!(f, ": {:?}", c),
+            ExtendedFloatParseError::TrailingChars(count) => write!(f, ": {} bytes left over", count),
+            ExtendedFloatParseError::InvalidFloat(offset) => write!(f, " at byte {}", offset),
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl ::std::error::Error for ExtendedFloatParseError {
+    fn description(&self) -> &str {
+        self.message()
     }
 }
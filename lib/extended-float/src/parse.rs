@@ -0,0 +1,84 @@
+//! Checks the syntax of numbers before they're parsed, so the errors can say where they went wrong.
+//!
+//! The conversion itself is done by `sys::soft::parse`, which is correctly rounded
+//! but just stops at the first byte that doesn't fit (like `strtold` does).
+
+/// Checks if the text starts with the keyword, ignoring case
+fn keyword(text: &[u8], keyword: &[u8]) -> Option<usize> {
+    if text.len() >= keyword.len() && text[..keyword.len()].eq_ignore_ascii_case(keyword) {
+        Some(keyword.len())
+    } else {
+        None
+    }
+}
+
+/// The number of digits at the start of the text
+fn digits(text: &[u8], radix: u32) -> usize {
+    text.iter().take_while(|&&c| (c as char).is_digit(radix)).count()
+}
+
+/// Finds the end of the number at the start of the text, which has already had its sign removed.
+///
+/// This accepts everything `sys::soft::parse` does: decimals, hex floats like `0x1.8p3`,
+/// `inf` or `infinity` and `nan` with an optional payload, ignoring case.
+/// Where `strtold` would give up and leave a malformed part over (like the `e` in `1e`, or the `x` in `0x`)
+/// this gives the offset of the byte that made it invalid.
+pub fn scan(text: &[u8]) -> Result<usize, usize> {
+    if let Some(length) = keyword(text, b"infinity").or_else(|| keyword(text, b"inf")) {
+        Ok(length)
+    } else if let Some(length) = keyword(text, b"nan") {
+        if text.get(length) != Some(&b'(') {
+            return Ok(length)
+        }
+        let sequence = &text[length + 1..];
+        let end = sequence.iter()
+            .position(|&c| !(c.is_ascii_alphanumeric() || c == b'_'))
+            .unwrap_or(sequence.len());
+        if sequence.get(end) == Some(&b')') {
+            Ok(length + end + 2)
+        } else {
+            Err(length + end + 1)
+        }
+    } else if text.len() >= 2 && text[0] == b'0' && (text[1] == b'x' || text[1] == b'X') {
+        scan_radix(&text[2..], 16)
+            .map(|length| length + 2)
+            .map_err(|offset| offset + 2)
+    } else {
+        scan_radix(text, 10)
+    }
+}
+
+/// Finds the end of the digits in the radix, which can have a fractional part and an exponent.
+///
+/// Just like `sys::soft::parse_radix`, only decimals can have an exponent (with `e`)
+/// and hexadecimal numbers can have a binary one (with `p`).
+pub fn scan_radix(text: &[u8], radix: u32) -> Result<usize, usize> {
+    let integer = digits(text, radix);
+    let mut index = integer;
+    if text.get(index) == Some(&b'.') {
+        let fraction = digits(&text[index + 1..], radix);
+        index += fraction + 1;
+        if integer == 0 && fraction == 0 {
+            return Err(index)
+        }
+    } else if integer == 0 {
+        return Err(0)
+    }
+    let marker = match radix {
+        10 => b'e',
+        16 => b'p',
+        _ => return Ok(index)
+    };
+    match text.get(index) {
+        Some(c) if c.eq_ignore_ascii_case(&marker) => index += 1,
+        _ => return Ok(index)
+    }
+    match text.get(index) {
+        Some(&b'+') | Some(&b'-') => index += 1,
+        _ => {}
+    }
+    match digits(&text[index..], 10) {
+        0 => Err(index),
+        length => Ok(index + length)
+    }
+}
@@ -5,6 +5,7 @@ extern crate extended_float_macros;
 extern crate num_traits;
 
 use num_traits::Float;
+use std::cmp::Ordering;
 use extended_float::consts::{PI, FRAC_PI_2};
 use extended_float::ExtendedFloat;
 use extended_float_macros::extended_float;
@@ -133,7 +134,11 @@ fn trunc() {
 #[test]
 fn exp() {
     assert_eq!(extended_float!(-0.0).exp(), extended_float!(1));
+    // glibc is an ulp off here, while the pure Rust version is correctly rounded
+    #[cfg(not(feature = "soft-float"))]
     assert_eq_precise!(extended_float!(25).exp(), extended_float!("72004899337.385872528"));
+    #[cfg(feature = "soft-float")]
+    assert_eq_precise!(extended_float!(25).exp(), extended_float!("72004899337.3858725205"));
     assert_eq_precise!(extended_float!(-25).exp(), extended_float!("1.3887943864964020595e-11"));
     assert_eq_precise!(extended_float!(-0.5).exp(), extended_float!("0.60653065971263342361"));
     assert_eq_precise!(extended_float!(0.5).exp(), extended_float!("1.6487212707001281469"));
@@ -177,3 +182,22 @@ fn eq() {
     assert_eq!(extended_float!("inf"), extended_float!("inf"));
 }
 
+#[test]
+fn cmp() {
+    assert_eq!(extended_float!(1).partial_cmp(&extended_float!(2)), Some(Ordering::Less));
+    assert_eq!(extended_float!(-0.0).partial_cmp(&extended_float!(0)), Some(Ordering::Equal));
+    assert_eq!(extended_float!("inf").partial_cmp(&extended_float!(2)), Some(Ordering::Greater));
+    assert_eq!(extended_float!("nan").partial_cmp(&extended_float!(1)), None);
+    assert_eq!(extended_float!(1).partial_cmp(&extended_float!("nan")), None);
+}
+
+#[test]
+fn display_hex() {
+    assert_eq!(format!("{:x}", extended_float!(1)), "0x8p-3");
+    assert_eq!(format!("{:x}", extended_float!(-1.5)), "-0xcp-3");
+    assert_eq!(format!("{:x}", extended_float!("0.1")), "0xc.ccccccccccccccdp-7");
+    assert_eq!(format!("{:x}", extended_float!(-0.0)), "-0x0p+0");
+    assert_eq!(format!("{:x}", extended_float!("-inf")), "-inf");
+    assert_eq!(format!("{:x}", extended_float!("nan")), "NaN");
+    assert_eq!("0xc.ccccccccccccccdp-7".parse::<ExtendedFloat>().unwrap(), extended_float!("0.1"));
+}
@@ -0,0 +1,369 @@
+//! Checks every `Float` method against MPFR, computing the exact result at 64 bits of precision.
+//!
+//! The basic operations are correctly rounded, so they have to give exactly the same bits.
+//! The transcendental functions aren't (in glibc or the pure Rust versions), so they get a tolerance in ulps,
+//! which is one more than the worst error measured against glibc.
+#![feature(proc_macro, proc_macro_non_items)]
+extern crate extended_float;
+extern crate extended_float_macros;
+extern crate num_traits;
+#[macro_use]
+extern crate quickcheck;
+extern crate rug;
+
+use std::cmp::Ordering;
+use std::num::FpCategory;
+
+use num_traits::Float;
+use quickcheck::{Arbitrary, Gen};
+use rug::float::{Constant, Round, Special};
+use rug::ops::{AssignRound, Pow};
+use rug::Float as BigFloat;
+
+use extended_float::{consts, ExtendedFloat};
+use extended_float_macros::extended_float;
+
+/// The bits in the significand, including the explicit integer bit
+const PRECISION: u32 = 64;
+/// The exponent of the smallest normal value, in MPFR's convention where the significand is in `[0.5, 1)`
+const MIN_EXPONENT: i32 = -16381;
+/// The exponent of the largest finite values, in MPFR's convention
+const MAX_EXPONENT: i32 = 16384;
+/// The precision for the references that MPFR can't round correctly in a single step
+const REFERENCE_PRECISION: u32 = 256;
+
+fn join(negative: bool, exponent: u16, significand: u64) -> ExtendedFloat {
+    let mut bits = [0; 10];
+    for (index, byte) in bits[..8].iter_mut().enumerate() {
+        *byte = (significand >> (index * 8)) as u8;
+    }
+    let top = (negative as u16) << 15 | exponent;
+    bits[8] = top as u8;
+    bits[9] = (top >> 8) as u8;
+    ExtendedFloat::from_bits(bits)
+}
+
+fn split(value: ExtendedFloat) -> (bool, u16, u64) {
+    let bits = value.to_bits();
+    let significand = bits[..8].iter().rev().fold(0, |acc, &byte| acc << 8 | byte as u64);
+    let top = (bits[9] as u16) << 8 | bits[8] as u16;
+    (top >> 15 != 0, top & 0x7FFF, significand)
+}
+
+/// Converts to MPFR exactly
+fn to_big(value: ExtendedFloat) -> BigFloat {
+    if value.is_nan() {
+        return BigFloat::with_val(PRECISION, Special::Nan)
+    }
+    let result = if value.is_infinite() {
+        BigFloat::with_val(PRECISION, Special::Infinity)
+    } else {
+        let (mantissa, exponent, _) = value.integer_decode();
+        BigFloat::with_val(PRECISION, mantissa) << exponent as i32
+    };
+    if value.is_sign_negative() { -result } else { result }
+}
+
+/// Encodes a value that MPFR has already rounded to the precision and exponent range
+fn from_big(value: &BigFloat) -> ExtendedFloat {
+    if value.is_nan() {
+        return consts::NAN
+    }
+    let (exponent, significand) = if value.is_infinite() || value.get_exp().map_or(false, |exp| exp > MAX_EXPONENT) {
+        (0x7FFF, 1 << 63)
+    } else if value.is_zero() {
+        (0, 0)
+    } else {
+        let (mantissa, exponent) = value.to_integer_exp().unwrap();
+        let mantissa = mantissa.abs().to_u64().unwrap();
+        // Move the leading bit into the integer bit
+        let shift = mantissa.leading_zeros();
+        let biased = exponent - shift as i32 + 63 + 16383;
+        if biased >= 1 {
+            (biased as u16, mantissa << shift)
+        } else {
+            (0, (mantissa << shift).checked_shr((1 - biased) as u32).unwrap_or(0))
+        }
+    };
+    join(value.is_sign_negative(), exponent, significand)
+}
+
+/// Rounds the result of the computation to the nearest extended precision value
+///
+/// MPFR gives the correctly rounded result at the full precision,
+/// which is then rounded again for subnormals (using the direction of the first rounding).
+fn nearest<T>(computation: T) -> ExtendedFloat where BigFloat: AssignRound<T, Round = Round, Ordering = Ordering> {
+    let (mut result, ordering) = BigFloat::with_val_round(PRECISION, computation, Round::Nearest);
+    result.subnormalize_round(MIN_EXPONENT, ordering, Round::Nearest);
+    from_big(&result)
+}
+
+/// Maps the value to an integer so the adjacent values differ by one
+///
+/// The integer bit is implicit except for subnormals, so it's removed by the exponent's offset.
+/// Infinity comes right after the largest finite value, and both zeros are the same.
+fn key(value: ExtendedFloat) -> i128 {
+    let (negative, exponent, significand) = split(value);
+    let magnitude = ((exponent.max(1) as i128 - 1) << 63) + significand as i128;
+    if negative { -magnitude } else { magnitude }
+}
+
+/// Checks the result is within the tolerance, printing the details if it's not
+///
+/// A tolerance of zero requires exactly the same bits, including the sign of zero.
+/// The NaNs are all considered the same, since their payloads are unspecified.
+fn check(name: &str, inputs: &[ExtendedFloat], actual: ExtendedFloat, expected: ExtendedFloat, tolerance: u32) -> bool {
+    let error = if actual.is_nan() || expected.is_nan() {
+        if actual.is_nan() && expected.is_nan() { 0 } else { u128::max_value() }
+    } else if tolerance == 0 && actual.to_bits() != expected.to_bits() {
+        1
+    } else {
+        (key(actual) - key(expected)).abs() as u128
+    };
+    let valid = error <= tolerance as u128;
+    if !valid {
+        eprintln!(
+            "{}{:?} gave {:?} but expected {:?} ({} ulps)",
+            name, inputs, actual, expected, error
+        );
+    }
+    valid
+}
+
+fn category(value: &BigFloat) -> FpCategory {
+    if value.is_nan() {
+        FpCategory::Nan
+    } else if value.is_infinite() {
+        FpCategory::Infinite
+    } else if value.is_zero() {
+        FpCategory::Zero
+    } else if value.get_exp().unwrap() < MIN_EXPONENT {
+        FpCategory::Subnormal
+    } else {
+        FpCategory::Normal
+    }
+}
+
+fn check_unary(x: ExtendedFloat) -> bool {
+    let big = to_big(x);
+    let degrees = BigFloat::with_val(REFERENCE_PRECISION, &big * 180) / BigFloat::with_val(REFERENCE_PRECISION, Constant::Pi);
+    let radians = BigFloat::with_val(REFERENCE_PRECISION, Constant::Pi) * &big / 180;
+    let checks = [
+        // The correctly rounded ones
+        ("sqrt", x.sqrt(), nearest(big.sqrt_ref()), 0),
+        ("recip", x.recip(), nearest(big.recip_ref()), 0),
+        ("floor", x.floor(), nearest(big.floor_ref()), 0),
+        ("ceil", x.ceil(), nearest(big.ceil_ref()), 0),
+        ("round", x.round(), nearest(big.round_ref()), 0),
+        ("trunc", x.trunc(), nearest(big.trunc_ref()), 0),
+        ("fract", x.fract(), nearest(big.fract_ref()), 0),
+        ("abs", x.abs(), nearest(big.abs_ref()), 0),
+        ("signum", x.signum(), nearest(big.signum_ref()), 0),
+        ("neg", -x, nearest(-&big), 0),
+        // The ones from the C library
+        ("cbrt", x.cbrt(), nearest(big.cbrt_ref()), 2),
+        ("exp", x.exp(), nearest(big.exp_ref()), 2),
+        ("exp2", x.exp2(), nearest(big.exp2_ref()), 2),
+        ("exp_m1", x.exp_m1(), nearest(big.exp_m1_ref()), 3),
+        ("ln", x.ln(), nearest(big.ln_ref()), 2),
+        ("ln_1p", x.ln_1p(), nearest(big.ln_1p_ref()), 3),
+        ("log2", x.log2(), nearest(big.log2_ref()), 2),
+        ("log10", x.log10(), nearest(big.log10_ref()), 2),
+        ("sin", x.sin(), nearest(big.sin_ref()), 2),
+        ("cos", x.cos(), nearest(big.cos_ref()), 2),
+        ("tan", x.tan(), nearest(big.tan_ref()), 2),
+        ("sin_cos.0", x.sin_cos().0, nearest(big.sin_ref()), 2),
+        ("sin_cos.1", x.sin_cos().1, nearest(big.cos_ref()), 2),
+        ("asin", x.asin(), nearest(big.asin_ref()), 2),
+        ("acos", x.acos(), nearest(big.acos_ref()), 2),
+        ("atan", x.atan(), nearest(big.atan_ref()), 2),
+        ("sinh", x.sinh(), nearest(big.sinh_ref()), 3),
+        ("cosh", x.cosh(), nearest(big.cosh_ref()), 2),
+        ("tanh", x.tanh(), nearest(big.tanh_ref()), 3),
+        ("asinh", x.asinh(), nearest(big.asinh_ref()), 3),
+        ("acosh", x.acosh(), nearest(big.acosh_ref()), 3),
+        ("atanh", x.atanh(), nearest(big.atanh_ref()), 3),
+        ("to_degrees", x.to_degrees(), nearest(&degrees), 2),
+        ("to_radians", x.to_radians(), nearest(&radians), 2),
+    ];
+    checks.iter()
+        .filter(|&&(name, actual, expected, tolerance)| !check(name, &[x], actual, expected, tolerance))
+        .count() == 0
+}
+
+fn check_binary(x: ExtendedFloat, y: ExtendedFloat) -> bool {
+    let (first, second) = (to_big(x), to_big(y));
+    let log = BigFloat::with_val(REFERENCE_PRECISION, first.ln_ref()) / BigFloat::with_val(REFERENCE_PRECISION, second.ln_ref());
+    let checks = [
+        ("add", x + y, nearest(&first + &second), 0),
+        ("sub", x - y, nearest(&first - &second), 0),
+        ("mul", x * y, nearest(&first * &second), 0),
+        ("div", x / y, nearest(&first / &second), 0),
+        ("rem", x % y, nearest(&first % &second), 0),
+        ("abs_sub", x.abs_sub(y), nearest(first.positive_diff_ref(&second)), 0),
+        ("powf", x.powf(y), nearest((&first).pow(&second)), 2),
+        ("hypot", x.hypot(y), nearest(first.hypot_ref(&second)), 1),
+        ("atan2", x.atan2(y), nearest(first.atan2_ref(&second)), 2),
+        ("log", x.log(y), nearest(&log), 3),
+    ];
+    // The C library and MPFR disagree on which zero is bigger, so only the values have to match
+    let same_value = |name, actual: ExtendedFloat, expected: ExtendedFloat| {
+        actual == expected || check(name, &[x, y], actual, expected, 0)
+    };
+    let valid = checks.iter()
+        .filter(|&&(name, actual, expected, tolerance)| !check(name, &[x, y], actual, expected, tolerance))
+        .count() == 0;
+    valid && same_value("max", x.max(y), nearest(first.max_ref(&second))) &&
+        same_value("min", x.min(y), nearest(first.min_ref(&second)))
+}
+
+/// An arbitrary value, which is biased towards the interesting ones
+///
+/// Unlike the differential tests for the soft-float this only gives the canonical encodings,
+/// since those are the only ones that are real floating point values.
+#[derive(Copy, Clone, Debug)]
+struct Value(ExtendedFloat);
+
+/// Picks a random number in the range, which must not be empty
+fn pick<G: Gen>(g: &mut G, start: u32, end: u32) -> u32 {
+    start + g.next_u32() % (end - start)
+}
+
+impl Arbitrary for Value {
+    fn arbitrary<G: Gen>(g: &mut G) -> Value {
+        let negative = g.next_u32() & 1 != 0;
+        let bits = g.next_u64();
+        let shift = pick(g, 1, 64);
+        let (exponent, significand) = match pick(g, 0, 16) {
+            0 => (0, 0),
+            1 => (0x7FFF, 1 << 63),
+            2 => (0x7FFF, 3 << 62),
+            3 => (0, bits >> shift),
+            // Close to overflow and underflow
+            4 => (pick(g, 1, 70), (1 << 63) | bits),
+            5 => (pick(g, 0x7FFF - 70, 0x7FFF), (1 << 63) | bits),
+            // Anywhere at all
+            6 => (pick(g, 1, 0x7FFF), (1 << 63) | bits),
+            // Close to one, where most of the inverse functions are interesting
+            7 | 8 => (pick(g, 0x3FFF - 3, 0x3FFF + 3), (1 << 63) | bits),
+            // Only a few bits set, which makes for integers, ties and exact results
+            9 | 10 => (pick(g, 0x3FFF - 70, 0x3FFF + 70), (1 << 63) | (bits >> shift << 32)),
+            _ => (pick(g, 0x3FFF - 70, 0x3FFF + 70), (1 << 63) | bits),
+        };
+        Value(join(negative, exponent as u16, significand))
+    }
+}
+
+/// A pair of values, which often have similar magnitudes so they interact in interesting ways
+#[derive(Copy, Clone, Debug)]
+struct Pair(Value, Value);
+impl Arbitrary for Pair {
+    fn arbitrary<G: Gen>(g: &mut G) -> Pair {
+        let first = Value::arbitrary(g);
+        let second = if g.next_u32() % 3 == 0 {
+            let (_, exponent, significand) = split(first.0);
+            let exponent = (exponent as i32 + pick(g, 0, 140) as i32 - 70).max(1).min(0x7FFE) as u16;
+            let significand = if g.next_u32() & 1 != 0 { significand ^ (1 << pick(g, 0, 64)) } else { significand };
+            Value(join(g.next_u32() & 1 != 0, exponent, significand | (1 << 63)))
+        } else {
+            Value::arbitrary(g)
+        };
+        Pair(first, second)
+    }
+}
+
+quickcheck! {
+    fn unary(value: Value) -> bool {
+        check_unary(value.0)
+    }
+
+    fn binary(pair: Pair) -> bool {
+        let Pair(Value(first), Value(second)) = pair;
+        check_binary(first, second) && check_binary(second, first)
+    }
+
+    fn mul_add(pair: Pair, addend: Value, cancel: bool) -> bool {
+        let Pair(Value(first), Value(second)) = pair;
+        // Cancelling the rounded product leaves just its rounding error
+        let addend = if cancel { -(first * second) } else { addend.0 };
+        let expected = nearest(&to_big(first) * &to_big(second) + &to_big(addend));
+        check("mul_add", &[first, second, addend], first.mul_add(second, addend), expected, 0)
+    }
+
+    fn powi(value: Value, exponent: i32, small: bool) -> bool {
+        let exponent = if small { exponent % 100 } else { exponent };
+        let expected = nearest((&to_big(value.0)).pow(exponent));
+        check("powi", &[value.0, ExtendedFloat::from(exponent)], value.0.powi(exponent), expected, 3)
+    }
+
+    fn classify(value: Value) -> bool {
+        let value = value.0;
+        let big = to_big(value);
+        value.classify() == category(&big) &&
+            value.is_nan() == big.is_nan() &&
+            value.is_infinite() == big.is_infinite() &&
+            value.is_finite() == big.is_finite() &&
+            value.is_normal() == (category(&big) == FpCategory::Normal) &&
+            // MPFR doesn't keep the sign of NaNs
+            (big.is_nan() || value.is_sign_negative() == big.is_sign_negative()) &&
+            (big.is_nan() || value.is_sign_positive() == big.is_sign_positive())
+    }
+
+    fn integer_decode(value: Value) -> bool {
+        let value = value.0;
+        let (mantissa, _, _) = value.integer_decode();
+        value.is_nan() || (
+            from_big(&to_big(value)).to_bits() == value.to_bits() &&
+                (mantissa >> 63 == 1) == (value.is_normal() || value.is_infinite())
+        )
+    }
+}
+
+#[test]
+fn special_values() {
+    let mut values = vec![
+        extended_float!(0), extended_float!(0.5), extended_float!(1), extended_float!(2), extended_float!(3),
+        extended_float!(10), extended_float!(0.1), extended_float!(1e300), extended_float!(1e-300),
+        extended_float!(11355.5), extended_float!(-11400), consts::PI, consts::FRAC_PI_2, consts::E,
+        consts::NAN, consts::INFINITY, consts::MAX, consts::MIN_POSITIVE, consts::EPSILON,
+        consts::MIN_POSITIVE / extended_float!(3), extended_float!(1) - consts::EPSILON / extended_float!(2),
+        extended_float!(1) + consts::EPSILON,
+    ];
+    let negated: Vec<ExtendedFloat> = values.iter().map(|&value| -value).collect();
+    values.extend(negated);
+    let mut valid = true;
+    for &first in &values {
+        valid &= check_unary(first);
+        for &second in &values {
+            valid &= check_binary(first, second);
+        }
+        for exponent in -20..20 {
+            let expected = nearest((&to_big(first)).pow(exponent));
+            valid &= check("powi", &[first, ExtendedFloat::from(exponent)], first.powi(exponent), expected, 3);
+        }
+    }
+    assert!(valid, "Some of the special values didn't match MPFR");
+}
+
+#[test]
+fn constants() {
+    let one = || BigFloat::with_val(PRECISION, 1);
+    let max = from_big(&(BigFloat::with_val(PRECISION, u64::max_value()) << 16320));
+    let checks = [
+        ("infinity", <ExtendedFloat as Float>::infinity(), from_big(&BigFloat::with_val(PRECISION, Special::Infinity))),
+        ("neg_infinity", <ExtendedFloat as Float>::neg_infinity(), from_big(&BigFloat::with_val(PRECISION, Special::NegInfinity))),
+        ("neg_zero", <ExtendedFloat as Float>::neg_zero(), from_big(&BigFloat::with_val(PRECISION, Special::NegZero))),
+        ("max_value", <ExtendedFloat as Float>::max_value(), max),
+        ("min_value", <ExtendedFloat as Float>::min_value(), -max),
+        ("min_positive_value", <ExtendedFloat as Float>::min_positive_value(), from_big(&(one() >> 16382))),
+        ("epsilon", <ExtendedFloat as Float>::epsilon(), from_big(&(one() >> 63))),
+        ("PI", consts::PI, nearest(Constant::Pi)),
+        ("LN_2", consts::LN_2, nearest(Constant::Log2)),
+        ("E", consts::E, nearest(one().exp_ref())),
+    ];
+    assert!(<ExtendedFloat as Float>::nan().is_nan());
+    let valid = checks.iter()
+        .filter(|&&(name, actual, expected)| !check(name, &[], actual, expected, 0))
+        .count() == 0;
+    assert!(valid, "Some of the constants didn't match MPFR");
+}
@@ -0,0 +1,168 @@
+#![feature(proc_macro, proc_macro_non_items)]
+extern crate extended_float;
+extern crate extended_float_macros;
+#[macro_use]
+extern crate quickcheck;
+
+use extended_float::{consts, ExtendedFloat};
+use extended_float_macros::extended_float;
+
+/// The smallest subnormal, which has the longest exact expansion
+fn smallest() -> ExtendedFloat {
+    "0x1p-16445".parse().unwrap()
+}
+
+#[test]
+fn shortest() {
+    assert_eq!(extended_float!("0.1").to_string(), "0.1");
+    assert_eq!(extended_float!(1).to_string(), "1");
+    assert_eq!(extended_float!(-2.25).to_string(), "-2.25");
+    assert_eq!(extended_float!("0.001").to_string(), "0.001");
+    assert_eq!(extended_float!(1e20).to_string(), "100000000000000000000");
+    assert_eq!(extended_float!(0).to_string(), "0");
+    assert_eq!(extended_float!(-0.0).to_string(), "-0");
+    // The double closest to 0.1 isn't the same as the extended one
+    assert_eq!(ExtendedFloat::from(0.1f64).to_string(), "0.10000000000000000555");
+    assert_eq!(consts::PI.to_string(), "3.1415926535897932385");
+    assert_eq!(format!("{:e}", consts::MAX), "1.189731495357231765e4932");
+    assert_eq!(format!("{:e}", consts::MIN_POSITIVE), "3.3621031431120935063e-4932");
+    assert_eq!(format!("{:e}", smallest()), "4e-4951");
+    assert_eq!(consts::MAX.to_string().len(), 4933);
+}
+
+#[test]
+fn debug() {
+    assert_eq!(format!("{:?}", extended_float!(1)), "1.0");
+    assert_eq!(format!("{:?}", extended_float!(-0.0)), "-0.0");
+    assert_eq!(format!("{:?}", extended_float!(1.5)), "1.5");
+    assert_eq!(format!("{:?}", extended_float!(1e20)), "100000000000000000000.0");
+    assert_eq!(format!("{:?}", extended_float!("0.1")), "0.1");
+    assert_eq!(format!("{:.2?}", extended_float!(1)), "1.00");
+}
+
+#[test]
+fn special() {
+    assert_eq!(consts::INFINITY.to_string(), "inf");
+    assert_eq!(format!("{:?}", consts::NEG_INFINITY), "-inf");
+    assert_eq!(format!("{:e}", consts::NEG_INFINITY), "-inf");
+    assert_eq!(consts::NAN.to_string(), "NaN");
+    assert_eq!(format!("{:?}", -consts::NAN), "NaN");
+    assert_eq!(format!("{:x}", consts::NAN), "NaN");
+}
+
+#[test]
+fn exponent() {
+    assert_eq!(format!("{:e}", extended_float!(1500)), "1.5e3");
+    assert_eq!(format!("{:E}", extended_float!(1500)), "1.5E3");
+    assert_eq!(format!("{:e}", extended_float!("0.001")), "1e-3");
+    assert_eq!(format!("{:e}", extended_float!(-1)), "-1e0");
+    assert_eq!(format!("{:e}", extended_float!(0)), "0e0");
+    assert_eq!(format!("{:.3e}", extended_float!(0)), "0.000e0");
+    assert_eq!(format!("{:.3e}", extended_float!(1)), "1.000e0");
+    assert_eq!(format!("{:.2e}", extended_float!(1234.5)), "1.23e3");
+    assert_eq!(format!("{:.2e}", extended_float!(9999)), "1.00e4");
+    // Ties go to even
+    assert_eq!(format!("{:.0e}", extended_float!(2.5)), "2e0");
+    assert_eq!(format!("{:.0e}", extended_float!(3.5)), "4e0");
+    assert_eq!(format!("{:.1e}", extended_float!(0.125)), "1.2e-1");
+    let long = format!("{:.20000e}", smallest());
+    assert!(long.starts_with("3.64519953188247460252840593361941981639905"));
+    assert!(long.ends_with("000e-4951"));
+}
+
+#[test]
+fn precision() {
+    assert_eq!(format!("{:.3}", extended_float!("0.1")), "0.100");
+    assert_eq!(format!("{:.2}", extended_float!(0.125)), "0.12");
+    assert_eq!(format!("{:.2}", extended_float!(0.375)), "0.38");
+    assert_eq!(format!("{:.0}", extended_float!(0.5)), "0");
+    assert_eq!(format!("{:.0}", extended_float!(1.5)), "2");
+    assert_eq!(format!("{:.0}", extended_float!(2.5)), "2");
+    assert_eq!(format!("{:.1}", extended_float!("0.04")), "0.0");
+    assert_eq!(format!("{:.1}", extended_float!("0.06")), "0.1");
+    assert_eq!(format!("{:.2}", extended_float!("9.999")), "10.00");
+    assert_eq!(format!("{:.3}", extended_float!(-0.0)), "-0.000");
+    assert_eq!(format!("{:.5}", extended_float!("1e-10")), "0.00000");
+    assert_eq!(format!("{:.25}", ExtendedFloat::from(0.1f64)), "0.1000000000000000055511151");
+    // Every digit of the smallest subnormal, which is exact
+    let exact = format!("{:.16445}", smallest());
+    assert_eq!(exact.len(), 16447);
+    assert_eq!(exact.parse::<ExtendedFloat>().unwrap(), smallest());
+    assert_eq!(format!("{:.16444}", smallest()).parse::<ExtendedFloat>().unwrap(), smallest());
+}
+
+#[test]
+fn flags() {
+    assert_eq!(format!("{:+}", extended_float!(1)), "+1");
+    assert_eq!(format!("{:+}", extended_float!(-1)), "-1");
+    assert_eq!(format!("{:+}", extended_float!(0)), "+0");
+    assert_eq!(format!("{:+}", consts::INFINITY), "+inf");
+    assert_eq!(format!("{:+}", consts::NAN), "NaN");
+    assert_eq!(format!("{:+e}", extended_float!(1500)), "+1.5e3");
+    assert_eq!(format!("{:8}", extended_float!(1.5)), "     1.5");
+    assert_eq!(format!("{:>8}", extended_float!(1.5)), "     1.5");
+    assert_eq!(format!("{:<8}|", extended_float!(1.5)), "1.5     |");
+    assert_eq!(format!("{:^9}", extended_float!(1.5)), "   1.5   ");
+    assert_eq!(format!("{:*^8}", extended_float!(-1.5)), "**-1.5**");
+    assert_eq!(format!("{:2}", extended_float!(-1.5)), "-1.5");
+    assert_eq!(format!("{:08.2}", extended_float!(-1.5)), "-0001.50");
+    assert_eq!(format!("{:+010e}", extended_float!(1500)), "+00001.5e3");
+    assert_eq!(format!("{:06}", consts::INFINITY), "   inf");
+    assert_eq!(format!("{:*>6}", consts::NEG_INFINITY), "**-inf");
+    assert_eq!(format!("{:<5}|", consts::NAN), "NaN  |");
+    assert_eq!(format!("{:010x}", extended_float!(1)), "0x00008p-3");
+    assert_eq!(format!("{:>10x}", extended_float!(-1)), "   -0x8p-3");
+}
+
+#[test]
+fn hex() {
+    assert_eq!(format!("{:x}", extended_float!(1.5)), "0xcp-3");
+    assert_eq!(format!("{:X}", extended_float!("0.1")), "0XC.CCCCCCCCCCCCCCDP-7");
+    assert_eq!(format!("{:+x}", extended_float!(2)), "+0x8p-2");
+    assert_eq!(format!("{:x}", extended_float!(0)), "0x0p+0");
+    assert_eq!(format!("{:x}", consts::MAX), "0xf.fffffffffffffffp+16380");
+    assert_eq!(format!("{:x}", consts::MIN_POSITIVE), "0x8p-16385");
+    assert_eq!(format!("{:x}", smallest()), "0x0.000000000000001p-16385");
+    assert_eq!(format!("{:.3x}", extended_float!("0.1")), "0xc.ccdp-7");
+    assert_eq!(format!("{:.2x}", extended_float!(1)), "0x8.00p-3");
+    assert_eq!(format!("{:.2x}", extended_float!(0)), "0x0.00p+0");
+    assert_eq!(format!("{:.20x}", extended_float!(1)), "0x8.00000000000000000000p-3");
+    // Ties go to even, even when that carries into another digit
+    assert_eq!(format!("{:.0x}", extended_float!(8.5)), "0x8p+0");
+    assert_eq!(format!("{:.0x}", extended_float!(9.5)), "0xap+0");
+    assert_eq!(format!("{:.0x}", extended_float!(10.5)), "0xap+0");
+    assert_eq!(format!("{:.0x}", extended_float!(11.5)), "0xcp+0");
+    assert_eq!(format!("{:.0x}", extended_float!(15.5)), "0x1p+4");
+    assert_eq!(format!("{:.1x}", consts::MAX), "0x1.0p+16384");
+}
+
+fn canonical(negative: bool, exponent: u16, significand: u64) -> ExtendedFloat {
+    let exponent = exponent & 0x7FFF;
+    // The integer bit is only set for the normal values
+    let significand = if exponent == 0 { significand & !(1 << 63) } else { significand | (1 << 63) };
+    let mut bits = [0u8; 10];
+    for (index, byte) in bits[..8].iter_mut().enumerate() {
+        *byte = (significand >> (index * 8)) as u8;
+    }
+    let top = exponent | if negative { 0x8000 } else { 0 };
+    bits[8] = top as u8;
+    bits[9] = (top >> 8) as u8;
+    ExtendedFloat::from_bits(bits)
+}
+
+quickcheck! {
+    fn round_trip(negative: bool, exponent: u16, significand: u64) -> bool {
+        let value = canonical(negative, exponent, significand);
+        // Most of the random exponents are enormous, so also try the same bits close to one
+        let close = canonical(negative, 0x3FFF - 80 + exponent % 160, significand);
+        [value, close].iter().all(|&value| {
+            value.is_nan() || [format!("{:?}", value), format!("{:e}", value), format!("{:x}", value)].iter()
+                .all(|text| text.parse::<ExtendedFloat>().unwrap().to_bits() == value.to_bits())
+        })
+    }
+
+    fn subnormal_round_trip(negative: bool, significand: u64) -> bool {
+        let value = canonical(negative, 0, significand >> (significand % 64));
+        format!("{:?}", value).parse::<ExtendedFloat>().unwrap().to_bits() == value.to_bits()
+    }
+}
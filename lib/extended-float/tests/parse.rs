@@ -0,0 +1,107 @@
+#![feature(proc_macro, proc_macro_non_items)]
+extern crate extended_float;
+extern crate extended_float_macros;
+
+use std::error::Error;
+
+use extended_float::{consts, ExtendedFloat, ExtendedFloatParseError};
+use extended_float_macros::extended_float;
+
+fn parse(text: &str) -> ExtendedFloat {
+    match text.parse() {
+        Ok(value) => value,
+        Err(error) => panic!("Unable to parse {:?}: {:?}", text, error)
+    }
+}
+
+fn error(text: &str) -> ExtendedFloatParseError {
+    match text.parse::<ExtendedFloat>() {
+        Ok(value) => panic!("Unexpectedly parsed {:?} as {:?}", text, value),
+        Err(error) => error
+    }
+}
+
+#[test]
+fn decimal() {
+    assert_eq!(parse("1.5"), extended_float!(1.5));
+    assert_eq!(parse("+2.5e-3"), extended_float!("0.0025"));
+    assert_eq!(parse("1E3"), extended_float!(1000));
+    assert_eq!(parse(".5"), extended_float!(0.5));
+    assert_eq!(parse("5."), extended_float!(5));
+    assert_eq!(parse("0.1"), extended_float!("0.1"));
+    assert_eq!(parse("-0").to_bits(), extended_float!(-0.0).to_bits());
+    assert_eq!(parse("1e5000"), consts::INFINITY);
+    assert_eq!(parse("-1e-5000").to_bits(), extended_float!(-0.0).to_bits());
+}
+
+/// Checks the decimals right on and next to the halfway point between two values
+#[test]
+fn exact_decimal() {
+    let even = ExtendedFloat::from(1u64 << 63);
+    let odd = ExtendedFloat::from((1u64 << 63) + 1);
+    assert_eq!(parse("9223372036854775808.5"), even);
+    assert_eq!(parse("9223372036854775808.5000000000000000000000000000000000001"), odd);
+    assert_eq!(parse("9223372036854775809.5"), ExtendedFloat::from((1u64 << 63) + 2));
+    assert_eq!(parse("9223372036854775809.4999999999999999999999999999999999999"), odd);
+    // The smallest subnormal, and halfway between it and zero
+    assert_eq!(parse("3.64519953188247460252840593361941982e-4951"), parse("0x1p-16445"));
+    assert_eq!(parse("1.82259976594123730126420296680970990e-4951"), extended_float!(0));
+    assert_eq!(parse("1.82259976594123730126420296680970991e-4951"), parse("0x1p-16445"));
+}
+
+#[test]
+fn hex() {
+    assert_eq!(parse("0x1.8p3"), extended_float!(12));
+    assert_eq!(parse("-0X.8P1"), extended_float!(-1));
+    assert_eq!(parse("0xc.ccccccccccccccdp-7"), extended_float!("0.1"));
+    assert_eq!(parse("0xAbC"), extended_float!(2748));
+    assert_eq!(parse("0x1.fffffffffffffffep16383"), consts::MAX);
+    assert_eq!(parse("0x1p-16382"), consts::MIN_POSITIVE);
+    assert_eq!(parse("0x1p16384"), consts::INFINITY);
+}
+
+#[test]
+fn special() {
+    for &text in &["inf", "INF", "Infinity", "+inf", "infINITY"] {
+        assert_eq!(parse(text), consts::INFINITY, "{:?}", text);
+    }
+    for &text in &["-inf", "-Infinity"] {
+        assert_eq!(parse(text), consts::NEG_INFINITY, "{:?}", text);
+    }
+    for &text in &["nan", "NaN", "-nan", "+NAN", "nan()", "nan(123)", "NaN(0x7ff)", "nan(abc_1)"] {
+        assert!(parse(text).is_nan(), "{:?}", text);
+    }
+}
+
+#[test]
+fn errors() {
+    assert_eq!(error(""), ExtendedFloatParseError::Empty);
+    assert_eq!(error(" 1"), ExtendedFloatParseError::LeadingWhitespace(' '));
+    assert_eq!(error("\u{a0}1"), ExtendedFloatParseError::LeadingWhitespace('\u{a0}'));
+    for &(text, count) in &[
+        ("1.5x", 1), ("1.2.3", 2), ("inf ", 1), ("infinit", 4), ("nan(1)x", 1), ("1\0", 1), ("1_000", 4),
+        ("0x1.8e3", 0), ("0x1e3p1", 0), ("12e3.5", 2),
+    ] {
+        if count == 0 {
+            assert!(text.parse::<ExtendedFloat>().is_ok(), "{:?}", text);
+        } else {
+            assert_eq!(error(text), ExtendedFloatParseError::TrailingChars(count), "{:?}", text);
+        }
+    }
+    for &(text, offset) in &[
+        ("abc", 0), ("-", 1), (".", 1), ("+.e1", 2), ("--1", 1), ("\0", 0), ("1e", 2), ("1e+", 3), ("1ex", 2),
+        ("0x", 2), ("0x.p1", 3), ("0xp1", 2), ("0x1p", 4), ("nan(", 4), ("nan(a b)", 5), ("in", 0),
+    ] {
+        assert_eq!(error(text), ExtendedFloatParseError::InvalidFloat(offset), "{:?}", text);
+    }
+}
+
+#[test]
+fn error_messages() {
+    assert_eq!(ExtendedFloatParseError::Empty.to_string(), "cannot parse float from empty string");
+    assert_eq!(error("1e+").to_string(), "invalid float literal at byte 3");
+    assert_eq!(error("12ab").to_string(), "unexpected characters after float: 2 bytes left over");
+    assert_eq!(error("\t1").to_string(), "unexpected whitespace before float: '\\t'");
+    let boxed: Box<Error> = Box::new(error("abc"));
+    assert_eq!(boxed.description(), "invalid float literal");
+}
@@ -0,0 +1,113 @@
+#![feature(proc_macro, proc_macro_non_items)]
+extern crate extended_float;
+extern crate extended_float_macros;
+
+extern crate num_traits;
+
+use num_traits::Num;
+use num_traits::Float;
+use extended_float::{ExtendedFloat, ExtendedFloatParseError};
+use extended_float::consts;
+use extended_float_macros::extended_float;
+
+/// Finds a root of the function with Newton's method, where the function gives its value and derivative
+fn newton<T: Float, F: Fn(T) -> (T, T)>(function: F, mut x: T) -> T {
+    for _ in 0..100 {
+        let (value, derivative) = function(x);
+        let next = x - value / derivative;
+        if (next - x).abs() <= T::epsilon() * next.abs() {
+            return next
+        }
+        x = next;
+    }
+    panic!("Newton's method didn't converge")
+}
+
+fn close<T: Float>(actual: T, expected: T) -> bool {
+    (actual - expected).abs() <= T::epsilon() * expected.abs() * T::from(2).unwrap()
+}
+
+#[test]
+fn newton_sqrt() {
+    let square = |x| (x * x - extended_float!(2), extended_float!(2) * x);
+    let root = newton(square, extended_float!(1));
+    assert!(close(root, extended_float!(2).sqrt()), "{:?}", root);
+    assert!(close(root, extended_float!("1.4142135623730950488016887242096980785696718753769")));
+    // The same code works for the primitive floats, which are much less precise
+    let double = newton(|x: f64| (x * x - 2.0, 2.0 * x), 1.0);
+    assert!(close(double, ::std::f64::consts::SQRT_2));
+    assert!((root - ExtendedFloat::from(double)).abs() > consts::EPSILON);
+}
+
+#[test]
+fn newton_transcendental() {
+    // The fixed point of cosine, where `cos(x) = x`
+    let dottie = newton(|x: ExtendedFloat| (x.cos() - x, -x.sin() - extended_float!(1)), extended_float!(1));
+    assert!(close(dottie, extended_float!("0.73908513321516064165531208767387340401341175890075746")), "{:?}", dottie);
+    // The root of `e^x = 10` is `ln(10)`
+    let ln_10 = newton(|x: ExtendedFloat| (x.exp() - extended_float!(10), x.exp()), extended_float!(1));
+    assert!(close(ln_10, consts::LN_10), "{:?}", ln_10);
+}
+
+#[test]
+fn bounds() {
+    let max: ExtendedFloat = Float::max_value();
+    assert!(max.is_finite());
+    assert!((max * extended_float!(2)).is_infinite());
+    assert_eq!(<ExtendedFloat as Float>::min_value(), -max);
+    let tiny: ExtendedFloat = Float::min_positive_value();
+    assert!(tiny.is_normal());
+    assert!(!(tiny / extended_float!(2)).is_normal());
+    assert!(tiny / extended_float!(2) > extended_float!(0));
+    let epsilon: ExtendedFloat = Float::epsilon();
+    assert!(extended_float!(1) + epsilon > extended_float!(1));
+    assert_eq!(extended_float!(1) + epsilon / extended_float!(2), extended_float!(1));
+}
+
+#[test]
+fn integer_decode() {
+    assert_eq!(extended_float!(1).integer_decode(), (1 << 63, -63, 1));
+    assert_eq!(extended_float!(0.5).integer_decode(), (1 << 63, -64, 1));
+    assert_eq!(extended_float!(-3).integer_decode(), (3 << 62, -62, -1));
+    assert_eq!(consts::MAX.integer_decode(), (!0, 16320, 1));
+    assert_eq!(consts::MIN_POSITIVE.integer_decode(), (1 << 63, -16445, 1));
+    assert_eq!((consts::MIN_POSITIVE / extended_float!(4)).integer_decode(), (1 << 61, -16445, 1));
+    assert_eq!(extended_float!(-0.0).integer_decode(), (0, -16445, -1));
+    for &value in &[extended_float!(3.75), extended_float!(-1e300), extended_float!("1e-4000"), consts::PI] {
+        let (mantissa, exponent, sign) = value.integer_decode();
+        let decoded = ExtendedFloat::from(mantissa) * extended_float!(2).powi(exponent as i32);
+        assert_eq!(if sign < 0 { -decoded } else { decoded }, value);
+    }
+}
+
+#[test]
+fn from_str_radix() {
+    assert_eq!(ExtendedFloat::from_str_radix("101.1", 2).unwrap(), extended_float!(5.5));
+    assert_eq!(ExtendedFloat::from_str_radix("-ff.8", 16).unwrap(), extended_float!(-255.5));
+    assert_eq!(ExtendedFloat::from_str_radix("1.8p3", 16).unwrap(), extended_float!(12));
+    assert_eq!(ExtendedFloat::from_str_radix("1e3", 16).unwrap(), extended_float!(483));
+    assert_eq!(ExtendedFloat::from_str_radix("1e3", 10).unwrap(), extended_float!(1000));
+    assert_eq!(ExtendedFloat::from_str_radix("12.5e-1", 10).unwrap(), extended_float!(1.25));
+    assert_eq!(ExtendedFloat::from_str_radix("+z.i", 36).unwrap(), extended_float!(35.5));
+    assert_eq!(ExtendedFloat::from_str_radix("0.1", 3).unwrap(), extended_float!(1) / extended_float!(3));
+    assert_eq!(ExtendedFloat::from_str_radix("0.1", 10).unwrap(), extended_float!("0.1"));
+    assert_eq!(ExtendedFloat::from_str_radix("inf", 7).unwrap(), consts::INFINITY);
+    assert_eq!(ExtendedFloat::from_str_radix("-inf", 7).unwrap(), consts::NEG_INFINITY);
+    assert!(ExtendedFloat::from_str_radix("NaN", 7).unwrap().is_nan());
+    // Numbers past the range overflow to infinity
+    let huge = format!("1{}", "0".repeat(3200));
+    assert_eq!(ExtendedFloat::from_str_radix(&huge, 36).unwrap(), consts::INFINITY);
+}
+
+#[test]
+fn from_str_radix_errors() {
+    let error = |text: &str, radix: u32| ExtendedFloat::from_str_radix(text, radix).unwrap_err();
+    assert_eq!(error("", 10), ExtendedFloatParseError::Empty);
+    assert_eq!(error("12x", 10), ExtendedFloatParseError::TrailingChars(1));
+    assert_eq!(error("1.2.3", 8), ExtendedFloatParseError::TrailingChars(2));
+    // There aren't any prefixes, so this is just a zero
+    assert_eq!(error("0x1", 16), ExtendedFloatParseError::TrailingChars(2));
+    for &(text, radix, offset) in &[(".", 10, 1), ("-", 16, 1), ("2", 2, 0), ("infinity", 10, 0), ("1e+", 10, 3)] {
+        assert_eq!(error(text, radix), ExtendedFloatParseError::InvalidFloat(offset), "{:?}", text);
+    }
+}
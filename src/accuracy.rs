@@ -0,0 +1,306 @@
+//! Measures the error of a result in units in the last place (ulps).
+//!
+//! A fixed absolute threshold like `1e-15` is meaningless once the results get large,
+//! and far too loose for tiny ones.
+//! Counting the representable values between the actual and expected result scales with the magnitude,
+//! which is also how the accuracy of libm implementations is usually specified.
+//! The relative error is available as well, for when the expected value is only known approximately.
+use rug::Float;
+
+use extended_float::ExtendedFloat;
+use float::ToyFloat;
+
+/// A value whose error can be measured in ulps.
+pub trait Ulps {
+    /// Returns the number of ulps between this value and the expected one.
+    ///
+    /// All NaNs are considered equal to each other,
+    /// but a NaN is `u64::max_value()` ulps away from any number.
+    fn ulps_from(&self, expected: &Self) -> u64;
+    /// Returns the relative error `|self - expected| / |expected|`.
+    ///
+    /// All NaNs are considered equal to each other, and anything else compared to zero is infinitely far away.
+    fn relative_error(&self, expected: &Self) -> f64;
+}
+
+/// Returns the number of ulps between the actual and expected values
+#[inline]
+pub fn ulp_distance<T: Ulps>(actual: T, expected: T) -> u64 {
+    actual.ulps_from(&expected)
+}
+
+/// Returns the relative error of the actual value compared to the expected one
+#[inline]
+pub fn relative_error<T: Ulps>(actual: T, expected: T) -> f64 {
+    actual.relative_error(&expected)
+}
+
+/// Returns the error of the actual value in ulps of its format,
+/// compared to an exact value computed with (much) higher precision.
+///
+/// Unlike `ulp_distance` this is fractional, so a correctly rounded result is at most half an ulp off.
+/// The size of an ulp is taken at the magnitude of the exact value, and is fixed in the subnormal range.
+pub fn ulp_error<T: ToyFloat>(actual: T, exact: &Float) -> f64 {
+    let rounded = T::from_big(exact);
+    if actual.is_nan() || exact.is_nan() {
+        return if actual.is_nan() && exact.is_nan() { 0.0 } else { ::std::f64::INFINITY };
+    } else if !actual.is_finite() || !rounded.is_finite() {
+        // There's nothing to count past the largest finite value, so it's either right or it isn't
+        return if actual == rounded { 0.0 } else { ::std::f64::INFINITY };
+    }
+    let min_exponent = 1 - T::EXPONENT_BIAS - T::FRACTION_BITS as i32;
+    let ulp_exponent = exact.get_exp()
+        .map_or(min_exponent, |exponent| (exponent - T::MANTISSA_DIGITS as i32).max(min_exponent));
+    let error = actual.to_big(exact.prec().max(T::MANTISSA_DIGITS)) - exact;
+    (error.abs() >> ulp_exponent).to_f64()
+}
+
+/// Asserts that the actual value is within the given number of ulps of the expected one,
+/// reporting the error in ulps if it isn't.
+#[macro_export]
+macro_rules! assert_within_ulps {
+    ($actual:expr, $expected:expr, $ulps:expr) => ({
+        match (&$actual, &$expected, $ulps) {
+            (actual_val, expected_val, allowed) => {
+                let error = $crate::accuracy::Ulps::ulps_from(actual_val, expected_val);
+                if error > allowed {
+                    panic!(r#"assertion failed: `(actual == expected)` within {} ulps
+  actual: `{:.20?}`,
+expected: `{:.20?}`,
+   error: {} ulps"#, allowed, actual_val, expected_val, error)
+                }
+            }
+        }
+    });
+    ($actual:expr, $expected:expr, $ulps:expr,) => (assert_within_ulps!($actual, $expected, $ulps));
+    ($actual:expr, $expected:expr, $ulps:expr, $($arg:tt)+) => ({
+        match (&$actual, &$expected, $ulps) {
+            (actual_val, expected_val, allowed) => {
+                let error = $crate::accuracy::Ulps::ulps_from(actual_val, expected_val);
+                if error > allowed {
+                    panic!(r#"assertion failed: `(actual == expected)` within {} ulps
+  actual: `{:.20?}`,
+expected: `{:.20?}`,
+   error: {} ulps: {}"#, allowed, actual_val, expected_val, error, format_args!($($arg)+))
+                }
+            }
+        }
+    })
+}
+
+/// Asserts that the relative error of the actual value is at most the given threshold,
+/// reporting the error in ulps as well if it isn't.
+#[macro_export]
+macro_rules! assert_within_relative_error {
+    ($actual:expr, $expected:expr, $threshold:expr) => ({
+        match (&$actual, &$expected, $threshold) {
+            (actual_val, expected_val, threshold) => {
+                let error = $crate::accuracy::Ulps::relative_error(actual_val, expected_val);
+                if !(error <= threshold) {
+                    panic!(r#"assertion failed: `(actual == expected)` within a relative error of {:e}
+  actual: `{:.20?}`,
+expected: `{:.20?}`,
+   error: {:e} ({} ulps)"#, threshold, actual_val, expected_val, error,
+                           $crate::accuracy::Ulps::ulps_from(actual_val, expected_val))
+                }
+            }
+        }
+    });
+    ($actual:expr, $expected:expr, $threshold:expr,) => (assert_within_relative_error!($actual, $expected, $threshold));
+    ($actual:expr, $expected:expr, $threshold:expr, $($arg:tt)+) => ({
+        match (&$actual, &$expected, $threshold) {
+            (actual_val, expected_val, threshold) => {
+                let error = $crate::accuracy::Ulps::relative_error(actual_val, expected_val);
+                if !(error <= threshold) {
+                    panic!(r#"assertion failed: `(actual == expected)` within a relative error of {:e}
+  actual: `{:.20?}`,
+expected: `{:.20?}`,
+   error: {:e} ({} ulps): {}"#, threshold, actual_val, expected_val, error,
+                           $crate::accuracy::Ulps::ulps_from(actual_val, expected_val), format_args!($($arg)+))
+                }
+            }
+        }
+    })
+}
+
+/// Counts the ulps between two values of the same format, using their packed bits.
+fn packed_ulps_from<T: ToyFloat>(actual: T, expected: T) -> u64 {
+    if actual.is_nan() || expected.is_nan() {
+        return if actual.is_nan() && expected.is_nan() { 0 } else { u64::max_value() };
+    }
+    /*
+     * Map the sign-magnitude bits onto a monotonic integer line, with both zeros at the origin.
+     * Adjacent values then differ by exactly one, even across binades and into the subnormals.
+     */
+    fn ordered<T: ToyFloat>(value: T) -> i128 {
+        let magnitude = value.abs().to_packed_bits() as i128;
+        if value.is_sign_negative() { -magnitude } else { magnitude }
+    }
+    let distance = (ordered(actual) - ordered(expected)).abs();
+    if distance > u64::max_value() as i128 { u64::max_value() } else { distance as u64 }
+}
+
+fn packed_relative_error<T: ToyFloat>(actual: T, expected: T) -> f64 {
+    if actual == expected || (actual.is_nan() && expected.is_nan()) {
+        0.0
+    } else if actual.is_nan() || expected.is_nan() {
+        ::std::f64::INFINITY
+    } else {
+        ((actual - expected) / expected).abs().to_f64()
+    }
+}
+
+impl Ulps for f32 {
+    #[inline]
+    fn ulps_from(&self, expected: &f32) -> u64 {
+        packed_ulps_from(*self, *expected)
+    }
+    #[inline]
+    fn relative_error(&self, expected: &f32) -> f64 {
+        // Widen first, so the relative error of a subnormal doesn't underflow
+        packed_relative_error(*self as f64, *expected as f64)
+    }
+}
+impl Ulps for f64 {
+    #[inline]
+    fn ulps_from(&self, expected: &f64) -> u64 {
+        packed_ulps_from(*self, *expected)
+    }
+    #[inline]
+    fn relative_error(&self, expected: &f64) -> f64 {
+        packed_relative_error(*self, *expected)
+    }
+}
+impl Ulps for ExtendedFloat {
+    #[inline]
+    fn ulps_from(&self, expected: &ExtendedFloat) -> u64 {
+        packed_ulps_from(*self, *expected)
+    }
+    #[inline]
+    fn relative_error(&self, expected: &ExtendedFloat) -> f64 {
+        packed_relative_error(*self, *expected)
+    }
+}
+/// Since arbitrary precision floats don't have a fixed format,
+/// this measures ulps at the precision of the actual value and the magnitude of the expected one.
+///
+/// That way the expected value can be computed with extra bits,
+/// and a correctly rounded result is at most one ulp away (the error is rounded up).
+impl Ulps for Float {
+    fn ulps_from(&self, expected: &Float) -> u64 {
+        if self.is_nan() || expected.is_nan() {
+            return if self.is_nan() && expected.is_nan() { 0 } else { u64::max_value() };
+        } else if self == expected {
+            return 0;
+        } else if !self.is_finite() || !expected.is_normal() {
+            // The exponent range is unbounded, so there's no smallest ulp to count with
+            return u64::max_value();
+        }
+        let ulp_exponent = expected.get_exp().unwrap() - self.prec() as i32;
+        let error = Float::with_val(self.prec().max(expected.prec()), self - expected).abs() >> ulp_exponent;
+        let error = error.to_f64().ceil();
+        if error >= u64::max_value() as f64 { u64::max_value() } else { error as u64 }
+    }
+    fn relative_error(&self, expected: &Float) -> f64 {
+        if self == expected || (self.is_nan() && expected.is_nan()) {
+            return 0.0;
+        } else if self.is_nan() || expected.is_nan() {
+            return ::std::f64::INFINITY;
+        }
+        let mut error = Float::with_val(self.prec().max(expected.prec()), self - expected);
+        error /= expected;
+        error.abs().to_f64()
+    }
+}
+/// Pairs like the result of `sin_cos` are as far off as their worst component.
+impl<A: Ulps, B: Ulps> Ulps for (A, B) {
+    #[inline]
+    fn ulps_from(&self, expected: &Self) -> u64 {
+        self.0.ulps_from(&expected.0).max(self.1.ulps_from(&expected.1))
+    }
+    #[inline]
+    fn relative_error(&self, expected: &Self) -> f64 {
+        let (first, second) = (self.0.relative_error(&expected.0), self.1.relative_error(&expected.1));
+        if first.is_nan() || second.is_nan() { ::std::f64::NAN } else { first.max(second) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ulp_distance, relative_error, ulp_error};
+    use rug::Float;
+    use extended_float::ExtendedFloat;
+    use utils::Finite;
+    use bits::{decode, next_up, next_down};
+
+    #[test]
+    fn basic() {
+        assert_eq!(ulp_distance(1.0, 1.0), 0);
+        assert_eq!(ulp_distance(1.0, 1.0 + ::std::f64::EPSILON), 1);
+        assert_eq!(ulp_distance(1.0, 1.0 - ::std::f64::EPSILON), 2);
+        assert_eq!(ulp_distance(0.0, -0.0), 0);
+        assert_eq!(ulp_distance(5e-324, -5e-324), 2);
+        assert_eq!(ulp_distance(::std::f64::MAX, ::std::f64::INFINITY), 1);
+        assert_eq!(ulp_distance(::std::f64::NAN, -::std::f64::NAN), 0);
+        assert_eq!(ulp_distance(::std::f64::NAN, 1.0), u64::max_value());
+        assert_eq!(ulp_distance(::std::f64::NEG_INFINITY, ::std::f64::INFINITY), 0xffe0000000000000);
+        assert_eq!(ulp_distance(1.0f32, 1.0 + ::std::f32::EPSILON), 1);
+        assert_eq!(ulp_distance((1.0, 2.0), (1.0, 2.0 + 4.0 * ::std::f64::EPSILON)), 2);
+        assert_eq!(relative_error(1.1, 1.0), 1.1 - 1.0);
+        assert_eq!(relative_error(0.0, 0.0), 0.0);
+        assert_eq!(relative_error(1e-300, 0.0), ::std::f64::INFINITY);
+        assert_eq!(relative_error(f32::from_bits(1), f32::from_bits(2)), 0.5);
+    }
+
+    #[test]
+    fn extended() {
+        let one = ExtendedFloat::from(1.0);
+        assert_eq!(ulp_distance(one, one), 0);
+        assert_eq!(ulp_distance(one, next_up(next_up(one))), 2);
+        assert_eq!(ulp_distance(ExtendedFloat::from(0.0), next_down(ExtendedFloat::from(0.0))), 1);
+        assert_eq!(relative_error(ExtendedFloat::from(3.0), ExtendedFloat::from(2.0)), 0.5);
+    }
+
+    #[test]
+    fn big() {
+        let one = Float::with_val(100, 1);
+        let next = Float::with_val(100, 1) + (Float::with_val(100, 1) >> 99);
+        assert_eq!(ulp_distance(one.clone(), next.clone()), 1);
+        assert_eq!(ulp_distance(next, one), 1);
+        // Measured in ulps of the actual value, even when the expected value is more precise
+        let third = Float::with_val(64, 1) / 3;
+        let exact = Float::with_val(256, 1) / 3;
+        assert_eq!(ulp_distance(third.clone(), exact.clone()), 1);
+        assert!(relative_error(third, exact) < 1e-19);
+        assert_eq!(ulp_distance(Float::with_val(64, 0), Float::with_val(64, 1)), u64::max_value());
+    }
+
+    #[test]
+    fn fractional() {
+        let third = Float::with_val(256, 1) / 3;
+        let error = ulp_error(1.0 / 3.0, &third);
+        assert!(error > 0.0 && error <= 0.5, "Unexpected error {}", error);
+        assert_eq!(ulp_error(1.0 + ::std::f64::EPSILON, &Float::with_val(256, 1)), 1.0);
+        assert_eq!(ulp_error(5e-324, &Float::with_val(256, 0)), 1.0);
+        assert_eq!(ulp_error(::std::f64::INFINITY, &(Float::with_val(256, 1e308) * 10)), 0.0);
+        assert_eq!(ulp_error(::std::f64::MAX, &(Float::with_val(256, 1e308) * 10)), ::std::f64::INFINITY);
+        assert_eq!(ulp_error(1.5f32, &Float::with_val(256, 1.5)), 0.0);
+    }
+
+    #[quickcheck]
+    fn adjacent_is_one_ulp(target: Finite) -> bool {
+        let Finite(target) = target;
+        ulp_distance(target, next_up(target)) == 1 && ulp_distance(next_down(target), target) == 1 &&
+            ulp_distance(next_down(target), next_up(target)) == 2
+    }
+
+    #[quickcheck]
+    fn big_matches_double(target: Finite) -> bool {
+        let Finite(target) = target;
+        let up = next_up(next_up(target));
+        // Crossing into the next binade changes the size of an ulp
+        !up.is_normal() || !target.is_normal() || decode(up).1 != decode(target).1 ||
+            ulp_distance(Float::with_val(53, up), Float::with_val(53, target)) ==
+                ulp_distance(up, target)
+    }
+}
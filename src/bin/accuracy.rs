@@ -0,0 +1,352 @@
+//! Reports the worst-case error of every toymath function, compared against MPFR.
+//!
+//! Each function is sampled over its range both uniformly and uniformly by bit pattern
+//! (which spreads the samples evenly over the exponents),
+//! along with adversarial inputs like the doubles closest to multiples of pi/2 and powers of two.
+//! The exact results are computed with `rug` at a much higher precision than a double,
+//! so the error can be measured in fractional ulps.
+extern crate rug;
+extern crate toymath;
+
+use std::env;
+use std::f64::MAX;
+use std::process;
+
+use rug::Float;
+use rug::float::Constant;
+use rug::ops::Pow;
+
+use toymath::accuracy::ulp_error;
+use toymath::bits::{next_up, next_down};
+
+/// Plenty for the reference results to be correctly rounded
+const PRECISION: u32 = 256;
+/// Enough bits of pi to find the double closest to a multiple of pi/2 anywhere in the range
+const PI_PRECISION: u32 = 2048;
+const DEFAULT_SAMPLES: usize = 100_000;
+const DEFAULT_SEED: u64 = 0x853c49e6748fea9b;
+
+type Exact = fn(&Float) -> Float;
+type BinaryExact = fn(&Float, &Float) -> Float;
+
+#[derive(Copy, Clone)]
+enum Kind {
+    Unary(fn(f64) -> f64, Exact),
+    Binary(fn(f64, f64) -> f64, BinaryExact),
+}
+
+#[derive(Clone)]
+struct Function {
+    name: &'static str,
+    kind: Kind,
+    range: (f64, f64),
+    /// The range of the second argument, for binary functions
+    second_range: (f64, f64),
+    /// Whether the function is periodic, so the inputs closest to multiples of pi/2 are adversarial
+    periodic: bool,
+}
+
+macro_rules! unary {
+    ($name:expr, $actual:expr, $method:ident, $range:expr) => (unary!($name, $actual, $method, $range, false));
+    ($name:expr, $actual:expr, $method:ident, $range:expr, $periodic:expr) => (Function {
+        name: $name,
+        kind: Kind::Unary($actual, |x| Float::with_val(PRECISION, x.$method())),
+        range: $range,
+        second_range: (0.0, 0.0),
+        periodic: $periodic,
+    });
+}
+
+fn functions() -> Vec<Function> {
+    vec![
+        unary!("sqrt", toymath::sqrt, sqrt_ref, (0.0, MAX)),
+        unary!("sqrt_correctly_rounded", toymath::sqrt_correctly_rounded, sqrt_ref, (0.0, MAX)),
+        unary!("rsqrt", toymath::rsqrt, recip_sqrt_ref, (0.0, MAX)),
+        unary!("cbrt", toymath::cbrt, cbrt_ref, (-MAX, MAX)),
+        unary!("sin", toymath::sin, sin_ref, (-1e6, 1e6), true),
+        unary!("cos", toymath::cos, cos_ref, (-1e6, 1e6), true),
+        unary!("tan", toymath::tan, tan_ref, (-1e6, 1e6), true),
+        unary!("asin", toymath::asin, asin_ref, (-1.0, 1.0)),
+        unary!("acos", toymath::acos, acos_ref, (-1.0, 1.0)),
+        unary!("atan", toymath::atan, atan_ref, (-1e10, 1e10)),
+        Function {
+            name: "atan2",
+            kind: Kind::Binary(toymath::atan2, |y, x| Float::with_val(PRECISION, y.atan2_ref(x))),
+            range: (-100.0, 100.0),
+            second_range: (-100.0, 100.0),
+            periodic: false,
+        },
+        unary!("exp", toymath::exp, exp_ref, (-745.0, 710.0)),
+        unary!("exp2", toymath::exp2, exp2_ref, (-1075.0, 1024.0)),
+        unary!("exp_m1", toymath::exp_m1, exp_m1_ref, (-40.0, 710.0)),
+        unary!("log2", toymath::log2, log2_ref, (0.0, MAX)),
+        unary!("ln", toymath::ln, ln_ref, (0.0, MAX)),
+        unary!("log10", toymath::log10, log10_ref, (0.0, MAX)),
+        unary!("log2_precise", toymath::log2_precise, log2_ref, (0.0, MAX)),
+        unary!("ln_precise", toymath::ln_precise, ln_ref, (0.0, MAX)),
+        unary!("log10_precise", toymath::log10_precise, log10_ref, (0.0, MAX)),
+        unary!("log1p", toymath::log1p, ln_1p_ref, (-1.0, MAX)),
+        Function {
+            name: "pow",
+            kind: Kind::Binary(toymath::pow, |base, power| Float::with_val(PRECISION, base.pow(power))),
+            range: (0.0, 100.0),
+            second_range: (-100.0, 100.0),
+            periodic: false,
+        },
+        unary!("sinh", toymath::sinh, sinh_ref, (-710.0, 710.0)),
+        unary!("cosh", toymath::cosh, cosh_ref, (-710.0, 710.0)),
+        unary!("tanh", toymath::tanh, tanh_ref, (-20.0, 20.0)),
+        unary!("asinh", toymath::asinh, asinh_ref, (-MAX, MAX)),
+        unary!("acosh", toymath::acosh, acosh_ref, (1.0, MAX)),
+        unary!("atanh", toymath::atanh, atanh_ref, (-1.0, 1.0)),
+    ]
+}
+
+/// A simple xorshift generator, so the sweep is deterministic for a given seed
+struct Random(u64);
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    /// A uniform value in `[0, 1)`
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+    /// Picks a value in the range, either uniformly or uniformly by bit pattern
+    fn sample(&mut self, (low, high): (f64, f64)) -> f64 {
+        if self.next() & 1 == 0 {
+            let t = self.unit();
+            // Interpolating this way can't overflow, even for the whole range of doubles
+            (low * (1.0 - t) + high * t).max(low).min(high)
+        } else {
+            let (low, high) = (ordered(low) as i128, ordered(high) as i128);
+            let offset = (self.next() as u128 % (high - low + 1) as u128) as i128;
+            from_ordered((low + offset) as i64)
+        }
+    }
+}
+
+/// Maps the sign-magnitude bits onto a monotonic integer line
+fn ordered(value: f64) -> i64 {
+    let bits = value.to_bits() as i64;
+    if bits < 0 { i64::min_value() - bits } else { bits }
+}
+fn from_ordered(value: i64) -> f64 {
+    f64::from_bits(if value < 0 { (i64::min_value() - value) as u64 } else { value as u64 })
+}
+
+/// The inputs most likely to expose problems, restricted to the range of the function.
+fn adversarial(function: &Function) -> Vec<f64> {
+    let mut targets = Vec::new();
+    // Powers of two are where the exponent changes, along with their neighbors
+    for exponent in -1074..1024 {
+        let power = toymath::ldexp(1.0, exponent);
+        targets.extend_from_slice(&[power, next_up(power), next_down(power)]);
+    }
+    if function.periodic {
+        // The doubles closest to multiples of pi/2, where all the precision comes from the range reduction
+        let half_pi = Float::with_val(PI_PRECISION, Constant::Pi) / 2;
+        let mut multiples: Vec<Float> = (1..1000).map(|k| Float::with_val(PI_PRECISION, k)).collect();
+        let mut power = Float::with_val(PI_PRECISION, 1000);
+        while power < 1e308 {
+            multiples.push(power.clone());
+            power *= 10;
+        }
+        for multiple in multiples {
+            let target = Float::with_val(PI_PRECISION, &multiple * &half_pi).to_f64();
+            targets.extend_from_slice(&[target, next_up(target), next_down(target)]);
+        }
+    }
+    let negated: Vec<f64> = targets.iter().map(|&target| -target).collect();
+    targets.extend(negated);
+    targets.retain(|&target| target.is_finite() && target >= function.range.0 && target <= function.range.1);
+    targets
+}
+
+/// The error statistics for a single function
+struct Report {
+    name: &'static str,
+    samples: usize,
+    max_ulps: f64,
+    total_ulps: f64,
+    worst_input: Vec<f64>,
+    worst_actual: f64,
+    worst_expected: f64,
+}
+impl Report {
+    fn new(name: &'static str) -> Report {
+        Report {
+            name,
+            samples: 0,
+            max_ulps: 0.0,
+            total_ulps: 0.0,
+            worst_input: Vec::new(),
+            worst_actual: 0.0,
+            worst_expected: 0.0,
+        }
+    }
+    fn record(&mut self, input: &[f64], actual: f64, exact: &Float) {
+        let error = ulp_error(actual, exact);
+        self.samples += 1;
+        self.total_ulps += error;
+        // Also take the first sample, so there's always a worst input even if everything's exact
+        if error > self.max_ulps || self.worst_input.is_empty() {
+            self.max_ulps = error;
+            self.worst_input = input.to_vec();
+            self.worst_actual = actual;
+            self.worst_expected = exact.to_f64();
+        }
+    }
+    fn mean_ulps(&self) -> f64 {
+        self.total_ulps / self.samples as f64
+    }
+}
+
+fn measure(function: &Function, samples: usize, seed: u64, include_adversarial: bool) -> Report {
+    let mut random = Random(seed);
+    let mut report = Report::new(function.name);
+    let adversarial = if include_adversarial { adversarial(function) } else { Vec::new() };
+    let random_inputs = (0..samples).map(|_| random.sample(function.range)).collect::<Vec<_>>();
+    for x in adversarial.into_iter().chain(random_inputs) {
+        match function.kind {
+            Kind::Unary(actual, exact) => {
+                report.record(&[x], actual(x), &exact(&Float::with_val(53, x)));
+            },
+            Kind::Binary(actual, exact) => {
+                let y = random.sample(function.second_range);
+                report.record(
+                    &[x, y], actual(x, y),
+                    &exact(&Float::with_val(53, x), &Float::with_val(53, y))
+                );
+            }
+        }
+    }
+    report
+}
+
+/// Formats a number for JSON, which doesn't have infinity or NaN
+fn json_number(value: f64) -> String {
+    if value.is_finite() { format!("{:e}", value) } else { "null".to_string() }
+}
+
+fn print_json(reports: &[Report]) {
+    println!("[");
+    for (index, report) in reports.iter().enumerate() {
+        let input = report.worst_input.iter().map(|&x| json_number(x)).collect::<Vec<_>>().join(", ");
+        println!(
+            "  {{\"function\": \"{}\", \"samples\": {}, \"max_ulps\": {}, \"mean_ulps\": {}, \
+             \"worst_input\": [{}], \"worst_actual\": {}, \"worst_expected\": {}}}{}",
+            report.name, report.samples, json_number(report.max_ulps), json_number(report.mean_ulps()),
+            input, json_number(report.worst_actual), json_number(report.worst_expected),
+            if index + 1 < reports.len() { "," } else { "" }
+        );
+    }
+    println!("]");
+}
+
+fn print_table(reports: &[Report]) {
+    println!(
+        "{:<24} {:>9} {:>12} {:>12}  {}",
+        "function", "samples", "max ulps", "mean ulps", "worst input"
+    );
+    for report in reports {
+        let input = report.worst_input.iter().map(|x| format!("{:e}", x)).collect::<Vec<_>>().join(", ");
+        println!(
+            "{:<24} {:>9} {:>12.4} {:>12.4}  {} = {:e} (expected {:e})",
+            report.name, report.samples, report.max_ulps, report.mean_ulps(),
+            input, report.worst_actual, report.worst_expected
+        );
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: toymath-accuracy [options] [function...]");
+    eprintln!();
+    eprintln!("Options:");
+    eprintln!("    --json                 Print the results as JSON instead of a table");
+    eprintln!("    --samples <count>      Random samples per function (default {})", DEFAULT_SAMPLES);
+    eprintln!("    --seed <seed>          Seed for the random samples");
+    eprintln!("    --range <low>:<high>   Override the range of the (first) argument");
+    eprintln!("    --second-range <low>:<high>");
+    eprintln!("                           Override the range of the second argument of binary functions");
+    eprintln!("    --no-adversarial       Skip the inputs near powers of two and multiples of pi/2");
+    eprintln!("    --list                 List the functions and their default ranges");
+    process::exit(2)
+}
+
+fn parse_range(text: &str) -> (f64, f64) {
+    let mut parts = text.splitn(2, ':');
+    match (parts.next().map(str::parse::<f64>), parts.next().map(str::parse::<f64>)) {
+        (Some(Ok(low)), Some(Ok(high))) if low <= high => (low, high),
+        _ => {
+            eprintln!("Invalid range: {}", text);
+            usage()
+        }
+    }
+}
+
+fn main() {
+    let mut json = false;
+    let mut samples = DEFAULT_SAMPLES;
+    let mut seed = DEFAULT_SEED;
+    let mut range = None;
+    let mut second_range = None;
+    let mut include_adversarial = true;
+    let mut names = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--json" => json = true,
+            "--samples" => samples = args.next().and_then(|count| count.parse().ok()).unwrap_or_else(|| usage()),
+            "--seed" => seed = args.next().and_then(|seed| seed.parse().ok()).unwrap_or_else(|| usage()),
+            "--range" => range = Some(parse_range(&args.next().unwrap_or_else(|| usage()))),
+            "--second-range" => second_range = Some(parse_range(&args.next().unwrap_or_else(|| usage()))),
+            "--no-adversarial" => include_adversarial = false,
+            "--list" => {
+                for function in functions() {
+                    match function.kind {
+                        Kind::Unary(..) => println!("{} [{:e}, {:e}]", function.name, function.range.0, function.range.1),
+                        Kind::Binary(..) => println!(
+                            "{} [{:e}, {:e}] x [{:e}, {:e}]", function.name,
+                            function.range.0, function.range.1, function.second_range.0, function.second_range.1
+                        ),
+                    }
+                }
+                return
+            },
+            "--help" | "-h" => usage(),
+            _ if arg.starts_with("--") => {
+                eprintln!("Unknown option: {}", arg);
+                usage()
+            },
+            _ => names.push(arg),
+        }
+    }
+    let selected = functions().into_iter().enumerate()
+        .filter(|&(_, ref function)| names.is_empty() || names.iter().any(|name| name == function.name))
+        .collect::<Vec<_>>();
+    for name in &names {
+        if !selected.iter().any(|&(_, ref function)| function.name == name) {
+            eprintln!("Unknown function: {}", name);
+            usage()
+        }
+    }
+    // Each function gets its own seed, so selecting a subset doesn't change their samples
+    let reports = selected.into_iter().map(|(index, mut function)| {
+        if let Some(range) = range {
+            function.range = range;
+        }
+        if let Some(range) = second_range {
+            function.second_range = range;
+        }
+        measure(&function, samples, seed.wrapping_add(index as u64).max(1), include_adversarial)
+    }).collect::<Vec<_>>();
+    if json {
+        print_json(&reports);
+    } else {
+        print_table(&reports);
+    }
+}
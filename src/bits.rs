@@ -0,0 +1,299 @@
+//! The IEEE bit layouts of `f32`, `f64` and the x87 80-bit format used by `ExtendedFloat`.
+//!
+//! Everything here works on the packed integer representation from `ToyFloat::to_packed_bits`,
+//! so it's exact for every category of value and never depends on floating point arithmetic.
+//! The x87 format stores its integer bit explicitly, but the packed layout always leaves it implicit,
+//! so the same code handles all three formats.
+use std::num::FpCategory;
+
+use float::ToyFloat;
+
+#[inline]
+fn fraction_mask<T: ToyFloat>() -> u128 {
+    (1 << T::FRACTION_BITS) - 1
+}
+#[inline]
+fn max_exponent_field<T: ToyFloat>() -> u128 {
+    (1 << T::EXPONENT_BITS) - 1
+}
+#[inline]
+fn sign_mask<T: ToyFloat>() -> u128 {
+    1 << (T::FRACTION_BITS + T::EXPONENT_BITS)
+}
+
+/// Returns the mantissa, exponent and sign as integers,
+/// such that the value is exactly `sign * mantissa * 2^exponent`.
+///
+/// Normal values include the implicit leading bit in the mantissa,
+/// while subnormals just use the fraction with the minimum exponent.
+/// Infinity and NaN decode with the all-ones exponent field,
+/// which `encode` treats like any other number too large to represent and maps to infinity.
+pub fn decode<T: ToyFloat>(value: T) -> (u64, i16, i8) {
+    let bits = value.to_packed_bits();
+    let sign = if bits & sign_mask::<T>() != 0 { -1 } else { 1 };
+    let field = (bits >> T::FRACTION_BITS) & max_exponent_field::<T>();
+    let fraction = (bits & fraction_mask::<T>()) as u64;
+    let (mantissa, exponent) = if field == 0 {
+        (fraction, 1 - T::EXPONENT_BIAS)
+    } else {
+        (fraction | (1 << T::FRACTION_BITS), field as i32 - T::EXPONENT_BIAS)
+    };
+    (mantissa, (exponent - T::FRACTION_BITS as i32) as i16, sign)
+}
+
+/// Computes `sign * mantissa * 2^exponent`, rounding to nearest (ties to even) only once.
+///
+/// This is the inverse of `decode`, but accepts any mantissa and exponent,
+/// correctly rounding values that have too many bits or fall in the subnormal range.
+/// Values past the largest finite number overflow to infinity, never NaN.
+pub fn encode<T: ToyFloat>(mantissa: u64, exponent: i32, sign: i8) -> T {
+    let sign_bit = if sign < 0 { sign_mask::<T>() } else { 0 };
+    let max_field = max_exponent_field::<T>();
+    if mantissa == 0 {
+        return T::from_packed_bits(sign_bit);
+    }
+    // The exponent of the leading bit, so the value is in [2^leading, 2^(leading + 1))
+    let length = 64 - mantissa.leading_zeros() as i64;
+    let leading = exponent as i64 + length - 1;
+    let biased = leading + T::EXPONENT_BIAS as i64;
+    // Subnormals have fewer significant bits the smaller they get
+    let kept = if biased >= 1 {
+        T::MANTISSA_DIGITS as i64
+    } else {
+        T::FRACTION_BITS as i64 + biased
+    };
+    if kept < 0 {
+        // Less than half the smallest subnormal
+        return T::from_packed_bits(sign_bit);
+    }
+    let shift = length - kept;
+    let significand = if shift <= 0 {
+        (mantissa as u128) << -shift
+    } else {
+        let mantissa = mantissa as u128;
+        let truncated = mantissa >> shift;
+        let remainder = mantissa & ((1 << shift) - 1);
+        let half = 1 << (shift - 1);
+        if remainder > half || (remainder == half && truncated & 1 != 0) {
+            truncated + 1
+        } else {
+            truncated
+        }
+    };
+    /*
+     * Adding the significand (including its leading bit) to the exponent field just below
+     * handles every carry for free: rounding up to the next binade bumps the exponent,
+     * and a subnormal that rounds up to the smallest normal gets an exponent field of one.
+     */
+    let packed = if biased >= 1 {
+        ((biased as u128 - 1) << T::FRACTION_BITS) + significand
+    } else {
+        significand
+    };
+    if packed >= max_field << T::FRACTION_BITS {
+        // Whatever fraction bits are left over would turn the infinity into a NaN
+        T::from_packed_bits(sign_bit | (max_field << T::FRACTION_BITS))
+    } else {
+        T::from_packed_bits(sign_bit | packed)
+    }
+}
+
+/// Classifies the value based on its exponent and fraction fields.
+pub fn classify<T: ToyFloat>(value: T) -> FpCategory {
+    let bits = value.to_packed_bits();
+    let field = (bits >> T::FRACTION_BITS) & max_exponent_field::<T>();
+    let fraction = bits & fraction_mask::<T>();
+    match (field, fraction) {
+        (0, 0) => FpCategory::Zero,
+        (0, _) => FpCategory::Subnormal,
+        (field, 0) if field == max_exponent_field::<T>() => FpCategory::Infinite,
+        (field, _) if field == max_exponent_field::<T>() => FpCategory::Nan,
+        _ => FpCategory::Normal
+    }
+}
+
+/// Returns the smallest value greater than the given one.
+///
+/// Both zeros step to the smallest positive subnormal, and NaN or positive infinity are returned unchanged.
+pub fn next_up<T: ToyFloat>(value: T) -> T {
+    let bits = value.to_packed_bits();
+    match classify(value) {
+        FpCategory::Nan => value,
+        FpCategory::Infinite if bits & sign_mask::<T>() == 0 => value,
+        FpCategory::Zero => T::from_packed_bits(1),
+        /*
+         * The magnitude is ordered the same way as the packed bits,
+         * and stepping past the largest subnormal carries into the exponent.
+         */
+        _ if bits & sign_mask::<T>() == 0 => T::from_packed_bits(bits + 1),
+        _ => T::from_packed_bits(bits - 1),
+    }
+}
+
+/// Returns the largest value less than the given one.
+///
+/// Both zeros step to the smallest negative subnormal, and NaN or negative infinity are returned unchanged.
+#[inline]
+pub fn next_down<T: ToyFloat>(value: T) -> T {
+    -next_up(-value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::num::FpCategory;
+    use quickcheck::{Arbitrary, Gen};
+    use rug::Float;
+    use extended_float::ExtendedFloat;
+    use float::ToyFloat;
+
+    /// Random packed bits that cover every category evenly,
+    /// which the builtin generators for floats never do.
+    #[derive(Copy, Clone, Debug)]
+    struct AnyBits(u128);
+    impl Arbitrary for AnyBits {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            let random = (g.next_u64() as u128) << 64 | g.next_u64() as u128;
+            let sign = g.next_u32() as u128 & 1;
+            let exponent = match g.next_u32() % 5 {
+                0 => 0,
+                // Every bit set is the maximum exponent field for each format
+                1 => !0,
+                _ => random >> 64,
+            };
+            let fraction = match g.next_u32() % 4 {
+                0 => 0,
+                _ => random,
+            };
+            AnyBits(sign << 127 | (exponent & ((1 << 63) - 1)) << 64 | fraction & ((1 << 64) - 1))
+        }
+    }
+    impl AnyBits {
+        /// Packs the random fields into the layout of the given type
+        fn get<T: ToyFloat>(self) -> T {
+            let AnyBits(bits) = self;
+            let exponent = (bits >> 64) & ((1 << T::EXPONENT_BITS) - 1);
+            let fraction = bits & ((1 << T::FRACTION_BITS) - 1);
+            let sign = bits >> 127;
+            T::from_packed_bits(
+                sign << (T::FRACTION_BITS + T::EXPONENT_BITS) | exponent << T::FRACTION_BITS | fraction
+            )
+        }
+    }
+
+    fn roundtrip<T: ToyFloat>(value: T) -> bool {
+        let (mantissa, exponent, sign) = decode(value);
+        let encoded: T = encode(mantissa, exponent as i32, sign);
+        if value.is_nan() {
+            // There's no way to encode a NaN, so its exponent field just overflows
+            encoded.is_infinite() && encoded.is_sign_negative() == value.is_sign_negative()
+        } else {
+            encoded.to_packed_bits() == value.to_packed_bits()
+        }
+    }
+
+    #[test]
+    fn basic() {
+        assert_eq!(decode(5.0f64), (5 << 50, -50, 1));
+        assert_eq!(decode(-1.0f32), (1 << 23, -23, -1));
+        assert_eq!(decode(5e-324f64), (1, -1074, 1));
+        assert_eq!(decode(ExtendedFloat::from(1.0)), (1 << 63, -63, 1));
+        assert_eq!(encode::<f64>(1, -1074, 1), 5e-324);
+        assert_eq!(encode::<f64>(5, 0, -1), -5.0);
+        assert_eq!(encode::<f64>(1, 1024, 1), ::std::f64::INFINITY);
+        assert_eq!(encode::<f32>(1, -149, 1), 1e-45);
+        assert_eq!(encode::<ExtendedFloat>(3, -1, 1), ExtendedFloat::from(1.5));
+        for &value in &[0.0, -0.0, 5.0, ::std::f64::consts::PI, 5e-324, ::std::f64::MAX] {
+            assert!(roundtrip(value), "Failed to roundtrip {}", value);
+        }
+    }
+
+    #[test]
+    fn rounding() {
+        // Ties go to the even neighbor, whether normal or subnormal
+        assert_eq!(encode::<f64>((1 << 53) + 1, 0, 1), 9007199254740992.0);
+        assert_eq!(encode::<f64>((1 << 53) + 3, 0, 1), 9007199254740996.0);
+        assert_eq!(encode::<f64>(1, -1075, 1), 0.0);
+        assert_eq!(encode::<f64>(3, -1076, 1), 5e-324);
+        assert_eq!(encode::<f64>(3, -1075, 1), 1e-323);
+        assert_eq!(encode::<f64>(1, -1076, -1).to_bits(), (-0.0f64).to_bits());
+        // Rounding up can carry into the next binade, or out of the subnormal range
+        assert_eq!(encode::<f64>(u64::max_value(), 0, 1), 18446744073709551616.0);
+        assert_eq!(encode::<f64>((1 << 53) - 1, -1075, 1), ::std::f64::MIN_POSITIVE);
+        assert_eq!(encode::<f64>(u64::max_value(), 960, 1), ::std::f64::INFINITY);
+    }
+
+    #[test]
+    fn overflow() {
+        // Finite values with the same mantissa and exponent that infinity and NaN decode to
+        assert_eq!(encode::<f64>(3 << 51, 972, 1), ::std::f64::INFINITY);
+        assert_eq!(encode::<f64>((1 << 53) - 1, 972, -1), ::std::f64::NEG_INFINITY);
+        assert_eq!(encode::<f32>((1 << 24) - 1, 105, 1), ::std::f32::INFINITY);
+        // Rounding up into the all-ones exponent field must not leave any fraction bits behind
+        assert_eq!(encode::<f64>((1 << 54) - 1, 970, 1), ::std::f64::INFINITY);
+        assert_eq!(encode::<f64>(u64::max_value(), 960, -1), ::std::f64::NEG_INFINITY);
+        assert!(encode::<ExtendedFloat>(u64::max_value(), 16321, 1).is_infinite());
+        assert!(encode::<ExtendedFloat>((1 << 63) | 1, 16321, 1).is_infinite());
+    }
+
+    #[test]
+    fn next() {
+        assert_eq!(next_up(1.0f64), 1.0 + ::std::f64::EPSILON);
+        assert_eq!(next_down(1.0f64), 1.0 - ::std::f64::EPSILON / 2.0);
+        assert_eq!(next_up(0.0f64), 5e-324);
+        assert_eq!(next_up(-0.0f64), 5e-324);
+        assert_eq!(next_down(0.0f64), -5e-324);
+        assert_eq!(next_up(-5e-324f64).to_bits(), (-0.0f64).to_bits());
+        assert_eq!(next_up(::std::f64::MAX), ::std::f64::INFINITY);
+        assert_eq!(next_up(::std::f64::INFINITY), ::std::f64::INFINITY);
+        assert_eq!(next_up(::std::f64::NEG_INFINITY), ::std::f64::MIN);
+        assert_eq!(next_down(::std::f64::NEG_INFINITY), ::std::f64::NEG_INFINITY);
+        assert!(next_up(::std::f64::NAN).is_nan());
+        assert_eq!(next_up(::std::f32::MAX), ::std::f32::INFINITY);
+        let one = ExtendedFloat::from(1.0);
+        assert_eq!(next_up(one) - one, ExtendedFloat::from(::std::f64::EPSILON / 2048.0));
+    }
+
+    #[quickcheck]
+    fn decode_roundtrip(bits: AnyBits) -> bool {
+        roundtrip(bits.get::<f32>()) && roundtrip(bits.get::<f64>()) &&
+            roundtrip(bits.get::<ExtendedFloat>())
+    }
+
+    #[quickcheck]
+    fn decode_is_exact(bits: AnyBits) -> bool {
+        let value: f64 = bits.get();
+        let (mantissa, exponent, sign) = decode(value);
+        let expected = Float::with_val(64, mantissa) << exponent as i32;
+        !value.is_finite() || (if sign < 0 { -expected } else { expected }) == value
+    }
+
+    #[quickcheck]
+    fn encode_matches_rug(mantissa: u64, exponent: i16) -> bool {
+        // Spread the exponent over the whole range, including past both ends
+        let exponent = (exponent as i32).wrapping_mul(13) % 1200;
+        let mantissa = mantissa.wrapping_mul(0x9E3779B97F4A7C15);
+        let expected = (Float::with_val(64, mantissa) << exponent).to_f64();
+        encode::<f64>(mantissa, exponent, 1).to_bits() == expected.to_bits()
+    }
+
+    #[quickcheck]
+    fn classify_matches_std(bits: AnyBits) -> bool {
+        let double: f64 = bits.get();
+        let single: f32 = bits.get();
+        classify(double) == double.classify() && classify(single) == single.classify()
+    }
+
+    #[quickcheck]
+    fn next_is_adjacent(bits: AnyBits) -> bool {
+        let value: f64 = bits.get();
+        match classify(value) {
+            FpCategory::Nan | FpCategory::Infinite => true,
+            _ => {
+                let (up, down) = (next_up(value), next_down(value));
+                up > value && down < value &&
+                    (value == 0.0 || next_down(up) == value && next_up(down) == value)
+            }
+        }
+    }
+}
@@ -0,0 +1,779 @@
+//! Double-double arithmetic, which represents a number as the unevaluated sum of two doubles.
+//!
+//! The low part holds the rounding error of the high part,
+//! so together they have about 106 bits of precision with the same exponent range as a double.
+//! That's far less than `rug` can do, but it never allocates and is only a handful of double operations,
+//! and unlike `ExtendedFloat` it works on every platform.
+//!
+//! The arithmetic follows the algorithms in the QD library by Hida, Li and Bailey,
+//! built out of the error-free transformations in `utils`.
+use std::error::Error;
+use std::fmt::{self, Display, LowerExp};
+use std::ops::{Add, Sub, Mul, Div, Neg, AddAssign, SubAssign, MulAssign, DivAssign};
+use std::str::FromStr;
+
+use rug::Float;
+
+use exponent::{frexp, ldexp};
+use log::{ln, log10};
+use reduce;
+use sqrt::sqrt_correctly_rounded;
+use utils::{two_sum, mul_double_double};
+
+/// The value of `ln(2)` split into three doubles, so the reduction in `exp` is accurate for any quotient
+const LN_2_PARTS: (f64, f64, f64) = (0.6931471805599453, 2.3190468138462996e-17, 5.707708438416212e-34);
+/// The number of times `exp` halves the reduced argument (and then squares the result)
+const EXP_SQUARINGS: i32 = 10;
+// Enough for the Taylor series to converge on [-ln(2)/2^11, ln(2)/2^11]
+const EXP_APPROX_ORDER: usize = 10;
+// Enough terms for the Taylor series of sine and cosine to converge on [-pi/4, pi/4]
+const TRIG_APPROX_TERMS: u32 = 15;
+/// The number of significant digits we print by default, which parses back to within `2^-100`.
+///
+/// Round tripping every one of the 106 bits would take 33 digits,
+/// but the decimal conversions themselves are only that accurate anyway.
+const DEFAULT_DIGITS: usize = 32;
+/// The largest power of ten we scale by at once, which keeps the power itself from overflowing
+const MAX_DECIMAL_STEP: i32 = 300;
+
+/// A double-double number, with about 106 bits of precision.
+///
+/// The value is always normalized so the low part is at most half an ulp of the high part,
+/// which means the high part is the nearest double to the whole value.
+/// Non-finite values always have a low part of zero.
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct f64x2 {
+    hi: f64,
+    lo: f64,
+}
+impl f64x2 {
+    pub const ZERO: f64x2 = f64x2 { hi: 0.0, lo: 0.0 };
+    pub const ONE: f64x2 = f64x2 { hi: 1.0, lo: 0.0 };
+    pub const PI: f64x2 = f64x2 { hi: 3.141592653589793, lo: 1.2246467991473532e-16 };
+    pub const FRAC_PI_2: f64x2 = f64x2 { hi: 1.5707963267948966, lo: 6.123233995736766e-17 };
+    pub const E: f64x2 = f64x2 { hi: 2.718281828459045, lo: 1.4456468917292502e-16 };
+    pub const LN_2: f64x2 = f64x2 { hi: LN_2_PARTS.0, lo: LN_2_PARTS.1 };
+    pub const NAN: f64x2 = f64x2 { hi: ::std::f64::NAN, lo: 0.0 };
+    pub const INFINITY: f64x2 = f64x2 { hi: ::std::f64::INFINITY, lo: 0.0 };
+    pub const NEG_INFINITY: f64x2 = f64x2 { hi: ::std::f64::NEG_INFINITY, lo: 0.0 };
+
+    /// Creates the double-double `hi + lo`, normalizing it if the parts overlap
+    #[inline]
+    pub fn new(hi: f64, lo: f64) -> f64x2 {
+        let (hi, lo) = two_sum(hi, lo);
+        f64x2::from_parts(hi, lo)
+    }
+    /// Creates a double-double from parts that are already normalized,
+    /// dropping the low part if the high part isn't finite (where it'd just be NaN).
+    #[inline]
+    fn from_parts(hi: f64, lo: f64) -> f64x2 {
+        if hi.is_finite() {
+            f64x2 { hi, lo }
+        } else {
+            f64x2 { hi, lo: 0.0 }
+        }
+    }
+    /// Computes the exact product of two doubles
+    #[inline]
+    fn product(first: f64, second: f64) -> f64x2 {
+        let product = first * second;
+        f64x2::from_parts(product, first.mul_add(second, -product))
+    }
+    #[inline]
+    pub fn hi(self) -> f64 {
+        self.hi
+    }
+    #[inline]
+    pub fn lo(self) -> f64 {
+        self.lo
+    }
+    /// Rounds to the nearest double, which is just the high part
+    #[inline]
+    pub fn to_f64(self) -> f64 {
+        self.hi
+    }
+    #[inline]
+    pub fn is_nan(self) -> bool {
+        self.hi.is_nan()
+    }
+    #[inline]
+    pub fn is_infinite(self) -> bool {
+        self.hi.is_infinite()
+    }
+    #[inline]
+    pub fn is_finite(self) -> bool {
+        self.hi.is_finite()
+    }
+    #[inline]
+    pub fn is_sign_negative(self) -> bool {
+        self.hi.is_sign_negative()
+    }
+    #[inline]
+    pub fn abs(self) -> f64x2 {
+        if self.is_sign_negative() { -self } else { self }
+    }
+    /// Multiplies the value by `2^exponent`, which is exact unless the result becomes subnormal
+    #[inline]
+    pub fn ldexp(self, exponent: i32) -> f64x2 {
+        f64x2::from_parts(ldexp(self.hi, exponent), ldexp(self.lo, exponent))
+    }
+    /// Raises the value to an integer power by repeated squaring
+    pub fn powi(self, power: i32) -> f64x2 {
+        let mut base = self;
+        let mut remaining = power.wrapping_abs() as u32;
+        let mut result = f64x2::ONE;
+        while remaining != 0 {
+            if remaining & 1 != 0 {
+                result *= base;
+            }
+            base *= base;
+            remaining >>= 1;
+        }
+        if power < 0 { f64x2::ONE / result } else { result }
+    }
+    /// Converts this value into an arbitrary-precision float,
+    /// which is exact as long as the precision spans both parts (at least 107 bits).
+    pub fn to_big(self, precision: u32) -> Float {
+        Float::with_val(precision, self.hi) + self.lo
+    }
+    /// Rounds an arbitrary-precision float to the nearest double-double
+    pub fn from_big(value: &Float) -> f64x2 {
+        let hi = value.to_f64();
+        if !hi.is_finite() {
+            return f64x2::from_parts(hi, 0.0)
+        }
+        let lo = Float::with_val(value.prec().max(53), value - hi).to_f64();
+        f64x2::new(hi, lo)
+    }
+
+    /// Computes the square root
+    pub fn sqrt(self) -> f64x2 {
+        if self.is_nan() || self.hi <= 0.0 || self.is_infinite() {
+            // This handles negative numbers and keeps the sign of negative zero
+            return f64x2::from(sqrt_correctly_rounded(self.hi))
+        }
+        // One Newton step from the correctly rounded root of the high part doubles the precision
+        let root = sqrt_correctly_rounded(self.hi);
+        let residual = (self - f64x2::product(root, root)).hi;
+        let (hi, lo) = two_sum(root, residual / (2.0 * root));
+        f64x2::from_parts(hi, lo)
+    }
+    /// Computes `e^x`
+    pub fn exp(self) -> f64x2 {
+        if self.is_nan() {
+            return self
+        } else if self.hi > 709.8 {
+            return f64x2::INFINITY
+        } else if self.hi < -745.2 {
+            return f64x2::ZERO
+        }
+        /*
+         * Reduce to `x = k * ln(2) + r` where `|r| <= ln(2)/2`, so that `e^x = 2^k * e^r`.
+         * The products with the first two parts of ln(2) are exact, and the third is far past our precision.
+         */
+        let quotient = (self.hi / LN_2_PARTS.0).round();
+        let reduced = (self - f64x2::product(quotient, LN_2_PARTS.0))
+            - f64x2::product(quotient, LN_2_PARTS.1)
+            - quotient * LN_2_PARTS.2;
+        /*
+         * Dividing by a power of two makes the series converge much faster,
+         * and then we undo it by squaring: e^2r - 1 = (e^r - 1) * (e^r + 1).
+         * Working with e^r - 1 instead of e^r keeps the tiny result from losing bits to the leading one.
+         */
+        let mut result = exp_m1_0(reduced.ldexp(-EXP_SQUARINGS));
+        for _ in 0..EXP_SQUARINGS {
+            result = result * (result + 2.0);
+        }
+        (result + 1.0).ldexp(quotient as i32)
+    }
+    /// Computes the natural logarithm
+    pub fn ln(self) -> f64x2 {
+        if self.is_nan() || self.hi < 0.0 {
+            return f64x2::NAN
+        } else if self.hi == 0.0 {
+            return f64x2::NEG_INFINITY
+        } else if self.is_infinite() {
+            return self
+        }
+        /*
+         * Reduce to ln(x) = ln(m) + k * ln(2) where m is in [sqrt(1/2), sqrt(2)],
+         * so e^-y never overflows or becomes subnormal below, and results near zero don't cancel.
+         */
+        let (mantissa, mut exponent) = frexp(self.hi);
+        if mantissa < ::std::f64::consts::FRAC_1_SQRT_2 {
+            exponent -= 1;
+        }
+        let reduced = self.ldexp(-exponent);
+        /*
+         * Newton's method on e^y = x, starting from the double precision logarithm.
+         * Each step doubles the correct bits, so one is enough.
+         */
+        let estimate = f64x2::from(ln(reduced.hi));
+        let result = estimate + (reduced * (-estimate).exp() - 1.0);
+        result + f64x2::LN_2 * f64::from(exponent)
+    }
+    /// Computes the sine
+    pub fn sin(self) -> f64x2 {
+        if !self.is_finite() {
+            return f64x2::NAN
+        } else if self.hi == 0.0 {
+            return self
+        }
+        // Reduce to [-pi/4, pi/4] and then pick the right function for the quadrant
+        let (quadrant, reduced) = self.rem_pio2();
+        match quadrant & 3 {
+            0 => sin_reduced(reduced),
+            1 => cos_reduced(reduced),
+            2 => -sin_reduced(reduced),
+            _ => -cos_reduced(reduced),
+        }
+    }
+    /// Computes the cosine
+    pub fn cos(self) -> f64x2 {
+        if !self.is_finite() {
+            return f64x2::NAN
+        }
+        let (quadrant, reduced) = self.rem_pio2();
+        match quadrant & 3 {
+            0 => cos_reduced(reduced),
+            1 => -sin_reduced(reduced),
+            2 => -cos_reduced(reduced),
+            _ => sin_reduced(reduced),
+        }
+    }
+    /// Returns a tuple of the sine and cosine
+    #[inline]
+    pub fn sin_cos(self) -> (f64x2, f64x2) {
+        (self.sin(), self.cos())
+    }
+    /// Reduces the argument to `x - n * pi/2`, returning the lowest bits of the quadrant `n` and the remainder.
+    ///
+    /// Subtracting multiples of pi/2 only gives a remainder that's accurate in absolute terms,
+    /// which falls apart right next to a multiple where the remainder is tiny,
+    /// so both parts go through Payne-Hanek together instead.
+    fn rem_pio2(self) -> (i32, f64x2) {
+        if self.hi.abs() <= ::std::f64::consts::FRAC_PI_4 {
+            return (0, self)
+        }
+        let (quadrant, hi, lo) = reduce::rem_pio2_double_double(self.hi, self.lo);
+        (quadrant & 3, f64x2::new(hi, lo))
+    }
+}
+
+/// Computes `e^x - 1` for a tiny `x` with the Taylor series,
+/// evaluated from the inside out as `x * (1 + x/2 * (1 + x/3 * (1 + ...)))`.
+fn exp_m1_0(x: f64x2) -> f64x2 {
+    let mut n = EXP_APPROX_ORDER;
+    let mut result = f64x2::ONE;
+    while n > 1 {
+        result = x * result / n as f64 + 1.0;
+        n -= 1;
+    }
+    x * result
+}
+
+/// Evaluates `1 - x^2/(n(n+1)) * (1 - x^2/((n+2)(n+3)) * (1 - ...))` from the inside out,
+/// which is the Taylor series of `sin(x)/x` starting from `n = 2` and of `cos(x)` starting from `n = 1`.
+fn alternating_series(square: f64x2, first: u32) -> f64x2 {
+    let mut n = first + 2 * TRIG_APPROX_TERMS;
+    let mut result = f64x2::ONE;
+    while n > first {
+        n -= 2;
+        result = f64x2::ONE - square * result / f64::from(n * (n + 1));
+    }
+    result
+}
+
+#[inline]
+fn sin_reduced(x: f64x2) -> f64x2 {
+    x * alternating_series(x * x, 2)
+}
+
+#[inline]
+fn cos_reduced(x: f64x2) -> f64x2 {
+    alternating_series(x * x, 1)
+}
+
+impl From<f64> for f64x2 {
+    #[inline]
+    fn from(value: f64) -> f64x2 {
+        f64x2::from_parts(value, 0.0)
+    }
+}
+impl From<f64x2> for f64 {
+    #[inline]
+    fn from(value: f64x2) -> f64 {
+        value.to_f64()
+    }
+}
+
+impl Neg for f64x2 {
+    type Output = f64x2;
+    #[inline]
+    fn neg(self) -> f64x2 {
+        f64x2 { hi: -self.hi, lo: -self.lo }
+    }
+}
+impl Add for f64x2 {
+    type Output = f64x2;
+    fn add(self, other: f64x2) -> f64x2 {
+        let sum = self.hi + other.hi;
+        if !sum.is_finite() {
+            return f64x2::from(sum)
+        }
+        /*
+         * Add the high and low parts separately, keeping the error of each,
+         * which is accurate even when the high parts cancel out.
+         */
+        let (hi, error) = two_sum(self.hi, other.hi);
+        let (lo, lo_error) = two_sum(self.lo, other.lo);
+        let (hi, error) = quick_two_sum(hi, error + lo);
+        let (hi, lo) = quick_two_sum(hi, error + lo_error);
+        f64x2::from_parts(hi, lo)
+    }
+}
+impl Sub for f64x2 {
+    type Output = f64x2;
+    #[inline]
+    fn sub(self, other: f64x2) -> f64x2 {
+        self + (-other)
+    }
+}
+impl Mul for f64x2 {
+    type Output = f64x2;
+    fn mul(self, other: f64x2) -> f64x2 {
+        let product = self.hi * other.hi;
+        if !product.is_finite() {
+            return f64x2::from(product)
+        }
+        let (hi, lo) = mul_double_double((self.hi, self.lo), (other.hi, other.lo));
+        f64x2::from_parts(hi, lo)
+    }
+}
+impl Div for f64x2 {
+    type Output = f64x2;
+    fn div(self, other: f64x2) -> f64x2 {
+        let quotient = self.hi / other.hi;
+        if !quotient.is_finite() || quotient == 0.0 {
+            return f64x2::from(quotient)
+        }
+        // Long division, where each partial quotient corrects the remainder of the previous ones
+        let remainder = self - other * quotient;
+        let second = remainder.hi / other.hi;
+        let remainder = remainder - other * second;
+        let third = remainder.hi / other.hi;
+        let (hi, lo) = quick_two_sum(quotient, second);
+        f64x2::from_parts(hi, lo) + third
+    }
+}
+
+/// Adds two doubles where the first is known to be larger in magnitude,
+/// returning the rounded sum and the exact rounding error.
+#[inline]
+fn quick_two_sum(larger: f64, smaller: f64) -> (f64, f64) {
+    let sum = larger + smaller;
+    (sum, smaller - (sum - larger))
+}
+
+macro_rules! impl_ops {
+    ($($target:ident, $method:ident, $assign_target:ident, $assign_method:ident);*) => {$(
+        impl $target<f64> for f64x2 {
+            type Output = f64x2;
+            #[inline]
+            fn $method(self, other: f64) -> f64x2 {
+                self.$method(f64x2::from(other))
+            }
+        }
+        impl $assign_target for f64x2 {
+            #[inline]
+            fn $assign_method(&mut self, other: f64x2) {
+                *self = (*self).$method(other);
+            }
+        }
+        impl $assign_target<f64> for f64x2 {
+            #[inline]
+            fn $assign_method(&mut self, other: f64) {
+                *self = (*self).$method(f64x2::from(other));
+            }
+        }
+    )*};
+}
+impl_ops!(
+    Add, add, AddAssign, add_assign;
+    Sub, sub, SubAssign, sub_assign;
+    Mul, mul, MulAssign, mul_assign;
+    Div, div, DivAssign, div_assign
+);
+
+/// Multiplies the value by `10^exponent`, in steps small enough that the power never overflows
+fn scale_decimal(mut value: f64x2, mut exponent: i32) -> f64x2 {
+    let ten = f64x2::from(10.0);
+    while exponent != 0 && value.hi != 0.0 && value.is_finite() {
+        let step = exponent.max(-MAX_DECIMAL_STEP).min(MAX_DECIMAL_STEP);
+        // Dividing by the power is more accurate than multiplying by its (inexact) reciprocal
+        value = if step > 0 { value * ten.powi(step) } else { value / ten.powi(-step) };
+        exponent -= step;
+    }
+    value
+}
+
+/// An error parsing a double-double from a string
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseDoubleDoubleError {
+    _private: (),
+}
+impl Display for ParseDoubleDoubleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid double-double literal")
+    }
+}
+impl Error for ParseDoubleDoubleError {
+    fn description(&self) -> &str {
+        "invalid double-double literal"
+    }
+}
+
+impl FromStr for f64x2 {
+    type Err = ParseDoubleDoubleError;
+    /// Parses a decimal number like `-1.25e-3`, along with `inf`, `infinity` and `nan`.
+    ///
+    /// The first 36 significant digits are kept exactly, which is more than enough for 106 bits.
+    fn from_str(text: &str) -> Result<f64x2, ParseDoubleDoubleError> {
+        let error = ParseDoubleDoubleError { _private: () };
+        let (negative, text) = if text.starts_with('-') {
+            (true, &text[1..])
+        } else if text.starts_with('+') {
+            (false, &text[1..])
+        } else {
+            (false, text)
+        };
+        let lower = text.to_ascii_lowercase();
+        let magnitude = match &*lower {
+            "inf" | "infinity" => f64x2::INFINITY,
+            "nan" => f64x2::NAN,
+            _ => {
+                let (mantissa, mut exponent) = match lower.find('e') {
+                    Some(index) => (&lower[..index], lower[index + 1..].parse::<i32>().map_err(|_| error.clone())?),
+                    None => (&*lower, 0),
+                };
+                let mut significand = 0u128;
+                let mut significant_digits = 0;
+                let mut any_digits = false;
+                let mut seen_point = false;
+                for c in mantissa.chars() {
+                    match c {
+                        '.' if !seen_point => seen_point = true,
+                        '0'..='9' => {
+                            any_digits = true;
+                            if significant_digits < 36 {
+                                significand = significand * 10 + c.to_digit(10).unwrap() as u128;
+                                if significand != 0 {
+                                    significant_digits += 1;
+                                }
+                                if seen_point {
+                                    exponent = exponent.saturating_sub(1);
+                                }
+                            } else if !seen_point {
+                                // Digits we can't keep still count toward the magnitude
+                                exponent = exponent.saturating_add(1);
+                            }
+                        },
+                        _ => return Err(error),
+                    }
+                }
+                if !any_digits {
+                    return Err(error)
+                }
+                // The significand has up to 120 bits, so it needs both parts (and the rest is negligible)
+                let hi = significand as f64;
+                let lo = (significand as i128 - hi as i128) as f64;
+                // Anything this far out is going to overflow or underflow anyway
+                scale_decimal(f64x2::new(hi, lo), exponent.max(-1000).min(1000))
+            },
+        };
+        Ok(if negative { -magnitude } else { magnitude })
+    }
+}
+
+impl Display for f64x2 {
+    /// Formats the value in scientific notation, just like `LowerExp`
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        LowerExp::fmt(self, f)
+    }
+}
+impl LowerExp for f64x2 {
+    /// Formats the value in scientific notation like `1.25e-3`.
+    ///
+    /// The precision is the number of digits after the decimal point,
+    /// and otherwise we print enough digits to parse back to within `2^-100` (without trailing zeros).
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_sign_negative() && !self.is_nan() {
+            f.write_str("-")?;
+        } else if f.sign_plus() {
+            f.write_str("+")?;
+        }
+        if self.is_nan() {
+            return f.write_str("NaN")
+        } else if self.is_infinite() {
+            return f.write_str("inf")
+        }
+        let fraction_digits = f.precision().unwrap_or(DEFAULT_DIGITS - 1);
+        let (digits, exponent) = if self.hi == 0.0 {
+            (vec![0; fraction_digits + 1], 0)
+        } else {
+            decimal_digits(self.abs(), fraction_digits + 1)
+        };
+        let mut fraction = &digits[1..];
+        if f.precision().is_none() {
+            while fraction.last() == Some(&0) {
+                fraction = &fraction[..fraction.len() - 1];
+            }
+        }
+        write!(f, "{}", digits[0])?;
+        if !fraction.is_empty() {
+            f.write_str(".")?;
+            for digit in fraction {
+                write!(f, "{}", digit)?;
+            }
+        }
+        write!(f, "e{}", exponent)
+    }
+}
+
+/// Returns the specified number of significant decimal digits of a positive finite value,
+/// rounded to nearest, along with the decimal exponent of the first one.
+fn decimal_digits(value: f64x2, count: usize) -> (Vec<u8>, i32) {
+    debug_assert!(value.hi > 0.0 && value.is_finite() && count > 0);
+    // This estimate of the exponent can be off by one, which the first digit fixes below
+    let mut exponent = log10(value.hi).floor() as i32;
+    let mut remaining = scale_decimal(value, -exponent);
+    /*
+     * Pull off one digit at a time, with a couple extra for rounding.
+     * Since the remainder is only approximately in [0, 10), a digit can come out as -1 or 10,
+     * so we carry those into the previous digit afterwards.
+     */
+    let mut digits = Vec::with_capacity(count + 2);
+    for _ in 0..count + 2 {
+        let digit = remaining.hi.floor();
+        digits.push(digit as i32);
+        remaining = (remaining - digit) * 10.0;
+    }
+    for index in (1..digits.len()).rev() {
+        if digits[index] < 0 {
+            digits[index] += 10;
+            digits[index - 1] -= 1;
+        } else if digits[index] > 9 {
+            digits[index] -= 10;
+            digits[index - 1] += 1;
+        }
+    }
+    if digits[0] == 0 {
+        digits.remove(0);
+        exponent -= 1;
+    } else if digits[0] > 9 {
+        digits.insert(1, digits[0] - 10);
+        digits[0] = 1;
+        exponent += 1;
+    }
+    // Round to nearest using the next digit, carrying as far as we need to
+    let round_up = digits[count] >= 5;
+    digits.truncate(count);
+    if round_up {
+        let mut index = count;
+        loop {
+            if index == 0 {
+                // Every digit was a nine, so the result is a power of ten
+                digits.insert(0, 1);
+                digits.truncate(count);
+                exponent += 1;
+                break
+            }
+            index -= 1;
+            if digits[index] == 9 {
+                digits[index] = 0;
+            } else {
+                digits[index] += 1;
+                break
+            }
+        }
+    }
+    (digits.into_iter().map(|digit| digit as u8).collect(), exponent)
+}
+
+#[cfg(test)]
+mod test {
+    use super::f64x2;
+    use rug::Float;
+
+    const PRECISION: u32 = 256;
+    /// The relative error we allow, which leaves a few bits of slack below the 106 we have
+    const RELATIVE_ERROR: f64 = 7.888609052210118e-31; // 2^-100
+
+    /// Makes a double-double with a non-trivial low part out of two doubles
+    fn arbitrary(first: f64, second: f64) -> f64x2 {
+        f64x2::from(first) / f64x2::from(second.abs() + 1.0)
+    }
+
+    fn error(actual: f64x2, exact: &Float) -> f64 {
+        let difference = Float::with_val(PRECISION, actual.to_big(PRECISION) - exact);
+        if exact.is_zero() {
+            difference.to_f64().abs()
+        } else {
+            (difference / exact).to_f64().abs()
+        }
+    }
+
+    #[test]
+    fn basic() {
+        let third = f64x2::ONE / 3.0;
+        assert_eq!(third.hi(), 1.0 / 3.0);
+        assert!(error(third, &(Float::with_val(PRECISION, 1) / 3)) <= RELATIVE_ERROR);
+        assert_eq!((third * 3.0).hi(), 1.0);
+        assert_eq!(f64x2::from(2.0).sqrt().hi(), ::std::f64::consts::SQRT_2);
+        assert_eq!(f64x2::from(4.0).sqrt(), f64x2::from(2.0));
+        assert_eq!(f64x2::ZERO.exp(), f64x2::ONE);
+        assert_eq!(f64x2::ONE.ln(), f64x2::ZERO);
+        assert!(error(f64x2::ONE.exp(), &Float::with_val(PRECISION, 1).exp()) <= RELATIVE_ERROR);
+        assert!(error(f64x2::E.ln(), &Float::with_val(PRECISION, 1)) <= RELATIVE_ERROR);
+        assert!(f64x2::from(-1.0).sqrt().is_nan());
+        assert!(f64x2::from(-1.0).ln().is_nan());
+        assert_eq!(f64x2::ZERO.ln(), f64x2::NEG_INFINITY);
+        assert_eq!(f64x2::from(1000.0).exp(), f64x2::INFINITY);
+        assert_eq!(f64x2::INFINITY + f64x2::ONE, f64x2::INFINITY);
+        assert!(f64x2::INFINITY.sin().is_nan());
+        assert!(f64x2::from(1.0) < f64x2::new(1.0, 1e-20));
+        assert!(f64x2::new(1.0, -1e-20) < f64x2::from(1.0));
+    }
+
+    #[test]
+    fn special_values() {
+        assert_eq!((-f64x2::ZERO).sqrt().hi().to_bits(), (-0.0f64).to_bits());
+        assert_eq!((-f64x2::ZERO).sin().hi().to_bits(), (-0.0f64).to_bits());
+        assert_eq!(f64x2::ZERO.cos(), f64x2::ONE);
+        assert_eq!(f64x2::INFINITY.sqrt(), f64x2::INFINITY);
+        assert_eq!(f64x2::NEG_INFINITY.exp(), f64x2::ZERO);
+        assert!(f64x2::NAN.exp().is_nan());
+        assert_eq!(f64x2::from(2.0).powi(-2), f64x2::from(0.25));
+        assert_eq!(f64x2::from(1.0) / 0.0, f64x2::INFINITY);
+    }
+
+    #[test]
+    fn parse() {
+        let tenth = "0.1".parse::<f64x2>().unwrap();
+        assert!(error(tenth, &Float::with_val(PRECISION, Float::parse("0.1").unwrap())) <= RELATIVE_ERROR);
+        assert_eq!("-1.5e3".parse::<f64x2>().unwrap(), f64x2::from(-1500.0));
+        assert_eq!("+25".parse::<f64x2>().unwrap(), f64x2::from(25.0));
+        assert_eq!(".5".parse::<f64x2>().unwrap(), f64x2::from(0.5));
+        assert_eq!("1e400".parse::<f64x2>().unwrap(), f64x2::INFINITY);
+        assert_eq!("1e-400".parse::<f64x2>().unwrap(), f64x2::ZERO);
+        assert_eq!("-Infinity".parse::<f64x2>().unwrap(), f64x2::NEG_INFINITY);
+        assert!("nan".parse::<f64x2>().unwrap().is_nan());
+        let pi = "3.14159265358979323846264338327950288".parse::<f64x2>().unwrap();
+        assert!(error(pi, &f64x2::PI.to_big(PRECISION)) <= RELATIVE_ERROR);
+        for invalid in &["", "-", ".", "e5", "1e", "1.2.3", "1x", "--1"] {
+            assert!(invalid.parse::<f64x2>().is_err(), "Parsed {:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn format() {
+        assert_eq!(f64x2::ONE.to_string(), "1e0");
+        assert_eq!(f64x2::from(-1500.0).to_string(), "-1.5e3");
+        assert_eq!(format!("{:.3e}", f64x2::from(0.00125)), "1.250e-3");
+        assert_eq!(format!("{:+e}", f64x2::from(2.0)), "+2e0");
+        assert_eq!(format!("{:.2}", f64x2::from(9.999)), "1.00e1");
+        assert_eq!(f64x2::ZERO.to_string(), "0e0");
+        assert_eq!(f64x2::NEG_INFINITY.to_string(), "-inf");
+        assert_eq!(f64x2::NAN.to_string(), "NaN");
+        assert_eq!(f64x2::PI.to_string(), "3.1415926535897932384626433832795e0");
+        assert_eq!((f64x2::ONE / 3.0).to_string(), "3.3333333333333333333333333333333e-1");
+    }
+
+    #[quickcheck]
+    fn arithmetic_matches_rug(a: f64, b: f64, c: f64, d: f64) -> bool {
+        let (x, y) = (arbitrary(a, b), arbitrary(c, d));
+        let (big_x, big_y) = (x.to_big(PRECISION), y.to_big(PRECISION));
+        let quotient = y.hi() == 0.0 || error(x / y, &Float::with_val(PRECISION, &big_x / &big_y)) <= RELATIVE_ERROR;
+        error(x + y, &Float::with_val(PRECISION, &big_x + &big_y)) <= RELATIVE_ERROR &&
+            error(x - y, &Float::with_val(PRECISION, &big_x - &big_y)) <= RELATIVE_ERROR &&
+            error(x * y, &Float::with_val(PRECISION, &big_x * &big_y)) <= RELATIVE_ERROR &&
+            quotient
+    }
+
+    #[quickcheck]
+    fn sqrt_matches_rug(a: f64, b: f64) -> bool {
+        let x = arbitrary(a, b).abs();
+        error(x.sqrt(), &x.to_big(PRECISION).sqrt()) <= RELATIVE_ERROR
+    }
+
+    #[quickcheck]
+    fn exp_matches_rug(a: f64, b: f64) -> bool {
+        let x = arbitrary(a, b);
+        // Past about e^-650 the low part starts losing bits to underflow
+        let big = x * 6.0;
+        error(x.exp(), &x.to_big(PRECISION).exp()) <= RELATIVE_ERROR &&
+            error(big.exp(), &big.to_big(PRECISION).exp()) <= RELATIVE_ERROR
+    }
+
+    #[quickcheck]
+    fn ln_matches_rug(a: f64, b: f64) -> bool {
+        let x = arbitrary(a, b).abs();
+        if x.hi() == 0.0 {
+            return x.ln() == f64x2::NEG_INFINITY
+        }
+        let tiny = x * 1e-300;
+        let exact = x.to_big(PRECISION).ln();
+        // Near one the logarithm is tiny, so the error is only small compared to the input
+        let allowed = RELATIVE_ERROR * exact.to_f64().abs().max(1.0);
+        error(x.ln(), &exact) * exact.to_f64().abs() <= allowed &&
+            error(tiny.ln(), &tiny.to_big(PRECISION).ln()) <= RELATIVE_ERROR
+    }
+
+    #[quickcheck]
+    fn trig_matches_rug(a: f64, b: f64) -> bool {
+        let x = arbitrary(a, b);
+        let big = x * 1e5;
+        error(x.sin(), &x.to_big(PRECISION).sin()) <= RELATIVE_ERROR &&
+            error(x.cos(), &x.to_big(PRECISION).cos()) <= RELATIVE_ERROR &&
+            error(big.sin(), &big.to_big(PRECISION).sin()) <= RELATIVE_ERROR &&
+            error(big.cos(), &big.to_big(PRECISION).cos()) <= RELATIVE_ERROR
+    }
+
+    /// Checks sine and cosine against rug, which reduces even huge arguments exactly
+    fn trig_close(x: f64x2) -> bool {
+        // Plenty to hold the whole double-double, however far apart the parts are
+        let exact = x.to_big(4096);
+        error(x.sin(), &exact.clone().sin()) <= RELATIVE_ERROR && error(x.cos(), &exact.cos()) <= RELATIVE_ERROR
+    }
+
+    #[test]
+    fn trig_large() {
+        // The last one comes closer to a multiple of pi/2 than any other double, leaving a remainder around 2^-61
+        for &x in &[1e12, 1e22, ::std::f64::MAX, 6381956970095103.0 * 2.0f64.powi(797)] {
+            assert!(trig_close(f64x2::from(x)), "{}", x);
+            assert!(trig_close(-f64x2::new(x, x * 1e-17)), "{}", -x);
+        }
+        // The doubles nearest to multiples of pi/2 need the remainder's low part too
+        for &x in &[::std::f64::consts::FRAC_PI_2, 3.0 * ::std::f64::consts::FRAC_PI_2, 1e6 * ::std::f64::consts::PI] {
+            assert!(trig_close(f64x2::from(x)), "{}", x);
+        }
+    }
+
+    #[quickcheck]
+    fn trig_matches_rug_large(a: f64, b: f64, power: u16) -> bool {
+        // The low part of huge arguments can be large enough to need reducing too
+        let x = arbitrary(a, b).ldexp(i32::from(power % 960));
+        !x.is_finite() || trig_close(x)
+    }
+
+    #[quickcheck]
+    fn format_parses_back_within_error(a: f64, b: f64, power: i8) -> bool {
+        let x = arbitrary(a, b) * f64x2::from(10.0).powi(i32::from(power));
+        let parsed = x.to_string().parse::<f64x2>().unwrap();
+        let printed = Float::with_val(PRECISION, Float::parse(x.to_string()).unwrap());
+        error(parsed, &x.to_big(PRECISION)) <= RELATIVE_ERROR &&
+            error(f64x2::from_big(&printed), &x.to_big(PRECISION)) <= RELATIVE_ERROR
+    }
+}
@@ -0,0 +1,281 @@
+//! Exponential functions
+use bits;
+use float::ToyFloat;
+
+/// The high bits of `ln(2)`, with enough trailing zeros that multiplying by the quotient is exact
+const LN2_HI: f64 = 6.93147180369123816490e-01;
+/// The value of `ln(2) - LN2_HI`
+const LN2_LO: f64 = 1.90821492927058770002e-10;
+/// The value of `ln(2) - f64::consts::LN_2`, used to extend the product in `exp2`
+const LN2_RESIDUAL: f64 = 2.3190468138462996e-17;
+/// The value of `log2(e)`
+const LOG2_E: f64 = 1.44269504088896338700e+00;
+// Enough for the Taylor series to converge on [-ln(2)/2, ln(2)/2]
+const EXP_APPROX_ORDER: usize = 18;
+/*
+ * Coefficients of the minimax polynomial for `r*(e^r + 1)/(e^r - 1)` on [-ln(2)/2, ln(2)/2],
+ * which approximates e^r to within 2^-59 after the rational correction in `exp_reduced`.
+ * These are the same coefficients that fdlibm uses.
+ */
+const P1: f64 = 1.66666666666666019037e-01;
+const P2: f64 = -2.77777777770155933842e-03;
+const P3: f64 = 6.61375632143793436117e-05;
+const P4: f64 = -1.65339022054652515390e-06;
+const P5: f64 = 4.13813679705723846039e-08;
+
+/// Computes `e^x`
+pub fn exp<T: ToyFloat>(x: T) -> T {
+    if x.is_nan() {
+        return x;
+    }
+    /*
+     * Anything past the exponent range is going to overflow to infinity or underflow to zero,
+     * and clamping here keeps the quotient from overflowing an integer.
+     */
+    let limit = T::from_i32(2 * T::EXPONENT_BIAS + T::MANTISSA_DIGITS as i32) * T::LN_2;
+    if x > limit {
+        return T::INFINITY;
+    } else if x < -limit {
+        return T::ZERO;
+    }
+    /*
+     * Reduce to `x = k * ln(2) + r` where `|r| <= ln(2)/2`,
+     * so that `e^x = 2^k * e^r` and the polynomial only has to cover a small interval.
+     * Splitting ln(2) into two parts keeps the reduced argument accurate.
+     */
+    let quotient = (x.to_f64() * LOG2_E).round();
+    if T::MANTISSA_DIGITS <= 53 {
+        // Everything fits in a double, so we can use the minimax polynomial directly
+        let x = x.to_f64();
+        let high = x - quotient * LN2_HI;
+        let low = quotient * LN2_LO;
+        T::from_f64(scale(exp_reduced(high, low), quotient as i32))
+    } else {
+        let k = T::from_f64(quotient);
+        let reduced = (x - k * T::from_f64(LN2_HI)) - k * T::from_f64(LN2_LO);
+        (T::ONE + exp_m1_0(reduced)).ldexp(quotient as i32)
+    }
+}
+
+/// Computes `2^x`
+pub fn exp2<T: ToyFloat>(x: T) -> T {
+    if x.is_nan() {
+        return x;
+    }
+    let limit = T::from_i32(2 * T::EXPONENT_BIAS + T::MANTISSA_DIGITS as i32);
+    if x > limit {
+        return T::INFINITY;
+    } else if x < -limit {
+        return T::ZERO;
+    }
+    if T::MANTISSA_DIGITS <= 53 {
+        return T::from_f64(exp2_double_double(x.to_f64(), 0.0));
+    }
+    // Reduce to `x = k + f` where `|f| <= 1/2`, which is exact since `k` is the nearest integer
+    let quotient = x.to_f64().round();
+    let fraction = x - T::from_f64(quotient);
+    (T::ONE + exp_m1_0(fraction * T::LN_2)).ldexp(quotient as i32)
+}
+
+/// Computes `2^(high + low)` for a double-double exponent, where `low` is much smaller than `high`.
+///
+/// This lets callers like `pow` compute the exponent to more than double precision,
+/// which matters since any absolute error in it turns into relative error in the result.
+pub fn exp2_double_double(high: f64, low: f64) -> f64 {
+    if high.is_nan() {
+        return high;
+    } else if high > 1100.0 {
+        return ::std::f64::INFINITY;
+    } else if high < -1100.0 {
+        return 0.0;
+    }
+    // Reduce to `k + f` where `|f| <= 1/2`, where subtracting the nearest integer is exact
+    let quotient = high.round();
+    let fraction = (high - quotient) + low;
+    /*
+     * Now 2^f = e^(f * ln(2)), and we need that product to more than double precision
+     * so the rounding error doesn't get magnified by the exponential.
+     */
+    let product = fraction * ::std::f64::consts::LN_2;
+    let error = fraction.mul_add(::std::f64::consts::LN_2, -product) + fraction * LN2_RESIDUAL;
+    scale(exp_reduced(product, -error), quotient as i32)
+}
+
+/// Computes `e^x - 1`, which stays accurate even when `x` is close to zero
+pub fn exp_m1<T: ToyFloat>(x: T) -> T {
+    if x.is_nan() {
+        return x;
+    } else if T::MANTISSA_DIGITS < 53 {
+        // Rounding the double precision result is more accurate than working in the narrower type
+        return T::from_f64(exp_m1(x.to_f64()));
+    }
+    // Once `2^k` is past the precision, subtracting one no longer changes anything
+    let digits = T::MANTISSA_DIGITS as i32 + 2;
+    if x < -T::from_i32(digits) * T::LN_2 {
+        return -T::ONE;
+    } else if x > T::from_i32(digits) * T::LN_2 {
+        return exp(x);
+    }
+    let quotient = (x.to_f64() * LOG2_E).round();
+    let k = T::from_f64(quotient);
+    let reduced = (x - k * T::from_f64(LN2_HI)) - k * T::from_f64(LN2_LO);
+    let small = exp_m1_0(reduced);
+    let quotient = quotient as i32;
+    if quotient == 0 {
+        small
+    } else if quotient <= T::MANTISSA_DIGITS as i32 {
+        /*
+         * e^x - 1 = 2^k * (e^r - 1) + (2^k - 1)
+         * The second term is exact, since 2^k - 1 fits in the mantissa as long as `k` does.
+         */
+        small.ldexp(quotient) + (T::exp2i(quotient) - T::ONE)
+    } else {
+        /*
+         * The last couple of quotients before we return `exp(x)` would round 2^k - 1,
+         * so factor out 2^k instead: e^x - 1 = 2^k * ((e^r - 1 - 2^-k) + 1)
+         * Subtracting 2^-k rounds by at most a quarter ulp of the sum, since `|e^r - 1| < 1/2`,
+         * and adding one rounds only once more.
+         */
+        ((small - T::exp2i(-quotient)) + T::ONE).ldexp(quotient)
+    }
+}
+
+/// Computes `e^x` for single precision floats
+#[inline]
+pub fn expf(x: f32) -> f32 {
+    exp(x)
+}
+
+/// Computes `2^x` for single precision floats
+#[inline]
+pub fn exp2f(x: f32) -> f32 {
+    exp2(x)
+}
+
+/// Computes `e^x - 1` for single precision floats
+#[inline]
+pub fn exp_m1f(x: f32) -> f32 {
+    exp_m1(x)
+}
+
+/// Computes `e^(high - low)`, where `|high - low| <= ln(2)/2` and `low` is much smaller than `high`
+fn exp_reduced(high: f64, low: f64) -> f64 {
+    debug_assert!((high - low).abs() <= 0.35);
+    let reduced = high - low;
+    let square = reduced * reduced;
+    let correction = reduced - square * (P1 + square * (P2 + square * (P3 + square * (P4 + square * P5))));
+    1.0 - ((low - (reduced * correction) / (2.0 - correction)) - high)
+}
+
+/// Multiplies the value by `2^exponent`, adjusting the exponent bits directly
+fn scale(value: f64, exponent: i32) -> f64 {
+    debug_assert!(value.is_normal());
+    let (mantissa, old_exponent, sign) = bits::decode(value);
+    // Encoding handles overflow and rounds subnormal results only once
+    bits::encode(mantissa, old_exponent as i32 + exponent, sign)
+}
+
+fn exp_m1_0<T: ToyFloat>(x: T) -> T {
+    // Taylor expansion at zero is x * (1 + x/2 * (1 + x/3 * (1 + ...))), evaluated from the inside out
+    debug_assert!(x.abs() <= T::LN_2);
+    let mut n = EXP_APPROX_ORDER;
+    let mut result = T::ONE;
+    while n > 1 {
+        result = T::ONE + x / T::from_u64(n as u64) * result;
+        n -= 1;
+    }
+    x * result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{exp, exp2, exp_m1, expf, exp2f, exp_m1f};
+    use rug::Float;
+    use accuracy::ulp_distance;
+    use extended_float::ExtendedFloat;
+    use float::ToyFloat;
+    /// The error allowed against the standard library, which is itself only faithfully rounded
+    const ALLOWED_ULPS: u64 = 2;
+
+    #[test]
+    fn basic() {
+        assert_eq!(exp(0.0), 1.0);
+        assert_within_ulps!(exp(1.0), ::std::f64::consts::E, ALLOWED_ULPS);
+        assert_eq!(exp(::std::f64::INFINITY), ::std::f64::INFINITY);
+        assert_eq!(exp(::std::f64::NEG_INFINITY), 0.0);
+        assert_eq!(exp(1000.0), ::std::f64::INFINITY);
+        assert_eq!(exp(-1000.0), 0.0);
+        assert!(exp(::std::f64::NAN).is_nan());
+        assert_eq!(exp2(10.0), 1024.0);
+        assert_eq!(exp2(-1074.0), 5e-324);
+        assert_eq!(exp2(1024.0), ::std::f64::INFINITY);
+        assert_eq!(exp_m1(0.0), 0.0);
+        assert_eq!(exp_m1(1e-20), 1e-20);
+        assert_eq!(exp_m1(-1000.0), -1.0);
+        assert_eq!(exp_m1(::std::f64::INFINITY), ::std::f64::INFINITY);
+        assert_eq!(expf(0.0), 1.0);
+        assert_eq!(exp2f(3.0), 8.0);
+        assert_eq!(exp_m1f(1e-20), 1e-20);
+    }
+
+    #[test]
+    fn subnormal() {
+        assert_within_ulps!(exp(-740.0), (-740.0f64).exp(), ALLOWED_ULPS);
+        assert_within_ulps!(exp(-708.5), (-708.5f64).exp(), ALLOWED_ULPS);
+        assert_within_ulps!(exp2(-1060.25), (-1060.25f64).exp2(), ALLOWED_ULPS);
+    }
+
+    #[test]
+    fn extended() {
+        for &target in &[-2.5, 0.1, 1.0, 7.25, 1e4] {
+            let expected = Float::with_val(64, target).exp();
+            let actual = exp(ExtendedFloat::from(target)).to_big(64);
+            assert_within_relative_error!(actual, expected, 1e-18, "Failed exp({})", target);
+            let expected = Float::with_val(64, target).exp_m1();
+            let actual = exp_m1(ExtendedFloat::from(target)).to_big(64);
+            assert_within_relative_error!(actual, expected, 1e-18, "Failed exp_m1({})", target);
+        }
+    }
+
+    /// Arguments where `2^k - 1` no longer fits in the mantissa
+    #[test]
+    fn exp_m1_large_quotient() {
+        for step in 0..64 {
+            let target = 36.0 + step as f64 / 16.0;
+            let expected = Float::with_val(53, target).exp_m1().to_f64();
+            assert_within_ulps!(exp_m1(target), expected, ALLOWED_ULPS);
+            let target = 44.0 + step as f64 / 32.0;
+            let expected = Float::with_val(64, target).exp_m1();
+            let actual = exp_m1(ExtendedFloat::from(target)).to_big(64);
+            assert_within_relative_error!(actual, expected, 1e-18, "Failed exp_m1({})", target);
+        }
+    }
+
+    #[quickcheck]
+    fn exp_matches_std(target: f64) -> bool {
+        let big = target * 7.0;
+        ulp_distance(exp(target), target.exp()) <= ALLOWED_ULPS &&
+            ulp_distance(exp(big), big.exp()) <= ALLOWED_ULPS
+    }
+
+    #[quickcheck]
+    fn exp2_matches_std(target: f64) -> bool {
+        let big = target * 10.0;
+        ulp_distance(exp2(target), target.exp2()) <= ALLOWED_ULPS &&
+            ulp_distance(exp2(big), big.exp2()) <= ALLOWED_ULPS
+    }
+
+    #[quickcheck]
+    fn exp_m1_matches_std(target: f64) -> bool {
+        let small = target * 1e-3;
+        ulp_distance(exp_m1(target), target.exp_m1()) <= ALLOWED_ULPS &&
+            ulp_distance(exp_m1(small), small.exp_m1()) <= ALLOWED_ULPS
+    }
+
+    #[quickcheck]
+    fn single_matches_std(target: f32) -> bool {
+        ulp_distance(expf(target), target.exp()) <= 1 &&
+            ulp_distance(exp2f(target), target.exp2()) <= 1 &&
+            ulp_distance(exp_m1f(target), target.exp_m1()) <= 1
+    }
+}
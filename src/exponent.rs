@@ -0,0 +1,200 @@
+//! Functions for taking apart and adjusting the binary exponent of a float.
+//!
+//! Unlike `ToyFloat::ldexp`, these round correctly when the result is subnormal.
+use bits;
+use float::ToyFloat;
+use log::floor_log2;
+
+/// The value `ilogb` returns for zero, matching `FP_ILOGB0` in glibc
+pub const ILOGB_ZERO: i32 = -2147483648;
+/// The value `ilogb` returns for NaN, matching `FP_ILOGBNAN` in glibc
+pub const ILOGB_NAN: i32 = -2147483648;
+/// The value `ilogb` returns for infinity
+pub const ILOGB_INFINITY: i32 = 2147483647;
+
+/// Splits the value into a fraction with magnitude in `[0.5, 1)` and a power of two,
+/// such that `x = fraction * 2^exponent`.
+///
+/// Zero, infinity and NaN are returned unchanged with an exponent of zero.
+pub fn frexp<T: ToyFloat>(x: T) -> (T, i32) {
+    if x == T::ZERO || !x.is_finite() {
+        return (x, 0);
+    }
+    let (mantissa, exponent, sign) = x.decode();
+    // Subnormals don't have the implicit bit, so count the bits that are actually there
+    let length = 64 - mantissa.leading_zeros() as i32;
+    (T::encode(mantissa, -length, sign), exponent as i32 + length)
+}
+
+/// Computes `x * 2^exponent`, rounding only once even if the result is subnormal.
+pub fn ldexp<T: ToyFloat>(x: T, exponent: i32) -> T {
+    if x == T::ZERO || !x.is_finite() || exponent == 0 {
+        return x;
+    }
+    let (fraction, original) = frexp(x);
+    // Now the result is `2 * fraction * 2^(target - 1)`, where `2 * fraction` is in [1, 2)
+    let target = original as i64 + exponent as i64 - 1;
+    let significand = fraction * T::TWO;
+    let min_exponent = 1 - T::EXPONENT_BIAS as i64;
+    if target > T::EXPONENT_BIAS as i64 {
+        significand * T::INFINITY
+    } else if target >= min_exponent {
+        significand * T::exp2i(target as i32)
+    } else if target >= min_exponent - T::MANTISSA_DIGITS as i64 - 1 {
+        /*
+         * The result is subnormal, so scaling it in two steps would round twice.
+         * Scale it down to the smallest normal binade first, which is exact,
+         * so that only the last multiplication rounds.
+         */
+        let smallest_normal = significand * T::exp2i(min_exponent as i32);
+        smallest_normal * T::exp2i((target - min_exponent) as i32)
+    } else {
+        // Less than half the smallest subnormal, so this always rounds to zero
+        significand * T::ZERO
+    }
+}
+
+/// Computes `x * 2^exponent`, which is the same as `ldexp` since our floats are binary.
+#[inline]
+pub fn scalbn<T: ToyFloat>(x: T, exponent: i32) -> T {
+    ldexp(x, exponent)
+}
+
+/// Returns the unbiased exponent of the value as an integer, which is `floor(log2(|x|))`.
+///
+/// Subnormals give their true exponent, not the minimum exponent of the format.
+/// Zero gives `ILOGB_ZERO`, NaN gives `ILOGB_NAN` and infinity gives `ILOGB_INFINITY`.
+pub fn ilogb<T: ToyFloat>(x: T) -> i32 {
+    if x.is_nan() {
+        ILOGB_NAN
+    } else if x.is_infinite() {
+        ILOGB_INFINITY
+    } else if x == T::ZERO {
+        ILOGB_ZERO
+    } else {
+        floor_log2(x)
+    }
+}
+
+/// Returns the unbiased exponent of the value as a float, which is `floor(log2(|x|))`.
+///
+/// Zero gives negative infinity and infinity gives positive infinity, just like `log2`.
+pub fn logb<T: ToyFloat>(x: T) -> T {
+    if x.is_nan() {
+        x
+    } else if x.is_infinite() {
+        T::INFINITY
+    } else if x == T::ZERO {
+        T::NEG_INFINITY
+    } else {
+        T::from_i32(floor_log2(x))
+    }
+}
+
+/// Returns the next representable value after `x` in the direction of `target`.
+///
+/// If the two are equal this returns `target`, so that `nextafter(-0.0, 0.0)` gives positive zero.
+pub fn nextafter<T: ToyFloat>(x: T, target: T) -> T {
+    if x.is_nan() || target.is_nan() {
+        T::NAN
+    } else if x == target {
+        target
+    } else if target > x {
+        bits::next_up(x)
+    } else {
+        bits::next_down(x)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::f64;
+    use extended_float::ExtendedFloat;
+    use utils::Finite;
+
+    #[test]
+    fn basic() {
+        assert_eq!(frexp(8.0), (0.5, 4));
+        assert_eq!(frexp(-0.75), (-0.75, 0));
+        assert_eq!(frexp(5e-324), (0.5, -1073));
+        assert_eq!(frexp(0.0), (0.0, 0));
+        assert_eq!(ldexp(0.5, 4), 8.0);
+        assert_eq!(ldexp(1.0, 1024), f64::INFINITY);
+        assert_eq!(ldexp(-1.0, 1024), f64::NEG_INFINITY);
+        assert_eq!(ldexp(1.0, -1074), 5e-324);
+        assert_eq!(ldexp(1.0, -1076), 0.0);
+        assert_eq!(ldexp(-1.0, -2000).to_bits(), (-0.0f64).to_bits());
+        assert_eq!(ldexp(f64::MAX, -2098), 5e-324);
+        assert_eq!(scalbn(3.0, 2), 12.0);
+        assert_eq!(ilogb(1.0), 0);
+        assert_eq!(ilogb(0.3), -2);
+        assert_eq!(ilogb(5e-324), -1074);
+        assert_eq!(ilogb(0.0), ILOGB_ZERO);
+        assert_eq!(ilogb(f64::NAN), ILOGB_NAN);
+        assert_eq!(ilogb(f64::NEG_INFINITY), ILOGB_INFINITY);
+        assert_eq!(logb(-1024.0), 10.0);
+        assert_eq!(logb(0.0), f64::NEG_INFINITY);
+        assert_eq!(logb(f64::NEG_INFINITY), f64::INFINITY);
+    }
+
+    #[test]
+    fn double_rounding() {
+        /*
+         * Scaling by 2^-1022 first would round this to 2^-1023 (since the extra bit is a tie),
+         * and then the second step would hit another tie and round down to zero.
+         * Rounded once, it's just over half the smallest subnormal.
+         */
+        let target = 0.5 + f64::EPSILON / 2.0;
+        assert_eq!(ldexp(target, -1074), 5e-324);
+        assert_eq!(ldexp(1.5, -1074), 1e-323);
+        assert_eq!(ldexp(1.25, -1074), 5e-324);
+        assert_eq!(ldexp(1.0, -1075), 0.0);
+    }
+
+    #[test]
+    fn next() {
+        assert_eq!(nextafter(1.0, 2.0), 1.0 + f64::EPSILON);
+        assert_eq!(nextafter(1.0, 0.0), 1.0 - f64::EPSILON / 2.0);
+        assert_eq!(nextafter(0.0, 1.0), 5e-324);
+        assert_eq!(nextafter(0.0, -1.0), -5e-324);
+        assert_eq!(nextafter(-5e-324, 1.0).to_bits(), (-0.0f64).to_bits());
+        assert_eq!(nextafter(-0.0, 0.0).to_bits(), 0.0f64.to_bits());
+        assert_eq!(nextafter(f64::MAX, f64::INFINITY), f64::INFINITY);
+        assert_eq!(nextafter(f64::INFINITY, 0.0), f64::MAX);
+        assert_eq!(nextafter(f64::MIN_POSITIVE, 0.0), f64::MIN_POSITIVE - 5e-324);
+        assert!(nextafter(f64::NAN, 0.0).is_nan());
+        let one = ExtendedFloat::from(1.0);
+        assert!(nextafter(one, ExtendedFloat::from(2.0)) > one);
+        assert_eq!(
+            nextafter(nextafter(one, ExtendedFloat::from(2.0)), ExtendedFloat::from(0.0)),
+            one
+        );
+        assert_eq!(nextafter(1.0f32, 2.0), 1.0 + ::std::f32::EPSILON);
+    }
+
+    #[quickcheck]
+    fn frexp_roundtrip(target: Finite) -> bool {
+        let Finite(target) = target;
+        let (fraction, exponent) = frexp(target);
+        (target == 0.0 || (fraction.abs() >= 0.5 && fraction.abs() < 1.0)) &&
+            ldexp(fraction, exponent) == target
+    }
+
+    #[quickcheck]
+    fn ldexp_is_exact(target: Finite, exponent: i16) -> bool {
+        let Finite(target) = target;
+        let exponent = exponent as i32 / 8;
+        let expected = ::rug::Float::with_val(53, target) << exponent;
+        ldexp(target, exponent) == ::rug::Float::with_val(53, expected).to_f64()
+    }
+
+    #[quickcheck]
+    fn next_is_adjacent(target: Finite) -> bool {
+        let Finite(target) = target;
+        let up = nextafter(target, f64::INFINITY);
+        let down = nextafter(target, f64::NEG_INFINITY);
+        up > target && down < target &&
+            nextafter(up, f64::NEG_INFINITY) == target && nextafter(down, f64::INFINITY) == target
+    }
+}
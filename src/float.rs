@@ -0,0 +1,367 @@
+//! Abstraction over the floating point types toymath supports.
+//!
+//! The algorithms in this crate only really care about the basic arithmetic operations
+//! and the IEEE bit layout (for things like the sqrt approximation).
+//! This trait exposes just enough of both that the same code can run on
+//! `f32`, `f64` and the x87 `ExtendedFloat`.
+use std::fmt::{Debug, Display};
+use std::num::FpCategory;
+use std::ops::{Add, Sub, Mul, Div, Rem, Neg, AddAssign, SubAssign, MulAssign, DivAssign};
+
+use rug::Float;
+use rug::float::Special;
+
+use extended_float::{self, ExtendedFloat};
+use extended_float_macros::extended_float;
+
+pub trait ToyFloat: Copy + PartialEq + PartialOrd + Debug + Display
+    + Add<Output=Self> + Sub<Output=Self> + Mul<Output=Self> + Div<Output=Self>
+    + Rem<Output=Self> + Neg<Output=Self>
+    + AddAssign + SubAssign + MulAssign + DivAssign {
+    /// The number of significant binary digits, including the leading integer bit.
+    const MANTISSA_DIGITS: u32;
+    /// The width of the biased exponent field.
+    const EXPONENT_BITS: u32;
+    /// The number of fraction bits in the packed representation,
+    /// which never includes the leading integer bit.
+    const FRACTION_BITS: u32 = Self::MANTISSA_DIGITS - 1;
+    /// The bias that's added to the exponent field.
+    const EXPONENT_BIAS: i32 = (1 << (Self::EXPONENT_BITS - 1)) - 1;
+
+    const ZERO: Self;
+    const ONE: Self;
+    const TWO: Self;
+    const HALF: Self;
+    const PI: Self;
+    const FRAC_PI_2: Self;
+    const LN_2: Self;
+    const NAN: Self;
+    const INFINITY: Self;
+    const NEG_INFINITY: Self;
+
+    /// Packs the value into the standard IEEE layout of `sign | exponent | fraction`,
+    /// where the leading integer bit is always implicit.
+    ///
+    /// This is exactly the native layout of `f32` and `f64`,
+    /// but the x87 format stores the integer bit explicitly so it has to be stripped.
+    fn to_packed_bits(self) -> u128;
+    /// Unpacks a value in the layout produced by `to_packed_bits`
+    fn from_packed_bits(bits: u128) -> Self;
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+    fn from_u64(value: u64) -> Self;
+    fn from_i32(value: i32) -> Self;
+    /// Converts this value into an arbitrary-precision float with the specified precision.
+    fn to_big(self, precision: u32) -> Float;
+    /// Rounds an arbitrary-precision float to the nearest value of this type.
+    fn from_big(value: &Float) -> Self;
+
+    #[inline]
+    fn exponent_field(self) -> u32 {
+        ((self.to_packed_bits() >> Self::FRACTION_BITS) & ((1 << Self::EXPONENT_BITS) - 1)) as u32
+    }
+    #[inline]
+    fn fraction_field(self) -> u128 {
+        self.to_packed_bits() & ((1 << Self::FRACTION_BITS) - 1)
+    }
+    #[inline]
+    fn classify(self) -> FpCategory {
+        ::bits::classify(self)
+    }
+    #[inline]
+    fn is_nan(self) -> bool {
+        self.classify() == FpCategory::Nan
+    }
+    #[inline]
+    fn is_infinite(self) -> bool {
+        self.classify() == FpCategory::Infinite
+    }
+    #[inline]
+    fn is_finite(self) -> bool {
+        match self.classify() {
+            FpCategory::Nan | FpCategory::Infinite => false,
+            _ => true
+        }
+    }
+    #[inline]
+    fn is_normal(self) -> bool {
+        self.classify() == FpCategory::Normal
+    }
+    #[inline]
+    fn is_sign_negative(self) -> bool {
+        (self.to_packed_bits() >> (Self::FRACTION_BITS + Self::EXPONENT_BITS)) & 1 != 0
+    }
+    #[inline]
+    fn is_sign_positive(self) -> bool {
+        !self.is_sign_negative()
+    }
+    #[inline]
+    fn abs(self) -> Self {
+        let sign_mask = 1u128 << (Self::FRACTION_BITS + Self::EXPONENT_BITS);
+        Self::from_packed_bits(self.to_packed_bits() & !sign_mask)
+    }
+    /// Returns the mantissa, exponent and sign as integers,
+    /// in the same format as `num_traits::Float::integer_decode`.
+    #[inline]
+    fn decode(self) -> (u64, i16, i8) {
+        ::bits::decode(self)
+    }
+    /// Computes `mantissa * 2^exponent` with the given sign,
+    /// the inverse of `decode`.
+    #[inline]
+    fn encode(mantissa: u64, exponent: i32, sign: i8) -> Self {
+        ::bits::encode(mantissa, exponent, sign)
+    }
+    /// Returns `2^exponent` as long as it's a normal number.
+    #[inline]
+    fn exp2i(exponent: i32) -> Self {
+        let biased = exponent + Self::EXPONENT_BIAS;
+        debug_assert!(biased > 0 && biased < (1 << Self::EXPONENT_BITS) - 1, "Invalid exponent: {}", exponent);
+        Self::from_packed_bits((biased as u128) << Self::FRACTION_BITS)
+    }
+    /// Multiplies the value by `2^exponent`.
+    ///
+    /// We do this as a series of multiplications by normal powers of two,
+    /// so it's exact unless the result overflows or becomes subnormal.
+    fn ldexp(self, mut exponent: i32) -> Self {
+        let max_step = Self::EXPONENT_BIAS;
+        let min_step = 1 - Self::EXPONENT_BIAS;
+        let mut result = self;
+        while exponent > max_step {
+            result *= Self::exp2i(max_step);
+            exponent -= max_step;
+        }
+        while exponent < min_step {
+            result *= Self::exp2i(min_step);
+            exponent -= min_step;
+        }
+        result * Self::exp2i(exponent)
+    }
+    /// Raises the value to an integer power by repeated squaring.
+    fn powi(self, power: i32) -> Self {
+        let mut remaining = (power as i64).abs() as u64;
+        let mut base = self;
+        let mut result = Self::ONE;
+        while remaining != 0 {
+            if remaining & 1 != 0 {
+                result *= base;
+            }
+            remaining >>= 1;
+            base *= base;
+        }
+        if power < 0 { Self::ONE / result } else { result }
+    }
+}
+
+impl ToyFloat for f64 {
+    const MANTISSA_DIGITS: u32 = 53;
+    const EXPONENT_BITS: u32 = 11;
+    const ZERO: f64 = 0.0;
+    const ONE: f64 = 1.0;
+    const TWO: f64 = 2.0;
+    const HALF: f64 = 0.5;
+    const PI: f64 = ::std::f64::consts::PI;
+    const FRAC_PI_2: f64 = ::std::f64::consts::FRAC_PI_2;
+    const LN_2: f64 = ::std::f64::consts::LN_2;
+    const NAN: f64 = ::std::f64::NAN;
+    const INFINITY: f64 = ::std::f64::INFINITY;
+    const NEG_INFINITY: f64 = ::std::f64::NEG_INFINITY;
+
+    #[inline]
+    fn to_packed_bits(self) -> u128 {
+        self.to_bits() as u128
+    }
+    #[inline]
+    fn from_packed_bits(bits: u128) -> f64 {
+        f64::from_bits(bits as u64)
+    }
+    #[inline]
+    fn from_f64(value: f64) -> f64 {
+        value
+    }
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self
+    }
+    #[inline]
+    fn from_u64(value: u64) -> f64 {
+        value as f64
+    }
+    #[inline]
+    fn from_i32(value: i32) -> f64 {
+        value as f64
+    }
+    #[inline]
+    fn to_big(self, precision: u32) -> Float {
+        Float::with_val(precision, self)
+    }
+    #[inline]
+    fn from_big(value: &Float) -> f64 {
+        value.to_f64()
+    }
+}
+
+impl ToyFloat for f32 {
+    const MANTISSA_DIGITS: u32 = 24;
+    const EXPONENT_BITS: u32 = 8;
+    const ZERO: f32 = 0.0;
+    const ONE: f32 = 1.0;
+    const TWO: f32 = 2.0;
+    const HALF: f32 = 0.5;
+    const PI: f32 = ::std::f32::consts::PI;
+    const FRAC_PI_2: f32 = ::std::f32::consts::FRAC_PI_2;
+    const LN_2: f32 = ::std::f32::consts::LN_2;
+    const NAN: f32 = ::std::f32::NAN;
+    const INFINITY: f32 = ::std::f32::INFINITY;
+    const NEG_INFINITY: f32 = ::std::f32::NEG_INFINITY;
+
+    #[inline]
+    fn to_packed_bits(self) -> u128 {
+        self.to_bits() as u128
+    }
+    #[inline]
+    fn from_packed_bits(bits: u128) -> f32 {
+        f32::from_bits(bits as u32)
+    }
+    #[inline]
+    fn from_f64(value: f64) -> f32 {
+        value as f32
+    }
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+    #[inline]
+    fn from_u64(value: u64) -> f32 {
+        value as f32
+    }
+    #[inline]
+    fn from_i32(value: i32) -> f32 {
+        value as f32
+    }
+    #[inline]
+    fn to_big(self, precision: u32) -> Float {
+        Float::with_val(precision, self)
+    }
+    #[inline]
+    fn from_big(value: &Float) -> f32 {
+        value.to_f32()
+    }
+}
+
+impl ToyFloat for ExtendedFloat {
+    const MANTISSA_DIGITS: u32 = 64;
+    const EXPONENT_BITS: u32 = 15;
+    const ZERO: ExtendedFloat = extended_float!(0);
+    const ONE: ExtendedFloat = extended_float!(1);
+    const TWO: ExtendedFloat = extended_float!(2);
+    const HALF: ExtendedFloat = extended_float!(0.5);
+    const PI: ExtendedFloat = extended_float::consts::PI;
+    const FRAC_PI_2: ExtendedFloat = extended_float::consts::FRAC_PI_2;
+    const LN_2: ExtendedFloat = extended_float::consts::LN_2;
+    const NAN: ExtendedFloat = extended_float::consts::NAN;
+    const INFINITY: ExtendedFloat = extended_float::consts::INFINITY;
+    const NEG_INFINITY: ExtendedFloat = extended_float::consts::NEG_INFINITY;
+
+    fn to_packed_bits(self) -> u128 {
+        /*
+         * The x87 format is a little-endian 64-bit mantissa followed by the sign and exponent.
+         * Unlike IEEE doubles, the integer bit is stored explicitly in the top of the mantissa,
+         * so we have to drop it to get the standard layout.
+         */
+        let raw = self.to_bits().iter().rev()
+            .fold(0u128, |acc, &byte| (acc << 8) | byte as u128);
+        let fraction = raw & ((1 << 63) - 1);
+        let sign_exponent = raw >> 64;
+        (sign_exponent << 63) | fraction
+    }
+    fn from_packed_bits(bits: u128) -> ExtendedFloat {
+        let fraction = bits & ((1 << 63) - 1);
+        let sign_exponent = (bits >> 63) & 0xFFFF;
+        // The integer bit is set for everything except zeros and subnormals
+        let integer_bit = if sign_exponent & 0x7FFF != 0 { 1 << 63 } else { 0 };
+        let raw = (sign_exponent << 64) | integer_bit | fraction;
+        let mut bytes = [0u8; 10];
+        for (index, byte) in bytes.iter_mut().enumerate() {
+            *byte = (raw >> (index * 8)) as u8;
+        }
+        ExtendedFloat::from_bits(bytes)
+    }
+    #[inline]
+    fn from_f64(value: f64) -> ExtendedFloat {
+        ExtendedFloat::from(value)
+    }
+    #[inline]
+    fn to_f64(self) -> f64 {
+        f64::from(self)
+    }
+    #[inline]
+    fn from_u64(value: u64) -> ExtendedFloat {
+        ExtendedFloat::from(value)
+    }
+    #[inline]
+    fn from_i32(value: i32) -> ExtendedFloat {
+        ExtendedFloat::from(value)
+    }
+    fn to_big(self, precision: u32) -> Float {
+        match self.classify() {
+            FpCategory::Nan => Float::with_val(precision, Special::Nan),
+            FpCategory::Infinite if self.is_sign_negative() => Float::with_val(precision, Special::NegInfinity),
+            FpCategory::Infinite => Float::with_val(precision, Special::Infinity),
+            FpCategory::Zero if self.is_sign_negative() => Float::with_val(precision, Special::NegZero),
+            FpCategory::Zero => Float::with_val(precision, Special::Zero),
+            FpCategory::Normal | FpCategory::Subnormal => {
+                let (mantissa, exponent, sign) = self.decode();
+                let result = Float::with_val(precision, mantissa) << exponent as i32;
+                if sign < 0 { -result } else { result }
+            }
+        }
+    }
+    fn from_big(value: &Float) -> ExtendedFloat {
+        if value.is_nan() {
+            return Self::NAN
+        } else if value.is_infinite() {
+            return if value.is_sign_negative() { Self::NEG_INFINITY } else { Self::INFINITY }
+        } else if value.is_zero() {
+            return if value.is_sign_negative() { -Self::ZERO } else { Self::ZERO }
+        }
+        /*
+         * Round to the 64 bits we can represent,
+         * then the mantissa always fits exactly in a u64.
+         * NOTE: This double rounds if the result turns out to be subnormal.
+         */
+        let rounded = Float::with_val(Self::MANTISSA_DIGITS, value);
+        let (mantissa, exponent) = rounded.to_integer_exp().unwrap();
+        let sign = if mantissa < 0 { -1 } else { 1 };
+        let mantissa = mantissa.abs().to_u64().unwrap();
+        Self::encode(mantissa, exponent, sign)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ToyFloat;
+    use extended_float::ExtendedFloat;
+    use std::f64::consts::PI;
+    #[test]
+    fn packed_roundtrip() {
+        for &value in &[0.0, -0.0, 1.0, -2.5, PI, 1e-310, ::std::f64::INFINITY] {
+            let extended = ExtendedFloat::from(value);
+            assert_eq!(ExtendedFloat::from_packed_bits(extended.to_packed_bits()).to_bits(), extended.to_bits());
+            assert_eq!(<f64 as ToyFloat>::from_packed_bits(value.to_packed_bits()).to_bits(), value.to_bits());
+        }
+    }
+    #[test]
+    fn decode() {
+        let (mantissa, exponent, sign) = ToyFloat::decode(5.0f64);
+        assert_eq!(f64::encode(mantissa, exponent as i32, sign), 5.0);
+        assert_eq!(ExtendedFloat::ONE.decode(), (1 << 63, -63, 1));
+        assert_eq!(ToyFloat::decode(-1.0f32), (1 << 23, -23, -1));
+    }
+    #[test]
+    fn big_roundtrip() {
+        let value = ExtendedFloat::from(PI);
+        assert_eq!(ExtendedFloat::from_big(&value.to_big(128)), value);
+        assert_eq!(f32::from_big(&ToyFloat::to_big(1.5f32, 128)), 1.5);
+    }
+}
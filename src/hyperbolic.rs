@@ -0,0 +1,275 @@
+//! Hyperbolic functions and their inverses
+use float::ToyFloat;
+
+use exp::exp;
+use log::{ln, log1p};
+use sqrt::sqrt;
+
+// Enough for the Taylor series to converge on [0, 1]
+const SINH_APPROX_ORDER: usize = 21;
+// Enough for the Taylor series to converge on [0, 1/2]
+const ASINH_APPROX_ORDER: usize = 51;
+// Enough for the Taylor series to converge on [0, 1/4]
+const ATANH_APPROX_ORDER: usize = 29;
+
+pub fn sinh<T: ToyFloat>(x: T) -> T {
+    if !x.is_finite() {
+        return x;
+    }
+    // Hyperbolic sine is an odd function
+    if x.is_sign_negative() {
+        return -sinh(-x);
+    }
+    if x <= T::ONE {
+        /*
+         * Computing `(e^x - e^-x) / 2` directly cancels catastrophically near zero
+         * (to the point sinh(1e-20) would be zero), but the Taylor series doesn't have that problem.
+         */
+        return sinh0(x);
+    }
+    let exponential = exp(x);
+    if exponential.is_infinite() {
+        // e^x overflows slightly before sinh(x) does
+        let half = exp(x * T::HALF);
+        half * (half * T::HALF)
+    } else {
+        (exponential - T::ONE / exponential) * T::HALF
+    }
+}
+
+pub fn cosh<T: ToyFloat>(x: T) -> T {
+    if x.is_nan() {
+        return x;
+    }
+    // Hyperbolic cosine is an even function
+    let x = x.abs();
+    let exponential = exp(x);
+    if exponential.is_infinite() {
+        // e^x overflows slightly before cosh(x) does
+        let half = exp(x * T::HALF);
+        half * (half * T::HALF)
+    } else {
+        (exponential + T::ONE / exponential) * T::HALF
+    }
+}
+
+pub fn tanh<T: ToyFloat>(x: T) -> T {
+    if x.is_nan() {
+        return x;
+    }
+    // Hyperbolic tangent is an odd function
+    if x.is_sign_negative() {
+        return -tanh(-x);
+    }
+    if x <= T::ONE {
+        // The series for sinh avoids cancellation near zero
+        sinh0(x) / cosh(x)
+    } else {
+        // tanh(x) = 1 - 2/(e^2x + 1), which goes to one as e^2x overflows
+        T::ONE - T::TWO / (exp(T::TWO * x) + T::ONE)
+    }
+}
+
+pub fn asinh<T: ToyFloat>(x: T) -> T {
+    if !x.is_finite() {
+        return x;
+    }
+    // Inverse hyperbolic sine is an odd function
+    if x.is_sign_negative() {
+        return -asinh(-x);
+    }
+    if x <= T::HALF {
+        asinh0(x)
+    } else if x > T::exp2i(T::MANTISSA_DIGITS as i32) {
+        // The square would overflow, but at this point sqrt(x^2 + 1) == x
+        ln(x) + T::LN_2
+    } else {
+        // asinh(x) = ln(x + sqrt(x^2 + 1))
+        ln(x + sqrt_precise(x * x + T::ONE))
+    }
+}
+
+pub fn acosh<T: ToyFloat>(x: T) -> T {
+    if x.is_nan() || x < T::ONE {
+        return T::NAN;
+    }
+    if x.is_infinite() {
+        x
+    } else if x > T::exp2i(T::MANTISSA_DIGITS as i32) {
+        // The square would overflow, but at this point sqrt(x^2 - 1) == x
+        ln(x) + T::LN_2
+    } else {
+        /*
+         * acosh(x) = ln(x + sqrt(x^2 - 1)), but the logarithm loses everything near one to cancellation.
+         * Writing it in terms of the exact `t = x - 1` gives ln(1 + t + sqrt(2t + t^2)) instead.
+         */
+        let t = x - T::ONE;
+        log1p(t + sqrt_precise(t * (t + T::TWO)))
+    }
+}
+
+pub fn atanh<T: ToyFloat>(x: T) -> T {
+    if x.is_nan() || x.abs() > T::ONE {
+        return T::NAN;
+    }
+    // Inverse hyperbolic tangent is an odd function
+    if x.is_sign_negative() {
+        return -atanh(-x);
+    }
+    if x == T::ONE {
+        T::INFINITY
+    } else if x <= T::HALF * T::HALF {
+        atanh0(x)
+    } else {
+        // atanh(x) = ln((1 + x) / (1 - x)) / 2
+        ln((T::ONE + x) / (T::ONE - x)) * T::HALF
+    }
+}
+
+/// Computes the square root, with one more Newton iteration than `sqrt` to get full precision
+fn sqrt_precise<T: ToyFloat>(target: T) -> T {
+    if target == T::ZERO {
+        return target;
+    }
+    let root = sqrt(target);
+    T::HALF * (root + target / root)
+}
+
+fn sinh0<T: ToyFloat>(x: T) -> T {
+    // Taylor expansion at zero is sum of x^(2n+1)/(2n+1)!
+    debug_assert!(x.is_sign_positive() && x <= T::ONE);
+    let mut n = 0usize;
+    let mut result = T::ZERO;
+    let mut term = x;
+    while 2 * n + 1 < SINH_APPROX_ORDER {
+        result += term;
+        term *= x * x / T::from_u64(((2 * n + 2) * (2 * n + 3)) as u64);
+        n += 1;
+    }
+    result
+}
+
+fn asinh0<T: ToyFloat>(x: T) -> T {
+    // Taylor expansion at zero is sum of ((-1)^n (2n)! x^(2n+1))/(4^n (n!)^2 (2n+1))
+    debug_assert!(x.is_sign_positive() && x <= T::HALF);
+    let mut n = 0usize;
+    let mut result = T::ZERO;
+    // The central binomial coefficient `(2n)!/(4^n (n!)^2)`, updated incrementally
+    let mut coefficient = T::ONE;
+    while 2 * n + 1 < ASINH_APPROX_ORDER {
+        let mut term = x.powi((2 * n + 1) as i32) * coefficient / T::from_u64((2 * n + 1) as u64);
+        if n & 1 == 1 {
+            term = -term;
+        }
+        result += term;
+        coefficient *= T::from_u64((2 * n + 1) as u64) / T::from_u64((2 * n + 2) as u64);
+        n += 1;
+    }
+    result
+}
+
+fn atanh0<T: ToyFloat>(x: T) -> T {
+    // Taylor expansion at zero is sum of x^(2n+1)/(2n+1)
+    debug_assert!(x.is_sign_positive() && x <= T::HALF * T::HALF);
+    let mut n = 0usize;
+    let mut result = T::ZERO;
+    while 2 * n + 1 < ATANH_APPROX_ORDER {
+        result += x.powi((2 * n + 1) as i32) / T::from_u64((2 * n + 1) as u64);
+        n += 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{sinh, cosh, tanh, asinh, acosh, atanh};
+    use rug::Float;
+    use accuracy::relative_error;
+    use utils::Finite;
+    use extended_float::ExtendedFloat;
+    const RELATIVE_ERROR: f64 = 1e-13;
+
+    fn relatively_equal(actual: f64, expected: f64) -> bool {
+        relative_error(actual, expected) <= RELATIVE_ERROR
+    }
+
+    #[test]
+    fn basic() {
+        assert_eq!(sinh(0.0), 0.0);
+        assert_eq!(cosh(0.0), 1.0);
+        assert_eq!(tanh(0.0), 0.0);
+        assert_within_relative_error!(sinh(1.0), 1.0f64.sinh(), RELATIVE_ERROR);
+        assert_within_relative_error!(cosh(-2.0), 2.0f64.cosh(), RELATIVE_ERROR);
+        assert_eq!(tanh(::std::f64::INFINITY), 1.0);
+        assert_eq!(tanh(-1000.0), -1.0);
+        assert_eq!(acosh(1.0), 0.0);
+        assert!(acosh(0.5).is_nan());
+        assert_eq!(atanh(1.0), ::std::f64::INFINITY);
+        assert_eq!(atanh(-1.0), ::std::f64::NEG_INFINITY);
+        assert!(atanh(1.5).is_nan());
+        assert_within_relative_error!(asinh(1e300), 1e300f64.asinh(), RELATIVE_ERROR);
+        assert_within_relative_error!(sinh(710.0), 710.0f64.sinh(), RELATIVE_ERROR);
+    }
+
+    #[test]
+    fn cancellation() {
+        assert_eq!(sinh(1e-20), 1e-20);
+        assert_eq!(tanh(1e-20), 1e-20);
+        assert_eq!(asinh(1e-20), 1e-20);
+        assert_eq!(atanh(-1e-20), -1e-20);
+        assert_within_relative_error!(sinh(1e-5), 1e-5f64.sinh(), RELATIVE_ERROR);
+        assert_within_relative_error!(tanh(1e-5), 1e-5f64.tanh(), RELATIVE_ERROR);
+        // The standard library loses digits just above one, so compare against the exact result instead
+        for &power in &[-10, -20, -30, -40, -52] {
+            let target = 1.0 + 2.0f64.powi(power);
+            let expected = Float::with_val(128, target).acosh().to_f64();
+            assert_within_relative_error!(acosh(target), expected, RELATIVE_ERROR, "Failed acosh(1 + 2^{})", power);
+        }
+    }
+
+    #[test]
+    fn generic() {
+        assert_eq!(sinh(1e-20f32), 1e-20);
+        assert_within_relative_error!(
+            f64::from(cosh(ExtendedFloat::from(3.0))),
+            3.0f64.cosh(),
+            RELATIVE_ERROR
+        );
+    }
+
+    #[quickcheck]
+    fn sinh_cosh_tanh_match_std(target: f64) -> bool {
+        relatively_equal(sinh(target), target.sinh()) &&
+            relatively_equal(cosh(target), target.cosh()) &&
+            relatively_equal(tanh(target), target.tanh())
+    }
+
+    #[quickcheck]
+    fn asinh_matches_std(target: Finite) -> bool {
+        let Finite(target) = target;
+        relatively_equal(asinh(target), target.asinh())
+    }
+
+    #[quickcheck]
+    fn acosh_matches_std(target: f64) -> bool {
+        relatively_equal(acosh(target), target.acosh())
+    }
+
+    #[quickcheck]
+    fn acosh_matches_exact_near_one(target: f64) -> bool {
+        let target = 1.0 + target.fract().abs() * 1e-6;
+        let expected = Float::with_val(128, target).acosh().to_f64();
+        relatively_equal(acosh(target), expected)
+    }
+
+    #[quickcheck]
+    fn atanh_matches_exact(target: f64) -> bool {
+        /*
+         * The standard library loses a few digits close to one,
+         * so compare the fractional part against the exact result instead.
+         */
+        let fraction = target.fract();
+        let expected = Float::with_val(128, fraction).atanh().to_f64();
+        relatively_equal(atanh(fraction), expected)
+    }
+}
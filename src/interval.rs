@@ -0,0 +1,564 @@
+//! Interval arithmetic, where every operation returns bounds guaranteed to contain the exact result.
+//!
+//! The basic operations use error-free transformations to find which way the nearest result was rounded,
+//! so they only widen by an ulp when the result is actually inexact.
+//! The square root uses an exact residual the same way,
+//! while the logarithms, `pow` and the trigonometric functions widen toymath's results by their error bounds.
+//!
+//! An empty interval (like the square root of negative numbers) is represented with NaN bounds,
+//! and propagates through every operation.
+use std::fmt::{self, Display};
+use std::ops::{Add, Sub, Mul, Div, Neg};
+
+use bits::{self, next_up, next_down};
+use log::{self, log2, ln};
+use pow::{self, pow};
+use reduce::rem_pio2;
+use sqrt::sqrt_correctly_rounded;
+use trig::{self, sin, cos};
+use utils::two_sum;
+
+/// Results smaller than this (`2^-969`) might have lost bits of their rounding error to underflow,
+/// so we can't rely on error-free transformations to tell which way they rounded.
+const EXACT_ERROR_LIMIT: f64 = ::std::f64::MIN_POSITIVE * 9007199254740992.0;
+
+/// A closed interval `[lower, upper]` of real numbers.
+///
+/// The bounds can be infinite to represent unbounded intervals,
+/// but the interval can't consist only of an infinity.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Interval<T> {
+    lower: T,
+    upper: T,
+}
+impl Interval<f64> {
+    /// The interval containing no numbers at all
+    pub const EMPTY: Interval<f64> = Interval { lower: ::std::f64::NAN, upper: ::std::f64::NAN };
+    /// The interval containing every real number
+    pub const ENTIRE: Interval<f64> = Interval {
+        lower: ::std::f64::NEG_INFINITY,
+        upper: ::std::f64::INFINITY
+    };
+
+    /// Creates the interval `[lower, upper]`.
+    ///
+    /// ## Panics
+    /// If either bound is NaN, the bounds are out of order,
+    /// or the interval would only contain an infinity.
+    #[inline]
+    pub fn new(lower: f64, upper: f64) -> Interval<f64> {
+        assert!(lower <= upper, "Invalid interval [{}, {}]", lower, upper);
+        assert!(
+            lower != ::std::f64::INFINITY && upper != ::std::f64::NEG_INFINITY,
+            "Interval only contains infinity: [{}, {}]", lower, upper
+        );
+        Interval { lower, upper }
+    }
+    /// Creates the interval containing just the specified value
+    #[inline]
+    pub fn point(value: f64) -> Interval<f64> {
+        Interval::new(value, value)
+    }
+    #[inline]
+    pub fn lower(&self) -> f64 {
+        self.lower
+    }
+    #[inline]
+    pub fn upper(&self) -> f64 {
+        self.upper
+    }
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.lower.is_nan()
+    }
+    #[inline]
+    pub fn contains(&self, value: f64) -> bool {
+        self.lower <= value && value <= self.upper
+    }
+    /// Returns the difference between the bounds, rounded up so it never underestimates
+    #[inline]
+    pub fn width(&self) -> f64 {
+        add_bounds(self.upper, -self.lower).1
+    }
+
+    pub fn sqrt(self) -> Interval<f64> {
+        if self.is_empty() || self.upper < 0.0 {
+            return Interval::EMPTY
+        }
+        // Negative numbers are outside the domain, so just ignore them
+        let lower = self.lower.max(0.0);
+        Interval::new(sqrt_bounds(lower).0, sqrt_bounds(self.upper).1)
+    }
+    pub fn log2(self) -> Interval<f64> {
+        if self.is_empty() || self.upper <= 0.0 {
+            return Interval::EMPTY
+        }
+        let lower = self.lower.max(0.0);
+        Interval::new(log2_bounds(lower).0, log2_bounds(self.upper).1)
+    }
+    pub fn ln(self) -> Interval<f64> {
+        if self.is_empty() || self.upper <= 0.0 {
+            return Interval::EMPTY
+        }
+        let lower = self.lower.max(0.0);
+        Interval::new(ln_bounds(lower).0, ln_bounds(self.upper).1)
+    }
+    /// Raises the values in this interval to the powers in the other interval.
+    ///
+    /// Negative bases are outside the domain, so they're ignored just like in `sqrt`.
+    /// The zero base follows `pow`, so powers that aren't positive can give infinity.
+    pub fn pow(self, power: Interval<f64>) -> Interval<f64> {
+        if self.is_empty() || power.is_empty() || self.upper < 0.0 {
+            return Interval::EMPTY
+        } else if self.upper == 0.0 && power.upper < 0.0 {
+            // Every result would be infinite
+            return Interval::EMPTY
+        }
+        let base = Interval::new(self.lower.max(0.0), self.upper);
+        /*
+         * With a non-negative base, x^y is monotonic in each argument,
+         * so the extremes are always at the corners.
+         */
+        corners(base, power, pow_bounds)
+    }
+    pub fn sin(self) -> Interval<f64> {
+        self.trig(sin, 1)
+    }
+    pub fn cos(self) -> Interval<f64> {
+        self.trig(cos, 0)
+    }
+    pub fn sin_cos(self) -> (Interval<f64>, Interval<f64>) {
+        (self.sin(), self.cos())
+    }
+    /// Bounds a trigonometric function whose maximum is at the multiples of `pi/2` in the specified quadrant.
+    ///
+    /// Between those extrema the function is monotonic, so the bounds come from the endpoints,
+    /// along with any extrema the interval contains.
+    fn trig(self, function: fn(f64) -> f64, maximum_quadrant: i32) -> Interval<f64> {
+        if self.is_empty() {
+            return Interval::EMPTY
+        } else if !self.lower.is_finite() || !self.upper.is_finite() || self.width() >= 2.0 * ::std::f64::consts::PI {
+            return Interval::new(-1.0, 1.0)
+        }
+        let (start_lower, start_upper) = widen(function(self.lower), trig::ERROR_ULPS);
+        let (end_lower, end_upper) = widen(function(self.upper), trig::ERROR_ULPS);
+        // Widening can't take the bounds past the range of the functions
+        let mut lower = start_lower.min(end_lower).max(-1.0);
+        let mut upper = start_upper.max(end_upper).min(1.0);
+        /*
+         * The extrema are at multiples of pi/2, so we check each multiple between the quadrants of the bounds.
+         * We only know the lowest two bits of each quadrant, but that's enough since the interval is less than a period.
+         * Each quadrant is the nearest multiple to the bound, so the quadrants can only be four apart
+         * if the interval is wider than 3pi/2, and can only be the same if it's narrower than pi/2.
+         */
+        let (start, start_remainder, _) = rem_pio2(self.lower);
+        let (end, end_remainder, _) = rem_pio2(self.upper);
+        let mut quadrants = end.wrapping_sub(start) & 3;
+        if quadrants == 0 && self.width() > 3.0 {
+            quadrants = 4;
+        }
+        /*
+         * Reducing a bound that's very close to an extremum might give the remainder the wrong sign,
+         * but then the function is so flat there that the bound rounds to the extremum anyway.
+         */
+        for offset in 0..(quadrants + 1) {
+            let included = (offset > 0 || start_remainder <= 0.0) && (offset < quadrants || end_remainder >= 0.0);
+            if !included {
+                continue
+            }
+            let quadrant = start.wrapping_add(offset).wrapping_sub(maximum_quadrant) & 3;
+            if quadrant == 0 {
+                upper = 1.0;
+            } else if quadrant == 2 {
+                lower = -1.0;
+            }
+        }
+        Interval::new(lower, upper)
+    }
+}
+impl From<f64> for Interval<f64> {
+    #[inline]
+    fn from(value: f64) -> Interval<f64> {
+        Interval::point(value)
+    }
+}
+impl Display for Interval<f64> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            write!(f, "[empty]")
+        } else {
+            write!(f, "[{}, {}]", self.lower, self.upper)
+        }
+    }
+}
+
+impl Neg for Interval<f64> {
+    type Output = Interval<f64>;
+    #[inline]
+    fn neg(self) -> Interval<f64> {
+        // Negation is exact, and the NaNs of an empty interval stay NaN
+        Interval { lower: -self.upper, upper: -self.lower }
+    }
+}
+impl Add for Interval<f64> {
+    type Output = Interval<f64>;
+    fn add(self, other: Interval<f64>) -> Interval<f64> {
+        if self.is_empty() || other.is_empty() {
+            return Interval::EMPTY
+        }
+        Interval::new(
+            add_bounds(self.lower, other.lower).0,
+            add_bounds(self.upper, other.upper).1
+        )
+    }
+}
+impl Sub for Interval<f64> {
+    type Output = Interval<f64>;
+    #[inline]
+    fn sub(self, other: Interval<f64>) -> Interval<f64> {
+        self + (-other)
+    }
+}
+impl Mul for Interval<f64> {
+    type Output = Interval<f64>;
+    fn mul(self, other: Interval<f64>) -> Interval<f64> {
+        if self.is_empty() || other.is_empty() {
+            return Interval::EMPTY
+        }
+        corners(self, other, mul_bounds)
+    }
+}
+impl Div for Interval<f64> {
+    type Output = Interval<f64>;
+    fn div(self, other: Interval<f64>) -> Interval<f64> {
+        if self.is_empty() || other.is_empty() || other.lower == 0.0 && other.upper == 0.0 {
+            return Interval::EMPTY
+        } else if other.contains(0.0) {
+            // The quotient gets arbitrarily large near zero, possibly with either sign
+            return Interval::ENTIRE
+        }
+        corners(self, other, div_bounds)
+    }
+}
+
+/// Combines the bounds of the operation on each pair of endpoints,
+/// which works for any operation that's monotonic in each argument.
+///
+/// Indeterminate forms (like `inf/inf`) give NaN, which gets ignored in favor of the other corners.
+fn corners<F>(first: Interval<f64>, second: Interval<f64>, operation: F) -> Interval<f64>
+    where F: Fn(f64, f64) -> (f64, f64) {
+    let results = [
+        operation(first.lower, second.lower),
+        operation(first.lower, second.upper),
+        operation(first.upper, second.lower),
+        operation(first.upper, second.upper),
+    ];
+    let lower = results.iter().fold(::std::f64::NAN, |lower, result| lower.min(result.0));
+    let upper = results.iter().fold(::std::f64::NAN, |upper, result| upper.max(result.1));
+    Interval::new(lower, upper)
+}
+
+/// Bounds an exact value `result + error`, where `result` is the nearest double.
+///
+/// A NaN error means we don't know which way it was rounded, so we have to widen in both directions.
+#[inline]
+fn enclose(result: f64, error: f64) -> (f64, f64) {
+    if error == 0.0 {
+        (result, result)
+    } else if error < 0.0 {
+        (next_down(result), result)
+    } else if error > 0.0 {
+        (result, next_up(result))
+    } else {
+        (next_down(result), next_up(result))
+    }
+}
+
+/// Bounds the result of widening `value` by the specified number of ulps in each direction
+fn widen(value: f64, ulps: u32) -> (f64, f64) {
+    let (mut lower, mut upper) = (value, value);
+    for _ in 0..ulps {
+        lower = next_down(lower);
+        upper = next_up(upper);
+    }
+    (lower, upper)
+}
+
+fn add_bounds(first: f64, second: f64) -> (f64, f64) {
+    let (sum, error) = two_sum(first, second);
+    if sum.is_infinite() {
+        // Either this overflowed, or one of the arguments was infinite to begin with
+        return if first.is_finite() && second.is_finite() { enclose(sum, ::std::f64::NAN) } else { (sum, sum) }
+    }
+    enclose(sum, error)
+}
+
+fn mul_bounds(first: f64, second: f64) -> (f64, f64) {
+    if first == 0.0 || second == 0.0 {
+        // This is still zero if the other one is infinite, since that's the limit of the bound
+        return (0.0, 0.0)
+    }
+    let product = first * second;
+    if product.is_infinite() {
+        return if first.is_finite() && second.is_finite() { enclose(product, ::std::f64::NAN) } else { (product, product) }
+    } else if product.abs() < EXACT_ERROR_LIMIT {
+        return enclose(product, ::std::f64::NAN)
+    }
+    enclose(product, first.mul_add(second, -product))
+}
+
+fn div_bounds(dividend: f64, divisor: f64) -> (f64, f64) {
+    debug_assert!(divisor != 0.0);
+    if dividend == 0.0 || divisor.is_infinite() {
+        // Dividing infinities gives NaN, which gets ignored by `corners`
+        let quotient = dividend / divisor;
+        return (quotient, quotient)
+    }
+    let quotient = dividend / divisor;
+    if quotient.is_infinite() {
+        return if dividend.is_finite() { enclose(quotient, ::std::f64::NAN) } else { (quotient, quotient) }
+    } else if quotient.abs() < EXACT_ERROR_LIMIT || dividend.abs() < EXACT_ERROR_LIMIT {
+        return enclose(quotient, ::std::f64::NAN)
+    }
+    // The remainder is exact, and the exact quotient is `quotient + remainder / divisor`
+    let remainder = (-quotient).mul_add(divisor, dividend);
+    enclose(quotient, if divisor < 0.0 { -remainder } else { remainder })
+}
+
+fn sqrt_bounds(value: f64) -> (f64, f64) {
+    let root = sqrt_correctly_rounded(value);
+    if value == 0.0 || value.is_infinite() {
+        (root, root)
+    } else if value < EXACT_ERROR_LIMIT {
+        enclose(root, ::std::f64::NAN)
+    } else {
+        // The residual of a correctly rounded root is exact, and has the sign of the root's error
+        enclose(root, (-root).mul_add(root, value))
+    }
+}
+
+fn log2_bounds(value: f64) -> (f64, f64) {
+    let (mantissa, exponent, _) = bits::decode(value);
+    if value > 0.0 && value.is_finite() && mantissa.is_power_of_two() {
+        // Powers of two have exact logarithms, even if they're subnormal
+        let exact = f64::from(exponent) + f64::from(mantissa.trailing_zeros());
+        return (exact, exact)
+    }
+    let result = log2(value);
+    if value == 0.0 || value.is_infinite() {
+        (result, result)
+    } else {
+        widen(result, log::ERROR_ULPS)
+    }
+}
+
+fn ln_bounds(value: f64) -> (f64, f64) {
+    let result = ln(value);
+    if value == 0.0 || value == 1.0 || value.is_infinite() {
+        // These are exact
+        (result, result)
+    } else {
+        widen(result, log::ERROR_ULPS)
+    }
+}
+
+fn pow_bounds(base: f64, power: f64) -> (f64, f64) {
+    let result = pow(base, power);
+    if base == 0.0 || base == 1.0 || base.is_infinite() || power == 0.0 || power.is_infinite() {
+        // The special cases are exact (or limits)
+        (result, result)
+    } else {
+        widen(result, pow::ERROR_ULPS)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Interval;
+    use bits::{next_up, next_down};
+    use std::f64::consts::{PI, FRAC_PI_2};
+    use quickcheck::{Arbitrary, Gen};
+    use rug::Float;
+    use rug::float::Constant;
+    use rug::ops::Pow;
+
+    const PRECISION: u32 = 256;
+
+    impl Arbitrary for Interval<f64> {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            let (first, second) = (f64::arbitrary(g), f64::arbitrary(g));
+            // Points and intervals that are much wider than the quickcheck range are both interesting
+            match g.next_u32() % 4 {
+                0 => Interval::point(first),
+                1 => Interval::new(first.min(second) * 1e3, first.max(second) * 1e3),
+                _ => Interval::new(first.min(second), first.max(second)),
+            }
+        }
+    }
+
+    /// Picks a point inside the interval, where zero and 255 are the bounds
+    fn sample(interval: Interval<f64>, position: u8) -> f64 {
+        let fraction = f64::from(position) / 255.0;
+        let point = interval.lower() + (interval.upper() - interval.lower()) * fraction;
+        point.max(interval.lower()).min(interval.upper())
+    }
+
+    fn encloses(interval: Interval<f64>, exact: &Float) -> bool {
+        if exact.is_nan() {
+            return interval.is_empty()
+        }
+        Float::with_val(53, interval.lower()) <= *exact && *exact <= Float::with_val(53, interval.upper())
+    }
+
+    #[test]
+    fn basic() {
+        let interval = Interval::new(1.0, 2.0);
+        assert_eq!(interval + interval, Interval::new(2.0, 4.0));
+        assert_eq!(interval - interval, Interval::new(-1.0, 1.0));
+        assert_eq!(interval * -interval, Interval::new(-4.0, -1.0));
+        assert_eq!(interval / Interval::new(4.0, 8.0), Interval::new(0.125, 0.5));
+        assert_eq!(interval / Interval::new(-1.0, 1.0), Interval::ENTIRE);
+        assert!((interval / Interval::point(0.0)).is_empty());
+        assert!((Interval::EMPTY + interval).is_empty());
+        assert_eq!(Interval::new(4.0, 9.0).sqrt(), Interval::new(2.0, 3.0));
+        assert_eq!(Interval::new(-4.0, 9.0).sqrt(), Interval::new(0.0, 3.0));
+        assert!(Interval::new(-4.0, -1.0).sqrt().is_empty());
+        assert_eq!(Interval::new(0.0, 8.0).log2(), Interval::new(::std::f64::NEG_INFINITY, 3.0));
+        assert_eq!(Interval::point(1.0).ln(), Interval::point(0.0));
+        assert_eq!(Interval::new(0.0, 2.0).pow(Interval::new(-1.0, 1.0)), Interval::new(0.0, ::std::f64::INFINITY));
+        assert_eq!(Interval::new(0.0, ::std::f64::INFINITY) * Interval::point(0.0), Interval::point(0.0));
+        assert_eq!(Interval::new(1.0, 2.0).to_string(), "[1, 2]");
+    }
+
+    #[test]
+    fn inexact() {
+        let third = Interval::point(1.0) / Interval::point(3.0);
+        assert!(third.lower() < third.upper());
+        assert_eq!(third.lower(), 1.0 / 3.0);
+        let sum = Interval::point(0.1) + Interval::point(0.2);
+        assert_eq!(sum.upper(), 0.1 + 0.2);
+        assert!(sum.lower() < sum.upper());
+        let overflow = Interval::point(::std::f64::MAX) * Interval::point(2.0);
+        assert_eq!(overflow, Interval::new(::std::f64::MAX, ::std::f64::INFINITY));
+    }
+
+    #[test]
+    fn extrema() {
+        assert_eq!(Interval::new(1.0, 2.0).sin().upper(), 1.0);
+        assert_eq!(Interval::new(-2.0, -1.0).sin().lower(), -1.0);
+        assert_eq!(Interval::new(-1.0, 1.0).cos().upper(), 1.0);
+        assert_eq!(Interval::new(3.0, 3.5).cos().lower(), -1.0);
+        // Neither contains an extremum, so the bounds come from the endpoints
+        let (sin, cos) = Interval::new(0.5, 1.0).sin_cos();
+        assert!(sin.upper() < 1.0 && sin.lower() > 0.0);
+        assert!(cos.upper() < 1.0 && cos.lower() > 0.0);
+        assert_eq!(Interval::new(0.0, 2.0 * PI).sin(), Interval::new(-1.0, 1.0));
+        // Almost a full period, containing both extrema of sine but only the minimum of cosine
+        let wide = Interval::new(0.1, 2.0 * PI - 0.1);
+        assert_eq!(wide.sin(), Interval::new(-1.0, 1.0));
+        assert_eq!(wide.cos().lower(), -1.0);
+        assert!(wide.cos().upper() < 1.0);
+        // Only the lowest bits of the quadrants of huge arguments are meaningful
+        let huge = Interval::new(1e15, 1e15 + 4.0);
+        for position in (0..256).step_by(15) {
+            let x = Float::with_val(PRECISION, sample(huge, position as u8));
+            assert!(encloses(huge.sin(), &Float::with_val(PRECISION, x.sin_ref())));
+            assert!(encloses(huge.cos(), &Float::with_val(PRECISION, x.cos_ref())));
+        }
+        assert_eq!(Interval::new(FRAC_PI_2, FRAC_PI_2).sin().upper(), 1.0);
+    }
+
+    /// The trigonometric functions are least accurate near multiples of pi/2,
+    /// where the results are tiny and all the precision comes from the range reduction.
+    #[test]
+    fn near_multiples_of_pi_2() {
+        let half_pi = Float::with_val(2048, Constant::Pi) / 2;
+        let mut multiple = Float::with_val(2048, 1);
+        let mut targets = vec![6381956970095103.0 * 2.0f64.powi(797)];
+        while multiple < 1e300 {
+            targets.push((Float::with_val(2048, &multiple) * &half_pi).to_f64());
+            multiple *= 10;
+        }
+        for &target in &targets {
+            let x = Float::with_val(PRECISION, target);
+            let interval = Interval::point(target);
+            assert!(encloses(interval.sin(), &Float::with_val(PRECISION, x.sin_ref())), "Failed sin({:e})", target);
+            assert!(encloses(interval.cos(), &Float::with_val(PRECISION, x.cos_ref())), "Failed cos({:e})", target);
+        }
+    }
+
+    #[test]
+    fn points() {
+        // Only inexact roots need widening, and then just by an ulp
+        let root = Interval::point(2.0).sqrt();
+        assert_eq!(next_up(root.lower()), root.upper());
+        assert!(encloses(root, &Float::with_val(PRECISION, 2).sqrt()));
+        let subnormal = Interval::point(5e-324).sqrt();
+        assert!(encloses(subnormal, &Float::with_val(PRECISION, 5e-324).sqrt()));
+        assert_eq!(Interval::point(5e-324).log2(), Interval::point(-1074.0));
+        // Logarithms lose the most relative accuracy right next to one
+        for &target in &[next_up(1.0), next_down(1.0), 1.0 + 1e-10, 1.0 - 1e-10] {
+            let x = Float::with_val(PRECISION, target);
+            assert!(encloses(Interval::point(target).log2(), &Float::with_val(PRECISION, x.log2_ref())));
+            assert!(encloses(Interval::point(target).ln(), &Float::with_val(PRECISION, x.ln_ref())));
+        }
+        // Large powers magnify any error in the logarithm of the base
+        for &(base, power) in &[(1.0 + 1e-10, 7e12), (next_down(1.0), -1e18), (0.5, 1074.0), (10.0, 308.0)] {
+            let exact = Float::with_val(PRECISION, base).pow(Float::with_val(PRECISION, power));
+            assert!(encloses(Interval::point(base).pow(Interval::point(power)), &exact), "Failed {}^{}", base, power);
+        }
+    }
+
+    #[quickcheck]
+    fn arithmetic_encloses(first: Interval<f64>, second: Interval<f64>, positions: (u8, u8)) -> bool {
+        let x = Float::with_val(PRECISION, sample(first, positions.0));
+        let y = Float::with_val(PRECISION, sample(second, positions.1));
+        let quotient = if second.contains(0.0) { true } else {
+            encloses(first / second, &Float::with_val(PRECISION, &x / &y))
+        };
+        encloses(first + second, &Float::with_val(PRECISION, &x + &y)) &&
+            encloses(first - second, &Float::with_val(PRECISION, &x - &y)) &&
+            encloses(first * second, &Float::with_val(PRECISION, &x * &y)) &&
+            quotient
+    }
+
+    #[quickcheck]
+    fn functions_enclose(interval: Interval<f64>, position: u8) -> bool {
+        let x = Float::with_val(PRECISION, sample(interval, position));
+        let magnitude = interval.abs_interval();
+        let positive = Float::with_val(PRECISION, sample(magnitude, position));
+        // The logarithm of just zero is empty, rather than negative infinity
+        let logarithms = magnitude.upper() == 0.0 || {
+            encloses(magnitude.log2(), &Float::with_val(PRECISION, positive.log2_ref())) &&
+                encloses(magnitude.ln(), &Float::with_val(PRECISION, positive.ln_ref()))
+        };
+        encloses(interval.sin(), &Float::with_val(PRECISION, x.sin_ref())) &&
+            encloses(interval.cos(), &Float::with_val(PRECISION, x.cos_ref())) &&
+            encloses(magnitude.sqrt(), &Float::with_val(PRECISION, positive.sqrt_ref())) &&
+            logarithms
+    }
+
+    #[quickcheck]
+    fn pow_encloses(base: Interval<f64>, power: Interval<f64>, positions: (u8, u8)) -> bool {
+        let base = base.abs_interval();
+        let x = Float::with_val(PRECISION, sample(base, positions.0));
+        let y = Float::with_val(PRECISION, sample(power, positions.1));
+        if base.upper() == 0.0 && power.upper() < 0.0 {
+            return base.pow(power).is_empty()
+        }
+        encloses(base.pow(power), &Float::with_val(PRECISION, (&x).pow(&y)))
+    }
+
+    impl Interval<f64> {
+        /// The interval containing the absolute values of this one
+        fn abs_interval(self) -> Interval<f64> {
+            if self.contains(0.0) {
+                Interval::new(0.0, self.upper().max(-self.lower()))
+            } else if self.lower() > 0.0 {
+                self
+            } else {
+                -self
+            }
+        }
+    }
+}
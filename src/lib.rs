@@ -1,4 +1,4 @@
-#![feature(plugin, const_fn, proc_macro, proc_macro_hygiene)]
+#![feature(plugin, const_fn, proc_macro, proc_macro_hygiene, proc_macro_non_items)]
 #![plugin(quickcheck_macros)]
 #[cfg(test)]
 extern crate quickcheck;
@@ -8,14 +8,43 @@ extern crate lazy_static;
 extern crate ordered_float;
 extern crate num_traits;
 extern crate rug;
+extern crate extended_float;
+extern crate extended_float_macros;
 
+#[macro_use]
+pub mod accuracy;
 #[macro_use]
 mod utils;
+mod float;
 mod sqrt;
+mod reduce;
 mod trig;
 mod pow;
 mod log;
+mod exp;
+mod hyperbolic;
+mod exponent;
+mod rounding;
+mod double_double;
+pub mod bits;
+pub mod interval;
 
-pub use self::trig::{sin, sin_cos, cos};
-pub use self::sqrt::{approximate_sqrt, sqrt};
-pub use self::log::{log, log2, log10, ln};
\ No newline at end of file
+pub use self::float::ToyFloat;
+pub use self::trig::{sin, sin_cos, cos, tan, asin, acos, atan, atan2};
+pub use self::sqrt::{
+    approximate_sqrt, sqrt, sqrt_correctly_rounded,
+    approximate_rsqrt, rsqrt, cbrt, nth_root
+};
+pub use self::log::{
+    log, log2, log10, ln, log2_precise, ln_precise, log10_precise,
+    log2_with_prec, ln_with_prec, log_with_prec, log1p
+};
+pub use self::pow::{pow, ipow, powi, checked_ipow_u64, ipow_mod};
+pub use self::exp::{exp, exp2, exp_m1, expf, exp2f, exp_m1f};
+pub use self::hyperbolic::{sinh, cosh, tanh, asinh, acosh, atanh};
+pub use self::exponent::{
+    frexp, ldexp, scalbn, ilogb, logb, nextafter,
+    ILOGB_ZERO, ILOGB_NAN, ILOGB_INFINITY
+};
+pub use self::rounding::{sqrt_round, log2_round, sin_round, cos_round, Round};
+pub use self::double_double::{f64x2, ParseDoubleDoubleError};
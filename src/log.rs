@@ -1,105 +1,406 @@
-use std::f64;
-use std::f64::consts::E;
-
 use rug::{Float};
 use rug::float::Special;
-use rug::ops::Pow;
+
+use float::ToyFloat;
+use bits;
+use utils::{two_sum, mul_double_double};
 
 const PRECISION: u32 = 128;
+/// The extra bits of precision used for intermediate values, to absorb rounding errors
+const GUARD_BITS: u32 = 64;
+/// The value of `ln(2)` as a double-double
+const LN_2_DOUBLE: (f64, f64) = (0.6931471805599453, 2.3190468138462996e-17);
+/// The value of `log10(2)` as a double-double
+const LOG10_2_DOUBLE: (f64, f64) = (0.3010299956639812, -2.8037281277851704e-18);
+/// One third as a double-double
+const THIRD: (f64, f64) = (0.3333333333333333, 1.850371707708594e-17);
+/// The value of `log2(e)` as a double-double
+const LOG2_E_DOUBLE: (f64, f64) = (1.4426950408889634, 2.0355273740931033e-17);
+// Enough terms of the series for atanh to converge past double-double precision on [0, 3 - 2*sqrt(2)]
+const ATANH_APPROX_TERMS: usize = 14;
+/// The worst error of `log2` and `ln` for doubles in ulps, which `interval` widens their results by.
+///
+/// The double-double core is usually correctly rounded,
+/// but the tests only hold it to two ulps of the standard library, which can be off by another one.
+pub const ERROR_ULPS: u32 = 3;
 
-/// Quickly computes the `floor(log2(x))` of the given number `x`
+/// Quickly computes the `floor(log2(x))` of the given positive finite number `x`
 #[inline]
-pub fn floor_log2(l: f64) -> i32 {
-    // For some odd reason we need to 'reverse' the 'mantissa shift'
-    (::utils::float_decode(l).1 as i32) + 52
+pub fn floor_log2<T: ToyFloat>(l: T) -> i32 {
+    let (mantissa, exponent, _) = l.decode();
+    // Subnormals don't have the implicit bit, so we need to find the highest bit that's actually set
+    (exponent as i32) + (63 - mantissa.leading_zeros() as i32)
 }
 #[inline]
-fn strip_exponent(l: f64) -> f64 {
-    let (significand, _, sign) = ::utils::float_decode(l);
-    ::utils::float_encode((significand, -52, sign))
+fn strip_exponent<T: ToyFloat>(l: T) -> T {
+    let (significand, _, sign) = l.decode();
+    T::encode(significand, -(63 - significand.leading_zeros() as i32), sign)
 }
 
+/// Computes the binary logarithm,
+/// using a double-double core for types that fit in a double and falling back to `log2_precise` otherwise.
+#[inline]
+pub fn log2<T: ToyFloat>(target: T) -> T {
+    match fast_log2(target) {
+        Some((high, low)) => T::from_f64(high + low),
+        None => log2_precise(target),
+    }
+}
+/// Computes the binary logarithm using arbitrary precision arithmetic,
+/// which is much slower than `log2` but serves as a reference.
+#[inline]
+pub fn log2_precise<T: ToyFloat>(target: T) -> T {
+    T::from_big(&log2_big(target))
+}
+/// Computes `log2(x)` as a double-double, if the type fits in a double and the input is in range
 #[inline]
-pub fn log2(target: f64) -> f64 {
-    log2_precise(target).to_f64()
+fn fast_log2<T: ToyFloat>(target: T) -> Option<(f64, f64)> {
+    if T::MANTISSA_DIGITS <= 53 && target > T::ZERO && target.is_finite() {
+        Some(log2_double_double(target.to_f64()))
+    } else {
+        None
+    }
 }
-fn log2_precise(target: f64) -> Float {
-    if target <= 0.0 || !target.is_normal() {
+/// Computes `log2(x)` of a positive finite double as an unevaluated sum `high + low`.
+///
+/// This is accurate to well past double precision,
+/// so callers like `pow` can multiply it by a large number without losing digits.
+pub fn log2_double_double(x: f64) -> (f64, f64) {
+    debug_assert!(x > 0.0 && x.is_finite(), "Invalid x = {}", x);
+    let (mantissa, exponent, _) = bits::decode(x);
+    // Normalize subnormals so the mantissa always has its leading bit set
+    let shift = mantissa.leading_zeros() as i32 - 11;
+    let mut characteristic = exponent as i32 - shift + 52;
+    let mut reduced = f64::from_bits((1023 << 52) | ((mantissa << shift) & 0xfffffffffffff));
+    // Center the reduced value around one, so it's in [sqrt(1/2), sqrt(2)]
+    if reduced > ::std::f64::consts::SQRT_2 {
+        reduced *= 0.5;
+        characteristic += 1;
+    }
+    /*
+     * We use ln(m) = 2 * atanh(s) where s = (m - 1)/(m + 1),
+     * since |s| <= 0.172 and the series for atanh only has odd powers.
+     * The subtraction is exact, but the division needs its rounding error tracked.
+     */
+    let numerator = reduced - 1.0;
+    let denominator = two_sum(reduced, 1.0);
+    let ratio = numerator / denominator.0;
+    let ratio = (
+        ratio,
+        (ratio.mul_add(-denominator.0, numerator) - ratio * denominator.1) / denominator.0
+    );
+    let square = mul_double_double(ratio, ratio);
+    // atanh(s)/s = 1 + s^2/3 + s^4/5 + ..., where everything past the first two terms is tiny enough for a double
+    let mut tail = 0.0;
+    let mut n = ATANH_APPROX_TERMS;
+    while n > 1 {
+        tail = tail * square.0 + 1.0 / (2 * n + 1) as f64;
+        n -= 1;
+    }
+    let (coefficient, error) = two_sum(THIRD.0, tail * square.0);
+    let series = mul_double_double(square, (coefficient, error + THIRD.1));
+    let (sum, error) = two_sum(1.0, series.0);
+    let atanh = mul_double_double(ratio, (sum, error + series.1));
+    let (high, low) = mul_double_double((2.0 * atanh.0, 2.0 * atanh.1), LOG2_E_DOUBLE);
+    let (result, error) = two_sum(characteristic as f64, high);
+    two_sum(result, error + low)
+}
+/// Computes `log2(x)` to `PRECISION` bits by repeated squaring, without rounding to the target type.
+pub fn log2_big<T: ToyFloat>(target: T) -> Float {
+    if target.is_nan() || target < T::ZERO {
         return Float::with_val(PRECISION, Special::Nan)
+    } else if target == T::ZERO {
+        // This includes negative zero, which is the limit from above just like positive zero
+        return Float::with_val(PRECISION, Special::NegInfinity)
+    } else if target.is_infinite() {
+        return Float::with_val(PRECISION, Special::Infinity)
     }
     let characteristic = floor_log2(target);
     let target = strip_exponent(target);
-    let one = Float::with_val(PRECISION, 1);
+    log2_iterate(characteristic, target.to_big(PRECISION + GUARD_BITS), PRECISION)
+}
+
+/// Computes `log2(x)` to the specified precision, using the same iterative algorithm as `log2_precise`.
+///
+/// The number of iterations grows with the precision, since each one determines at least one more bit.
+pub fn log2_with_prec(x: &Float, prec: u32) -> Float {
+    if x.is_nan() || x.is_sign_negative() && !x.is_zero() {
+        return Float::with_val(prec, Special::Nan);
+    } else if x.is_zero() {
+        return Float::with_val(prec, Special::NegInfinity);
+    } else if x.is_infinite() {
+        return Float::with_val(prec, Special::Infinity);
+    }
+    // The exponent is for a significand in [0.5, 1), so it's one more than the characteristic
+    let characteristic = x.get_exp().unwrap() - 1;
+    /*
+     * Close to one the result is tiny, and the squarings only keep absolute precision,
+     * so we need more bits to get the same relative precision.
+     * The subtraction is exact, since `x` is within a factor of two of one.
+     */
+    let extra = if characteristic == 0 || characteristic == -1 {
+        let difference = Float::with_val(x.prec(), x - 1u32);
+        difference.get_exp().map_or(0, |exponent| (-exponent).max(0) as u32)
+    } else {
+        0
+    };
+    let reduced = Float::with_val(prec + GUARD_BITS + extra, x >> characteristic);
+    log2_iterate(characteristic, reduced, prec)
+}
+
+/// Computes `ln(x)` to the specified precision, dividing by `log2(e)` from `log2_with_prec`.
+pub fn ln_with_prec(x: &Float, prec: u32) -> Float {
+    let working = prec + GUARD_BITS;
+    let log2_e = log2_with_prec(&euler_with_prec(working), working);
+    Float::with_val(prec, log2_with_prec(x, working) / log2_e)
+}
+
+/// Computes the logarithm of `x` with the specified base to the specified precision.
+pub fn log_with_prec(x: &Float, base: &Float, prec: u32) -> Float {
+    let working = prec + GUARD_BITS;
+    Float::with_val(prec, log2_with_prec(x, working) / log2_with_prec(base, working))
+}
+
+/// Computes `characteristic + log2(y)` for `y` in [1, 2), rounding the result to the given precision.
+///
+/// The reduced value should already have `GUARD_BITS` more precision than the result,
+/// since every squaring doubles its relative error.
+fn log2_iterate(characteristic: i32, mut y: Float, prec: u32) -> Float {
+    let working = y.prec();
     /*
      * We use the algorithm described on wikipedia: https://en.wikipedia.org/wiki/Binary_logarithm#Iterative_approximation
      * First we need to compute the 'characteristic' of the logarithm,
      * or rather the integer part given by `floor(log2(x))`.
-     *
+     * Then we repeatedly square the remaining value until it's at least two,
+     * and the number of squarings gives the position of the next set bit.
      */
-    let mut result = Float::with_val(PRECISION, characteristic);
-    let mut y = Float::with_val(PRECISION, target);
-    let mut pow = one.clone();
-    for _ in 0..100 {
-        if y == 1.0 { break }
-        debug_assert!(y >= 1.0 && y < 2.0, "Invalid y: {}", y);
-        let mut z = y.clone().square();
-        let mut m = 1;
-        while z < 2 {
-            z.square_mut();
+    debug_assert!(y >= 1.0 && y < 2.0, "Invalid y: {}", y);
+    let mut result = Float::with_val(working, characteristic);
+    let mut pow = Float::with_val(working, 1);
+    let mut bits = 0;
+    while y != 1.0 {
+        let mut m = 0;
+        while y < 2 {
+            y.square_mut();
             m += 1;
+            if bits + m > working {
+                // The remaining bits are past the precision
+                return Float::with_val(prec, result);
+            }
         }
-        debug_assert!(z >= 2 && z < 4, "Invalid z = {}", z);
-        debug_assert_nearly_equals!(
-            y.clone().pow(Float::with_val(PRECISION, m).exp2()),
-            z, Float::with_val(PRECISION, Float::parse("10e-6").unwrap()),
-            "Invalid m = {} for y = {}", m, y
-        );
-        let half = z / 2;
-        debug_assert!(half >= 1 && half < 2);
-        if half == y {
-            // We have the best approximation we can give
-            break
-        }
-        pow *= Float::with_val(PRECISION, -m).exp2();
+        debug_assert!(y >= 2 && y < 4, "Invalid y = {}", y);
+        bits += m;
+        pow >>= m;
         result += &pow;
-        y = half;
+        y >>= 1;
+    }
+    Float::with_val(prec, result)
+}
+
+/// Computes Euler's number to the specified precision by summing `1/n!`
+fn euler_with_prec(prec: u32) -> Float {
+    let mut result = Float::with_val(prec, 1);
+    let mut term = Float::with_val(prec, 1);
+    let mut n = 1u32;
+    while term.get_exp().map_or(false, |exponent| exponent > -(prec as i32)) {
+        term /= n;
+        result += &term;
+        n += 1;
     }
     result
 }
 
 lazy_static! {
-    static ref LOG2_E: Float = log2_precise(E);
-    static ref LOG2_10: Float = log2_precise(10.0);
+    static ref LOG2_E: Float = log2_with_prec(&euler_with_prec(PRECISION), PRECISION);
+    static ref LOG2_10: Float = log2_with_prec(&Float::with_val(PRECISION, 10), PRECISION);
+}
+pub fn ln<T: ToyFloat>(target: T) -> T {
+    match fast_log2(target) {
+        Some(value) => {
+            let (high, low) = mul_double_double(value, LN_2_DOUBLE);
+            T::from_f64(high + low)
+        },
+        None => ln_precise(target),
+    }
+}
+pub fn log10<T: ToyFloat>(target: T) -> T {
+    match fast_log2(target) {
+        Some(value) => {
+            let (high, low) = mul_double_double(value, LOG10_2_DOUBLE);
+            T::from_f64(high + low)
+        },
+        None => log10_precise(target),
+    }
+}
+pub fn log<T: ToyFloat>(target: T, base: T) -> T {
+    match (fast_log2(target), fast_log2(base)) {
+        (Some(value), Some(divisor)) if divisor.0 == 0.0 => {
+            // The base is one, so let IEEE division decide between infinity and NaN
+            T::from_f64(value.0 / divisor.0)
+        },
+        (Some(value), Some(divisor)) => {
+            // Divide the double-doubles with one correction step, using the remainder of the first quotient
+            let quotient = value.0 / divisor.0;
+            let remainder = (-quotient).mul_add(divisor.0, value.0) - quotient * divisor.1 + value.1;
+            T::from_f64(quotient + remainder / divisor.0)
+        },
+        _ => T::from_big(&(log2_big(target) / log2_big(base))),
+    }
 }
-pub fn ln(target: f64) -> f64 {
-    (log2_precise(target) / &*LOG2_E).to_f64()
+/// Computes `ln(1 + x)`, which stays accurate when `x` is tiny and `1 + x` would round to one.
+pub fn log1p<T: ToyFloat>(x: T) -> T {
+    if x.is_nan() || x < -T::ONE {
+        return T::NAN;
+    } else if x == -T::ONE {
+        return T::NEG_INFINITY;
+    } else if x == T::ZERO || x.is_infinite() {
+        // This keeps the sign of negative zero
+        return x;
+    }
+    if T::MANTISSA_DIGITS <= 53 {
+        let x = x.to_f64();
+        let sum = 1.0 + x;
+        /*
+         * ln(1 + x) = ln(s) + ln(1 + e/s), where `e` is the rounding error in the sum `s`.
+         * The subtraction is exact whenever the error matters, and e/s is tiny enough for one term.
+         */
+        let error = x - (sum - 1.0);
+        let (high, low) = mul_double_double(log2_double_double(sum), LN_2_DOUBLE);
+        T::from_f64(high + (low + error / sum))
+    } else {
+        let sum = T::ONE + x;
+        if sum == T::ONE {
+            return x;
+        }
+        // Scaling by x/(s - 1) cancels out the rounding error in the sum
+        ln(sum) * (x / (sum - T::ONE))
+    }
 }
-pub fn log10(target: f64) -> f64 {
-    (log2_precise(target) / &*LOG2_10).to_f64()
+pub fn ln_precise<T: ToyFloat>(target: T) -> T {
+    T::from_big(&(log2_big(target) / &*LOG2_E))
 }
-pub fn log(target: f64, base: f64) -> f64 {
-    (log2_precise(target) / log2_precise(base)).to_f64()
+pub fn log10_precise<T: ToyFloat>(target: T) -> T {
+    T::from_big(&(log2_big(target) / &*LOG2_10))
 }
 
 #[cfg(test)]
 mod test {
 
-    use super::{log2, ln, log10, log};
-    use utils::NearlyEquals;
-    const ALLOWED_ERROR: f64 = 1e-15;
+    use super::{log2, ln, log10, log, log2_precise, ln_precise, log10_precise, floor_log2, log1p};
+    use super::{log2_with_prec, ln_with_prec, log_with_prec, ERROR_ULPS};
+    use rug::Float;
+    use rug::float::Special;
+    use accuracy::ulp_distance;
+    use utils::Subnormal;
+    use extended_float::ExtendedFloat;
+    /// The fast paths are usually correctly rounded, but `log10` and `log` in the standard library aren't
+    const ALLOWED_ULPS: u64 = 2;
     #[test]
     fn basic() {
-        assert_nearly_equals!(log2(18.0), 18.0f64.log2(), ALLOWED_ERROR);
+        assert_within_ulps!(log2(18.0), 18.0f64.log2(), 1);
         assert_eq!(log2(38.052098393873905), 5.24990410864147473);
     }
+    #[test]
+    fn generic() {
+        assert_eq!(log2(8.0f32), 3.0);
+        assert_eq!(log2(ExtendedFloat::from(1024.0)), ExtendedFloat::from(10.0));
+        assert_within_ulps!(ln(10.0f32), 10.0f32.ln(), 1);
+    }
 
+    #[test]
+    fn special() {
+        let inf = ::std::f64::INFINITY;
+        for &(target, expected) in &[(0.0, -inf), (-0.0, -inf), (inf, inf), (1.0, 0.0)] {
+            assert_eq!(log2(target), expected);
+            assert_eq!(ln(target), expected);
+            assert_eq!(log10(target), expected);
+            assert_eq!(log2_precise(target), expected);
+            assert_eq!(ln_precise(target), expected);
+            assert_eq!(log10_precise(target), expected);
+        }
+        assert_eq!(log2(1.0).to_bits(), 0.0f64.to_bits());
+        for &target in &[-1.0, -inf, -5e-324, ::std::f64::NAN] {
+            assert!(log2(target).is_nan());
+            assert!(ln(target).is_nan());
+            assert!(log10(target).is_nan());
+            assert!(log2_precise(target).is_nan());
+        }
+        assert_eq!(log(8.0, 1.0), inf);
+        assert_eq!(log(0.5, 1.0), -inf);
+        assert!(log(1.0, 1.0).is_nan());
+        assert_eq!(log(1.0, 10.0), 0.0);
+        assert_eq!(log(0.0, 10.0), -inf);
+        assert_eq!(log(inf, 10.0), inf);
+        assert!(log(-2.0, 10.0).is_nan());
+        assert!(log(2.0, -10.0).is_nan());
+        assert!(log(ExtendedFloat::from(1.0), ExtendedFloat::from(1.0)).is_nan());
+        assert_eq!(log(ExtendedFloat::from(2.0), ExtendedFloat::from(1.0)), ExtendedFloat::from(inf));
+        assert_eq!(log2(ExtendedFloat::from(0.0)), ExtendedFloat::from(-inf));
+        assert_eq!(log2(0.0f32), -::std::f32::INFINITY);
+    }
+    #[test]
+    fn subnormal() {
+        assert_eq!(floor_log2(5e-324), -1074);
+        assert_eq!(floor_log2(3e-320), -1062);
+        assert_eq!(floor_log2(1.0), 0);
+        assert_eq!(floor_log2(2.2250738585072014e-308), -1022);
+        assert_eq!(log2(5e-324), -1074.0);
+        assert_eq!(log2_precise(5e-324), -1074.0);
+        assert_within_ulps!(log2_precise(3e-320), (3e-320f64).log2(), 1);
+        assert_within_ulps!(ln_precise(3e-320), (3e-320f64).ln(), 1);
+    }
+    #[quickcheck]
+    fn subnormal_matches_std(target: Subnormal) -> bool {
+        let Subnormal(target) = target;
+        ulp_distance(log2(target), target.log2()) <= 1 &&
+            ulp_distance(log2_precise(target), target.log2()) <= 1
+    }
+    #[test]
+    fn log1p_tiny() {
+        assert_eq!(log1p(1e-20), 1e-20);
+        assert_eq!(log1p(-1e-300), -1e-300);
+        assert_eq!(log1p(-0.0).to_bits(), (-0.0f64).to_bits());
+        assert_eq!(log1p(-1.0), -::std::f64::INFINITY);
+        assert!(log1p(-2.0).is_nan());
+        assert_eq!(log1p(::std::f64::INFINITY), ::std::f64::INFINITY);
+        assert_within_ulps!(log1p(1e-10), 1e-10f64.ln_1p(), 1);
+        assert_within_ulps!(f64::from(log1p(ExtendedFloat::from(1e-10))), 1e-10f64.ln_1p(), 1);
+        assert_eq!(log1p(1e-20f32), 1e-20);
+    }
+    #[quickcheck]
+    fn log1p_matches_std(target: f64) -> bool {
+        let small = target * 1e-12;
+        let above = target.abs() - 1.0 + 1e-9;
+        ulp_distance(log1p(target), target.ln_1p()) <= 1 &&
+            ulp_distance(log1p(small), small.ln_1p()) <= 1 &&
+            ulp_distance(log1p(above), above.ln_1p()) <= 1
+    }
+    #[test]
+    fn arbitrary_precision() {
+        for &prec in &[64, 256, 1024] {
+            for &target in &["0.001", "0.75", "1.000000000000000000000000000001", "2", "3", "1e100", "12345.6789"] {
+                let x = Float::with_val(prec, Float::parse(target).unwrap());
+                let base = Float::with_val(prec, 7);
+                // Allow a few ulps, since neither our algorithm nor the expected quotient is correctly rounded
+                let check = |actual: Float, expected: Float| {
+                    assert_within_ulps!(actual, expected, 4, "Failed {} at {} bits", target, prec);
+                };
+                check(log2_with_prec(&x, prec), Float::with_val(prec, x.log2_ref()));
+                check(ln_with_prec(&x, prec), Float::with_val(prec, x.ln_ref()));
+                check(log_with_prec(&x, &base, prec), Float::with_val(prec, x.ln_ref()) / base.ln());
+            }
+        }
+        assert!(log2_with_prec(&Float::with_val(256, -1), 256).is_nan());
+        assert_eq!(log2_with_prec(&Float::with_val(256, 0), 256), Float::with_val(256, Special::NegInfinity));
+        assert_eq!(log2_with_prec(&Float::with_val(256, 1024), 256), 10);
+    }
     #[quickcheck]
     fn log2_matches_std(target: f64) {
         if target.is_sign_positive() {
-            assert_nearly_equals!(
+            assert_within_ulps!(
                 log2(target),
                 target.log2(),
-                ALLOWED_ERROR,
+                ALLOWED_ULPS,
                 "Invalid log2({})",
                 target
             )
@@ -108,23 +409,33 @@ mod test {
     #[quickcheck]
     fn ln_matches_std(target: f64) {
         if target.is_sign_positive() {
-            assert_nearly_equals!(
+            assert_within_ulps!(
                 ln(target),
                 target.ln(),
-                ALLOWED_ERROR,
+                ALLOWED_ULPS,
                 "Invalid log2({})",
                 target
             )
         }
     }
 
+    #[quickcheck]
+    fn within_error_bound(target: f64) -> bool {
+        let target = target.abs();
+        if target == 0.0 || !target.is_finite() {
+            return true
+        }
+        ulp_distance(log2(target), log2_precise(target)) <= u64::from(ERROR_ULPS) &&
+            ulp_distance(ln(target), ln_precise(target)) <= u64::from(ERROR_ULPS)
+    }
+
     #[quickcheck]
     fn log10_matches_std(target: f64) {
         if target.is_sign_positive() {
-            assert_nearly_equals!(
+            assert_within_ulps!(
                 log10(target),
                 target.log10(),
-                ALLOWED_ERROR,
+                ALLOWED_ULPS,
                 "Invalid log10({})",
                 target
             )
@@ -134,13 +445,21 @@ mod test {
     #[quickcheck]
     fn log_matches_std(target: f64, base: f64) {
         if target.is_sign_positive() && base.is_sign_positive() {
-            assert_nearly_equals!(
+            assert_within_ulps!(
                 log(target, base),
                 target.log(base),
-                ALLOWED_ERROR,
+                ALLOWED_ULPS,
                 "Invalid log({}, {})",
                 target, base
             )
         }
     }
-}
\ No newline at end of file
+
+    #[quickcheck]
+    fn fast_matches_precise(target: f64) -> bool {
+        let target = target.abs() * 1e10;
+        ulp_distance(log2(target), log2_precise(target)) <= 1 &&
+            ulp_distance(ln(target), ln_precise(target)) <= 1 &&
+            ulp_distance(log10(target), log10_precise(target)) <= 1
+    }
+}
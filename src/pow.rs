@@ -1,53 +1,226 @@
-use rug::Float;
+use rug::ops::Pow;
+
+use float::ToyFloat;
+use utils::mul_double_double;
+use exp::{exp2, exp2_double_double};
+use log::{log2_double_double, log2_big};
 
 const PRECISION: u32 = 128;
+/// The worst error of `pow` for doubles in ulps, which `interval` widens its results by.
+///
+/// The tests hold it to an ulp of the standard library, which can be off by another one.
+pub const ERROR_ULPS: u32 = 2;
+
+/// Raises the base to a floating-point power,
+/// following the special cases for `pow` in Annex F of the C99 standard.
+pub fn pow<T: ToyFloat>(base: T, power: T) -> T {
+    // These hold even if the other argument is NaN
+    if power == T::ZERO || base == T::ONE {
+        return T::ONE;
+    }
+    if base.is_nan() || power.is_nan() {
+        return T::NAN;
+    }
+    if power.is_infinite() {
+        let magnitude = base.abs();
+        return if magnitude == T::ONE {
+            T::ONE
+        } else if (magnitude > T::ONE) == power.is_sign_positive() {
+            T::INFINITY
+        } else {
+            T::ZERO
+        };
+    }
+    let odd = is_odd_integer(power);
+    if base == T::ZERO || base.is_infinite() {
+        // The result is either zero or infinity, and only odd powers keep the sign of the base
+        let result = if base.is_infinite() == power.is_sign_positive() {
+            T::INFINITY
+        } else {
+            T::ZERO
+        };
+        return if odd && base.is_sign_negative() { -result } else { result };
+    }
+    if base.is_sign_negative() {
+        // A negative number has no real roots, so only integer powers are defined
+        if !is_integer(power) {
+            return T::NAN;
+        }
+        let result = pow_positive(-base, power);
+        return if odd { -result } else { result };
+    }
+    pow_positive(base, power)
+}
 
-fn big_pow(base: Float, power: Float, precision: Float) -> Float {
-    // Taken from stack overflow: https://stackoverflow.com/a/3519308/5713037
-    if base == 1.0 {
-        Float::with_val(PRECISION, 1)
-    } else if power < 0.0 {
-        Float::with_val(PRECISION, 1.0) / big_pow(base, -power, precision)
-    } else if power >= 10.0 {
-        big_pow(base, power / 2, precision / 2).square()
-    } else if power >= 1.0 {
-        let f = big_pow(base.clone(), power - 1, precision);
-        base * f
-    } else if precision >= 1.0 {
-        base.sqrt()
+/// Computes `2^(y * log2(x))` for a positive finite `x` and finite `y`
+fn pow_positive<T: ToyFloat>(base: T, power: T) -> T {
+    if T::MANTISSA_DIGITS <= 53 {
+        /*
+         * The product can be as large as ~1075 before the result overflows,
+         * so we need it to about 64 bits for a correct double, which is why everything is double-double.
+         */
+        let (high, low) = log2_double_double(base.to_f64());
+        let power = power.to_f64();
+        let product = power * high;
+        let error = power.mul_add(high, -product) + power * low;
+        T::from_f64(exp2_double_double(product, error))
     } else {
-        big_pow(base, power * 2, precision * 2).sqrt()
+        // Wider types need more than a double-double, so use the arbitrary precision logarithm instead
+        let exponent = log2_big(base) * power.to_big(PRECISION);
+        let quotient = exponent.to_f64().round();
+        if quotient.abs() > f64::from(1 << 20) {
+            // Overflows or underflows regardless of the fraction
+            return exp2(T::from_f64(quotient));
+        }
+        let fraction = T::from_big(&(exponent - quotient));
+        exp2(fraction).ldexp(quotient as i32)
     }
 }
-pub fn pow(base: f64, power: f64) -> f64 {
-    big_pow(
-        Float::with_val(PRECISION, base),
-        Float::with_val(PRECISION, power),
-        Float::with_val(PRECISION, Float::parse("1e-20").unwrap())
-    ).to_f64()
+
+/// Checks if the value is an integer, which is always true past the precision of the mantissa
+fn is_integer<T: ToyFloat>(value: T) -> bool {
+    let (mantissa, exponent, _) = value.decode();
+    exponent >= 0 || mantissa.trailing_zeros() as i32 >= -(exponent as i32)
 }
-pub fn ipow(mut base: f64, mut power: i32) -> f64 {
-    let recip = power < 0;
-    let mut result = 1.0;
+
+/// Checks if the value is an odd integer, meaning the last bit before the binary point is set
+fn is_odd_integer<T: ToyFloat>(value: T) -> bool {
+    let (mantissa, exponent, _) = value.decode();
+    mantissa != 0 && exponent <= 0 && mantissa.trailing_zeros() as i32 == -(exponent as i32)
+}
+/// Raises the base to an integer power by repeated squaring.
+///
+/// This multiplies in the same order as the `powi` intrinsic, so it gives identical results.
+pub fn ipow<T: ToyFloat>(mut base: T, power: i32) -> T {
+    // Widen before taking the magnitude, since `-i32::MIN` doesn't fit in an `i32`
+    let mut remaining = (power as i64).abs() as u64;
+    let mut result = T::ONE;
     loop {
-        if (power & 1) != 0 {
+        if (remaining & 1) != 0 {
             result *= base;
         }
+        remaining /= 2;
+        if remaining == 0 { break }
+        base *= base;
+    }
+    if power < 0 { T::ONE / result } else { result }
+}
+
+/// Raises the base to an integer power, accumulating the product in extended precision.
+///
+/// Plain repeated squaring picks up a rounding error with every multiplication,
+/// which adds up to several ulps once the exponent gets large.
+pub fn powi<T: ToyFloat>(base: T, power: i32) -> T {
+    if T::MANTISSA_DIGITS <= 53 {
+        let value = base.to_f64();
+        if value == 0.0 || !value.is_finite() {
+            return T::from_f64(ipow(value, power));
+        }
+        // Take the reciprocal first, so intermediate results don't overflow when the final one wouldn't
+        let mut base = if power < 0 {
+            let quotient = 1.0 / value;
+            (quotient, -quotient.mul_add(value, -1.0) / value)
+        } else {
+            (value, 0.0)
+        };
+        let mut remaining = (power as i64).abs() as u64;
+        let mut result = (1.0, 0.0);
+        loop {
+            if (remaining & 1) != 0 {
+                result = mul_double_double(result, base);
+            }
+            remaining /= 2;
+            if remaining == 0 { break }
+            base = mul_double_double(base, base);
+        }
+        if result.0.is_finite() {
+            T::from_f64(result.0 + result.1)
+        } else {
+            // Once the high part overflows the low part turns into NaN, so let the plain version handle it
+            T::from_f64(ipow(value, power))
+        }
+    } else {
+        let precision = 2 * T::MANTISSA_DIGITS + 32;
+        T::from_big(&base.to_big(precision).pow(power))
+    }
+}
+
+/// Raises an integer to an integer power, returning `None` if the result overflows.
+pub fn checked_ipow_u64(mut base: u64, mut power: u32) -> Option<u64> {
+    let mut result: u64 = 1;
+    loop {
+        if (power & 1) != 0 {
+            result = result.checked_mul(base)?;
+        }
         power /= 2;
         if power == 0 { break }
-        base *= base;
+        /*
+         * If the square overflows then so would the result,
+         * since there are still higher bits left that need it.
+         */
+        base = base.checked_mul(base)?;
+    }
+    Some(result)
+}
+
+/// Computes `base^power mod modulus` without overflowing.
+///
+/// ## Panics
+/// Panics if the modulus is zero.
+pub fn ipow_mod(base: u64, mut power: u64, modulus: u64) -> u64 {
+    assert_ne!(modulus, 0, "Zero modulus");
+    let modulus = modulus as u128;
+    let mut base = base as u128 % modulus;
+    let mut result = 1 % modulus;
+    while power != 0 {
+        if (power & 1) != 0 {
+            result = (result * base) % modulus;
+        }
+        power /= 2;
+        base = (base * base) % modulus;
     }
-    if recip { 1.0 / result } else { result }
+    result as u64
 }
 
 #[cfg(test)]
 mod test {
-    use super::{pow, ipow};
+    use super::{pow, ipow, powi, checked_ipow_u64, ipow_mod};
+    use std::i32;
+    use rug::{Float, Integer};
+    use rug::ops::Pow;
+    use num_traits::checked_pow;
     use ordered_float::OrderedFloat;
+    use extended_float::ExtendedFloat;
+    use accuracy::ulp_distance;
     #[test]
     fn basic() {
         assert_eq!(ipow(2.0, 0), 1.0);
-        assert_eq!(ipow(2.0, 2), 4.0)
+        assert_eq!(ipow(2.0, 2), 4.0);
+        assert_eq!(ipow(2.0, -3), 0.125);
+        assert_eq!(ipow(-2.0, -3), -0.125);
+        assert_eq!(ipow(0.5, -1074), ::std::f64::INFINITY);
+    }
+    #[test]
+    fn min_exponent() {
+        assert_eq!(ipow(1.0, i32::MIN), 1.0);
+        assert_eq!(ipow(-1.0, i32::MIN), 1.0);
+        assert_eq!(ipow(2.0, i32::MIN), 0.0);
+        assert_eq!(ipow(0.5, i32::MIN), ::std::f64::INFINITY);
+        assert_eq!(powi(-1.0, i32::MIN), 1.0);
+        assert_eq!(powi(2.0, i32::MIN), 0.0);
+        assert_within_ulps!(powi(1.0 + 1e-10, i32::MIN), (1.0f64 + 1e-10).powf(i32::MIN as f64), 1);
+    }
+    #[test]
+    fn integer() {
+        assert_eq!(checked_ipow_u64(3, 40), Some(12157665459056928801));
+        assert_eq!(checked_ipow_u64(3, 41), None);
+        assert_eq!(checked_ipow_u64(2, 63), Some(1 << 63));
+        assert_eq!(checked_ipow_u64(2, 64), None);
+        assert_eq!(checked_ipow_u64(0, 0), Some(1));
+        assert_eq!(checked_ipow_u64(1, u32::max_value()), Some(1));
+        assert_eq!(ipow_mod(4, 13, 497), 445);
+        assert_eq!(ipow_mod(u64::max_value(), u64::max_value(), u64::max_value() - 1), 1);
+        assert_eq!(ipow_mod(7, 0, 1), 0);
     }
     #[quickcheck]
     fn powi_matches_std(base: f64, power: i32) {
@@ -59,12 +232,63 @@ mod test {
         )
     }
     #[quickcheck]
+    fn precise_powi_is_correctly_rounded(base: f64, power: i32) -> bool {
+        let power = power.wrapping_mul(37);
+        let expected = Float::with_val(4096, base).pow(power).to_f64();
+        ulp_distance(powi(base, power), expected) <= 1
+    }
+    #[quickcheck]
+    fn checked_ipow_matches_num(base: u64, power: u8) -> bool {
+        let base = base % 1000;
+        checked_ipow_u64(base, power as u32) == checked_pow(base, power as usize)
+    }
+    #[quickcheck]
+    fn ipow_mod_matches_rug(base: u64, power: u64, modulus: u64) -> bool {
+        let modulus = modulus.max(1);
+        let expected = Integer::from(base).pow_mod(&Integer::from(power), &Integer::from(modulus)).unwrap();
+        Integer::from(ipow_mod(base, power, modulus)) == expected
+    }
+    #[test]
+    fn special() {
+        let nan = ::std::f64::NAN;
+        let inf = ::std::f64::INFINITY;
+        assert!(pow(-8.0, 1.0 / 3.0).is_nan());
+        assert_eq!(pow(-8.0, 3.0), -512.0);
+        assert_eq!(pow(-2.0, -2.0), 0.25);
+        assert_eq!(pow(nan, 0.0), 1.0);
+        assert_eq!(pow(nan, -0.0), 1.0);
+        assert_eq!(pow(1.0, nan), 1.0);
+        assert!(pow(nan, 1.0).is_nan());
+        assert!(pow(2.0, nan).is_nan());
+        assert_eq!(pow(-1.0, inf), 1.0);
+        assert_eq!(pow(-1.0, -inf), 1.0);
+        assert_eq!(pow(0.5, inf), 0.0);
+        assert_eq!(pow(0.5, -inf), inf);
+        assert_eq!(pow(-3.0, inf), inf);
+        assert_eq!(pow(-3.0, -inf), 0.0);
+        assert_eq!(pow(-0.0, -3.0), -inf);
+        assert_eq!(pow(-0.0, -2.0), inf);
+        assert_eq!(pow(0.0, -inf), inf);
+        assert_eq!(pow(-0.0, 3.0).to_bits(), (-0.0f64).to_bits());
+        assert_eq!(pow(-0.0, 0.5).to_bits(), 0.0f64.to_bits());
+        assert_eq!(pow(-inf, 3.0), -inf);
+        assert_eq!(pow(-inf, 2.0), inf);
+        assert_eq!(pow(-inf, -3.0).to_bits(), (-0.0f64).to_bits());
+        assert_eq!(pow(inf, -0.5), 0.0);
+        assert_eq!(pow(2.0, 1e300), inf);
+        assert_eq!(pow(2.0, -1e300), 0.0);
+        assert_eq!(pow(-2.0, 1e300), inf);
+    }
+    #[test]
+    fn generic() {
+        assert_eq!(pow(2.0f32, 10.0), 1024.0);
+        assert_within_ulps!(f64::from(pow(ExtendedFloat::from(3.0), ExtendedFloat::from(4.5))), 3.0f64.powf(4.5), 1);
+        assert_eq!(pow(ExtendedFloat::from(4.0), ExtendedFloat::from(0.5)), ExtendedFloat::from(2.0));
+    }
+    #[quickcheck]
     fn powf_matches_std(base: f64, power: f64) {
-        assert_eq!(
-            OrderedFloat(pow(base, power)),
-            OrderedFloat(base.powf(power)),
-            "Failed {}^{}",
-            base, power
-        )
+        for &(base, power) in &[(base, power), (base, power.round()), (base.abs(), power * 10.0)] {
+            assert_within_ulps!(pow(base, power), base.powf(power), 1, "Failed {}^{}", base, power)
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,396 @@
+//! Range reduction for the trigonometric functions
+//!
+//! Naively computing `x % (2 * PI)` falls apart for large arguments,
+//! since `PI` is only accurate to 53 bits and that error gets multiplied by the quotient.
+//! Instead we compute `x - n * pi/2` using as many bits of pi as the argument needs,
+//! returning the quadrant `n` along with the reduced argument in `[-pi/4, pi/4]`.
+//!
+//! Moderately sized arguments use Cody-Waite reduction with pi/2 split into three pieces,
+//! following fdlibm's `__ieee754_rem_pio2`.
+//! Huge arguments use the Payne-Hanek algorithm,
+//! which multiplies the exact mantissa by just the relevant bits of a stored table of `2/pi`.
+//! Double-doubles always use Payne-Hanek, adding up the fractions of both parts before multiplying by `pi/2`.
+use float::ToyFloat;
+
+/// The value of `2/pi`
+const INV_PIO2: f64 = 6.36619772367581382433e-01;
+/// The first 33 bits of `pi/2`, so multiplying by the quotient is exact
+const PIO2_1: f64 = 1.57079632673412561417e+00;
+/// `pi/2 - PIO2_1`
+const PIO2_1T: f64 = 6.07710050650619224932e-11;
+/// The second 33 bits of `pi/2`
+const PIO2_2: f64 = 6.07710050630396597660e-11;
+/// `pi/2 - (PIO2_1 + PIO2_2)`
+const PIO2_2T: f64 = 2.02226624879595063154e-21;
+/// The third 33 bits of `pi/2`
+const PIO2_3: f64 = 2.02226624871116645580e-21;
+/// `pi/2 - (PIO2_1 + PIO2_2 + PIO2_3)`
+const PIO2_3T: f64 = 8.47842766036889956997e-32;
+/// The high part of `pi/2` as a double-double
+const PIO2_HI: f64 = 1.57079632679489655800e+00;
+/// The low part of `pi/2` as a double-double
+const PIO2_LO: f64 = 6.12323399573676603587e-17;
+/// Cody-Waite is accurate as long as the quotient fits in 20 bits (`2^19 * pi/2`)
+const CODY_WAITE_LIMIT: f64 = 823549.6;
+
+/// The binary expansion of `2/pi`, 64 bits at a time.
+///
+/// Since the reduction only ever needs about 200 bits starting around the argument's exponent,
+/// this needs to cover the whole exponent range of the 80-bit format (16383 bits plus some slack).
+static TWO_OVER_PI: [u64; 260] = [
+    0xA2F9836E4E441529, 0xFC2757D1F534DDC0, 0xDB6295993C439041, 0xFE5163ABDEBBC561,
+    0xB7246E3A424DD2E0, 0x06492EEA09D1921C, 0xFE1DEB1CB129A73E, 0xE88235F52EBB4484,
+    0xE99C7026B45F7E41, 0x3991D639835339F4, 0x9C845F8BBDF9283B, 0x1FF897FFDE05980F,
+    0xEF2F118B5A0A6D1F, 0x6D367ECF27CB09B7, 0x4F463F669E5FEA2D, 0x7527BAC7EBE5F17B,
+    0x3D0739F78A5292EA, 0x6BFB5FB11F8D5D08, 0x56033046FC7B6BAB, 0xF0CFBC209AF4361D,
+    0xA9E391615EE61B08, 0x6599855F14A06840, 0x8DFFD8804D732731, 0x06061556CA73A8C9,
+    0x60E27BC08C6B47C4, 0x19C367CDDCE8092A, 0x8359C4768B961CA6, 0xDDAF44D15719053E,
+    0xA5FF07053F7E33E8, 0x32C2DE4F98327DBB, 0xC33D26EF6B1E5EF8, 0x9F3A1F35CAF27F1D,
+    0x87F121907C7C246A, 0xFA6ED5772D30433B, 0x15C614B59D19C3C2, 0xC4AD414D2C5D000C,
+    0x467D862D71E39AC6, 0x9B0062337CD2B497, 0xA7B4D55537F63ED7, 0x1810A3FC764D2A9D,
+    0x64ABD770F87C6357, 0xB07AE715175649C0, 0xD9D63B3884A7CB23, 0x24778AD623545AB9,
+    0x1F001B0AF1DFCE19, 0xFF319F6A1E666157, 0x9947FBACD87F7EB7, 0x652289E83260BFE6,
+    0xCDC4EF09366CD43F, 0x5DD7DE16DE3B5892, 0x9BDE2822D2E88628, 0x4D58E232CAC616E3,
+    0x08CB7DE050C017A7, 0x1DF35BE01834132E, 0x6212830148835B8E, 0xF57FB0ADF2E91E43,
+    0x4A48D36710D8DDAA, 0x425FAECE616AA428, 0x0AB499D3F2A6067F, 0x775C83C2A3883C61,
+    0x78738A5A8CAFBDD7, 0x6F63A62DCBBFF4EF, 0x818D67C12645CA55, 0x36D9CAD2A8288D61,
+    0xC277C9121426049B, 0x4612C459C444C5C8, 0x91B24DF31700AD43, 0xD4E5492910D5FDFC,
+    0xBE00CC941EEECE70, 0xF53E1380F1ECC3E7, 0xB328F8C79405933E, 0x71C1B3092EF3450B,
+    0x9C12887B20AB9FB5, 0x2EC292472F327B6D, 0x550C90A7721FE76B, 0x96CB314A1679E279,
+    0x4189DFF49794E884, 0xE6E29731996BED88, 0x365F5F0EFDBBB49A, 0x486CA46742727132,
+    0x5D8DB8159F09E5BC, 0x25318D3974F71C05, 0x30010C0D68084B58, 0xEE2C90AA4702E774,
+    0x24D6BDA67DF77248, 0x6EEF169FA6948EF6, 0x91B45153D1F20ACF, 0x3398207E4BF56863,
+    0xB25F3EDD035D407F, 0x8985295255C06437, 0x10D86D324832754C, 0x5BD4714E6E5445C1,
+    0x090B69F52AD56614, 0x9D072750045DDB3B, 0xB4C576EA17F9877D, 0x6B49BA271D296996,
+    0xACCCC65414AD6AE2, 0x9089D98850722CBE, 0xA4049407777030F3, 0x27FC00A871EA49C2,
+    0x663DE06483DD9797, 0x3FA3FD94438C860D, 0xDE41319D39928C70, 0xDDE7B7173BDF082B,
+    0x3715A0805C93805A, 0x921110D8E80FAF80, 0x6C4BFFDB0F903876, 0x185915A562BBCB61,
+    0xB989C7BD401004F2, 0xD2277549F6B6EBBB, 0x22DBAA140A2F2689, 0x768364333B091A94,
+    0x0EAA3A51C2A31DAE, 0xEDAF12265C4DC26D, 0x9C7A2D9756C0833F, 0x03F6F0098C402B99,
+    0x316D07B43915200C, 0x5BC3D8C492F54BAD, 0xC6A5CA4ECD37A736, 0xA9E69492AB6842DD,
+    0xDE6319EF8C76528B, 0x6837DBFCABA1AE31, 0x15DFA1AE00DAFB0C, 0x664D64B705ED3065,
+    0x29BF56573AFF47B9, 0xF96AF3BE75DF9328, 0x3080ABF68C6615CB, 0x040622FA1DE4D9A4,
+    0xB33D8F1B5709CD36, 0xE9424EA4BE13B523, 0x331AAAF0A8654FA5, 0xC1D20F3F0BCD785B,
+    0x76F923048B7B7217, 0x8953A6C6E26E6F00, 0xEBEF584A9BB7DAC4, 0xBA66AACFCF761D02,
+    0xD12DF1B1C1998C77, 0xADC3DA4886A05DF7, 0xF480C62FF0AC9AEC, 0xDDBC5C3F6DDED01F,
+    0xC790B6DB2A3A25A3, 0x9AAF009353AD0457, 0xB6B42D297E804BA7, 0x07DA0EAA76A1597B,
+    0x2A12162DB7DCFDE5, 0xFAFEDB89FDBE896C, 0x76E4FCA90670803E, 0x156E85FF87FD073E,
+    0x2833676186182AEA, 0xBD4DAFE7B36E6D8F, 0x3967955BBF3148D7, 0x8416DF30432DC735,
+    0x6125CE70C9B8CB30, 0xFD6CBFA200A4E46C, 0x05A0DD5A476F21D2, 0x1262845CB9496170,
+    0xE0566B0152993755, 0x50B7D51EC4F1335F, 0x6E13E4305DA92E85, 0xC3B21D3632A1A4B7,
+    0x08D4B1EA21F716E4, 0x698F77FF2780030C, 0x2D408DA0CD4F99A5, 0x20D3A2B30A5D2F42,
+    0xF9B4CBDA11D0BE7D, 0xC1DB9BBD17AB81A2, 0xCA5C6A0817552E55, 0x0027F0147F8607E1,
+    0x640B148D4196DEBE, 0x872AFDDAB6256B34, 0x897BFEF3059EBFB9, 0x4F6A68A82A4A5AC4,
+    0x4FBCF82D985AD795, 0xC7F48D4D0DA63A20, 0x5F57A4B13F149538, 0x800120CC86DD71B6,
+    0xDEC9F560BF11654D, 0x6B0701ACB08CD0C0, 0xB24855510EFB1EC3, 0x72953B06A33540C0,
+    0x7BDC06CC45E0FA29, 0x4EC8CAD641F3E8DE, 0x647CD8649B31BED9, 0xC397A4D45877C5E3,
+    0x6913DAF03C3ABA46, 0x18465F7555F5BDD2, 0xC6926E5D2EACED44, 0x0E423E1C87C461E9,
+    0xFD29F3D6E7CA7C22, 0x35916FC5E0088DD7, 0xFFE26A6EC6FDB0C1, 0x0893745D7CB2AD6B,
+    0x9D6ECD7B723E6A11, 0xC6A9CFF7DF7329BA, 0xC9B55100B70DB2E2, 0x24BA74607DE58AD8,
+    0x742C150D0C188194, 0x667E162901767A9F, 0xBEFDFDEF4556367E, 0xD913D9ECB9BA8BFC,
+    0x97C427A831C36EF1, 0x36C59456A8D8B5A8, 0xB40ECCCF2D891234, 0x576F89562CE3CE99,
+    0xB920D6AA5E6B9C2A, 0x3ECC5F114A0BFDFB, 0xF4E16D3B8E2C86E2, 0x84D4E9A9B4FCD1EE,
+    0xEFC9352E61392F44, 0x2138C8D91B0AFC81, 0x6A4AFBD81C2F84B4, 0x538C994ECC2254DC,
+    0x552AD6C6C096190B, 0xB8701A649569605A, 0x26EE523F0F117F11, 0xB5F4F5CBFC2DBC34,
+    0xEEBC34CC5DE8605E, 0xDD9B8E67EF3392B8, 0x17C99B5861BC57E1, 0xC68351103ED84871,
+    0xDDDD1C2DA118AF46, 0x2C21D7F359987AD9, 0xC0549EFA864FFC06, 0x56AE79E536228922,
+    0xAD38DC9367AAE855, 0x3826829BE7CAA40D, 0x51B133990ED7A948, 0x0569F0B265A7887F,
+    0x974C8836D1F9B392, 0x214A827B21CF98DC, 0x9F405547DC3A74E1, 0x42EB67DF9DFE5FD4,
+    0x5EA4677B7AACBAA2, 0xF65523882B55BA41, 0x086E59862A218347, 0x39E6E389D49EE540,
+    0xFB49E956FFCA0F1C, 0x8A59C52BFA94C5C1, 0xD3CFC50FAE5ADB86, 0xC5476243853B8621,
+    0x94792C8761107B4C, 0x2A1A2C8012BF4390, 0x2688893C78E4C4A8, 0x7BDBE5C23AC4EAF4,
+    0x268A67F7BF920D2B, 0xA365B1933D0B7CBD, 0xDC51A463DD27DDE1, 0x6919949A9529A828,
+    0xCE68B4ED09209F44, 0xCA984E638270237C, 0x7E32B90F8EF5A7E7, 0x561408F1212A9DB5,
+    0x4D7E6F5119A5ABF9, 0xB5D6DF8261DD9602, 0x36169F3AC4A1A283, 0x6DED727A8D39A9B8,
+    0x825C326B5B2746ED, 0x34007700D255F4FC, 0x4D59018071E0E13F, 0x89B295F364A8F1AE,
+];
+
+/// Reduces the argument to `x - n * pi/2`,
+/// returning the quadrant `n` and the remainder as a sum of a high and low part.
+///
+/// The reduced argument is in `[-pi/4, pi/4]` (give or take rounding),
+/// and only the lowest two bits of the quadrant are meaningful.
+pub fn rem_pio2<T: ToyFloat>(x: T) -> (i32, T, T) {
+    debug_assert!(x.is_finite(), "Can't reduce {}", x);
+    if x.is_sign_negative() {
+        let (quadrant, hi, lo) = rem_pio2(-x);
+        return (-quadrant, -hi, -lo)
+    }
+    if x <= T::FRAC_PI_2 * T::HALF {
+        return (0, x, T::ZERO)
+    }
+    /*
+     * Cody-Waite depends on the products with the 33-bit pieces of pi/2 being exact,
+     * which only works out if the argument is a double (or smaller).
+     */
+    if T::MANTISSA_DIGITS <= 53 && x.to_f64() < CODY_WAITE_LIMIT {
+        let (quadrant, hi, lo) = cody_waite(x.to_f64());
+        return (quadrant, T::from_f64(hi), T::from_f64(lo))
+    }
+    let (mantissa, exponent, _) = x.decode();
+    let (quadrant, hi, lo) = payne_hanek(mantissa, exponent as i32);
+    (quadrant, T::from_f64(hi), T::from_f64(lo))
+}
+
+#[inline]
+fn biased_exponent(value: f64) -> i32 {
+    ((value.to_bits() >> 52) & 0x7ff) as i32
+}
+
+/// Reduces a positive double by subtracting up to three 33-bit pieces of `pi/2`,
+/// only using the later pieces if cancellation has eaten the earlier ones.
+fn cody_waite(x: f64) -> (i32, f64, f64) {
+    debug_assert!(x >= 0.0 && x < CODY_WAITE_LIMIT);
+    let quotient = (x * INV_PIO2).round();
+    // Exact since the quotient has at most 20 bits
+    let mut r = x - quotient * PIO2_1;
+    let mut w = quotient * PIO2_1T;
+    let mut hi = r - w;
+    // This first round is good to about 85 bits, unless the result had a lot of cancellation
+    if biased_exponent(x) - biased_exponent(hi) > 16 {
+        let t = r;
+        w = quotient * PIO2_2;
+        r = t - w;
+        w = quotient * PIO2_2T - ((t - r) - w);
+        hi = r - w;
+        // The second round is good to 118 bits, and the third covers every possible double
+        if biased_exponent(x) - biased_exponent(hi) > 49 {
+            let t = r;
+            w = quotient * PIO2_3;
+            r = t - w;
+            w = quotient * PIO2_3T - ((t - r) - w);
+            hi = r - w;
+        }
+    }
+    let lo = (r - hi) - w;
+    (quotient as i32, hi, lo)
+}
+
+#[inline]
+fn two_over_pi_word(index: i32) -> u64 {
+    // Word `k` holds the bits `2^(-64k)` through `2^(-64k - 63)`, so anything before that is zero
+    if index < 1 { 0 } else { TWO_OVER_PI[(index - 1) as usize] }
+}
+
+/// Reduces a double-double `hi + lo` to `x - n * pi/2` with Payne-Hanek,
+/// returning the lowest three bits of the quadrant `n` and the remainder as a double-double.
+///
+/// The fractions of `x * 2/pi` for both parts are added up exactly before multiplying by `pi/2`,
+/// so the remainder stays accurate relative to itself until it's as tiny as `2^-190` or so,
+/// rather than just in absolute terms like the remainder from `rem_pio2`.
+pub fn rem_pio2_double_double(hi: f64, lo: f64) -> (i32, f64, f64) {
+    debug_assert!(hi.is_finite() && lo.is_finite(), "Can't reduce {} + {}", hi, lo);
+    let (high_quadrant, high_top, high_bottom) = signed_fraction_bits(hi);
+    let (low_quadrant, low_top, low_bottom) = signed_fraction_bits(lo);
+    let (bottom, carry) = high_bottom.overflowing_add(low_bottom);
+    let (top, top_carry) = high_top.overflowing_add(low_top);
+    let (top, final_carry) = top.overflowing_add(carry as u128);
+    let mut quadrant = high_quadrant + low_quadrant + top_carry as i32 + final_carry as i32;
+    // Round to the nearest quadrant just like `payne_hanek`
+    quadrant += (top >> 127) as i32;
+    let (hi, lo) = wide_fixed_to_double_double(top as i128, bottom);
+    let (hi, lo) = times_pio2(hi, lo);
+    (quadrant & 7, hi, lo)
+}
+
+/// Reduces the positive value `mantissa * 2^exponent` with the Payne-Hanek algorithm.
+fn payne_hanek(mantissa: u64, exponent: i32) -> (i32, f64, f64) {
+    let (mut quadrant, fraction, _) = fraction_bits(mantissa, exponent);
+    /*
+     * Round to the nearest quadrant, so the fraction ends up in [-1/2, 1/2).
+     * Reinterpreting the top 128 bits of the fraction as signed does exactly that,
+     * as long as we bump the quadrant whenever the fraction was at least a half.
+     */
+    quadrant += (fraction >> 127) as i32;
+    let (hi, lo) = fixed_to_double_double(fraction as i128);
+    let (hi, lo) = times_pio2(hi, lo);
+    (quadrant & 7, hi, lo)
+}
+
+/// Splits `mantissa * 2^exponent * 2/pi` into its integer part modulo 8 and the fraction,
+/// which is 192 bits of fixed-point as the top 128 followed by the next 64.
+fn fraction_bits(mantissa: u64, exponent: i32) -> (i32, u128, u64) {
+    /*
+     * Each word `k` of the table contributes `mantissa * word * 2^(exponent - 64k)`,
+     * which is a multiple of eight whenever `exponent - 64k >= 3`.
+     * Those words can't affect the result, so we skip them and start at the first one that can.
+     * The next five words then give us at least 254 bits of fraction,
+     * where the words we leave off only affect the bits past `2^-200` or so.
+     * That's far more than the ~120 bits needed for the worst case cancellation in a double,
+     * and still leaves over a hundred once a double-double cancels that much.
+     */
+    let first_word = if exponent >= 3 {
+        (exponent - 3) / 64 + 1
+    } else {
+        -((2 - exponent) / 64)
+    };
+    let mut product = [0u64; 6];
+    let mut carry = 0u128;
+    for (limb, offset) in product.iter_mut().zip((0..5).rev()) {
+        let word = two_over_pi_word(first_word + offset);
+        let partial = (mantissa as u128) * (word as u128) + carry;
+        *limb = partial as u64;
+        carry = partial >> 64;
+    }
+    product[5] = carry as u64;
+    // The product is scaled by `2^(exponent - 64 * (first_word + 4))`, so that's where the binary point is
+    let point = (64 * (first_word + 4) - exponent) as u32;
+    debug_assert!(point >= 254 && point <= 317, "Invalid binary point {}", point);
+    let quadrant = (extract_bits(&product, point) & 7) as i32;
+    (quadrant, extract_bits(&product, point - 128), extract_bits(&product, point - 192) as u64)
+}
+
+/// Like `fraction_bits`, but for a double of either sign
+fn signed_fraction_bits(x: f64) -> (i32, u128, u64) {
+    let (mantissa, exponent, sign) = x.decode();
+    let (quadrant, top, bottom) = fraction_bits(mantissa, exponent as i32);
+    if sign > 0 || (top == 0 && bottom == 0) {
+        (quadrant, top, bottom)
+    } else {
+        // Negating `quadrant + fraction` gives `(-quadrant - 1) + (1 - fraction)`
+        (-quadrant - 1, (!top).wrapping_add((bottom == 0) as u128), bottom.wrapping_neg())
+    }
+}
+
+/// Multiplies a double-double by `pi/2`,
+/// using a fused multiply-add to get the exact error of the high product.
+fn times_pio2(hi: f64, lo: f64) -> (f64, f64) {
+    let product_hi = hi * PIO2_HI;
+    let error = hi.mul_add(PIO2_HI, -product_hi) + (hi * PIO2_LO + lo * PIO2_HI);
+    let result_hi = product_hi + error;
+    (result_hi, error - (result_hi - product_hi))
+}
+
+/// Extracts the 128 bits starting at the specified bit, treating anything past the end as zero.
+fn extract_bits(limbs: &[u64; 6], start: u32) -> u128 {
+    let limb = |index: usize| limbs.get(index).map_or(0, |&limb| limb as u128);
+    let index = (start / 64) as usize;
+    let shift = start % 64;
+    let mut result = (limb(index) >> shift) | (limb(index + 1) << (64 - shift));
+    if shift != 0 {
+        result |= limb(index + 2) << (128 - shift);
+    }
+    result
+}
+
+/// Converts a signed fixed-point value with 128 fractional bits into a double-double.
+fn fixed_to_double_double(fixed: i128) -> (f64, f64) {
+    let (hi, lo) = scaled_to_double_double(fixed.wrapping_abs() as u128, 128);
+    if fixed < 0 { (-hi, -lo) } else { (hi, lo) }
+}
+
+/// Converts a signed fixed-point value with 128 fractional bits, followed by 64 more, into a double-double.
+fn wide_fixed_to_double_double(fixed: i128, extra: u64) -> (f64, f64) {
+    // Negate all 192 bits at once, which only carries into the top when the bottom word is zero
+    let negative = fixed < 0;
+    let (top, bottom) = if negative {
+        ((!(fixed as u128)).wrapping_add((extra == 0) as u128), extra.wrapping_neg())
+    } else {
+        (fixed as u128, extra)
+    };
+    /*
+     * A double-double only needs the first 107 bits or so, which fit in 128 bits starting at the leading one.
+     * If the top has at least 64 leading zeros, every remaining bit fits without shifting anything out.
+     */
+    let leading_zeros = top.leading_zeros();
+    let (hi, lo) = if leading_zeros >= 64 {
+        scaled_to_double_double((top << 64) | u128::from(bottom), 192)
+    } else {
+        let window = (top << leading_zeros) | (u128::from(bottom) >> (64 - leading_zeros));
+        scaled_to_double_double(window, 128 + leading_zeros as i32)
+    };
+    if negative { (-hi, -lo) } else { (hi, lo) }
+}
+
+/// Converts the value `magnitude * 2^-scale` into a double-double.
+fn scaled_to_double_double(magnitude: u128, scale: i32) -> (f64, f64) {
+    if magnitude == 0 {
+        return (0.0, 0.0)
+    }
+    /*
+     * Normalize so the leading one is the top bit,
+     * then the first 53 bits convert exactly and the next 64 give the low part.
+     */
+    let leading_zeros = magnitude.leading_zeros() as i32;
+    let normalized = magnitude << leading_zeros;
+    let hi = ((normalized >> 75) as f64) * f64::exp2i(75 - scale - leading_zeros);
+    let lo = (((normalized << 53) >> 64) as f64) * f64::exp2i(11 - scale - leading_zeros);
+    (hi, lo)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{rem_pio2, rem_pio2_double_double};
+    use accuracy::relative_error;
+    use float::ToyFloat;
+    use utils::Finite;
+    use extended_float::ExtendedFloat;
+    use rug::Float;
+    use rug::float::Constant;
+    /// Plenty to exactly reduce anything up to about 1e600
+    const PRECISION: u32 = 4096;
+    const RELATIVE_ERROR: f64 = 1e-15;
+    const DOUBLE_DOUBLE_ERROR: f64 = 7.888609052210118e-31; // 2^-100
+
+    /// Reduces the value using a ridiculous number of bits of pi
+    fn exact_rem_pio2(target: Float) -> (i32, Float) {
+        let half_pi = Float::with_val(PRECISION, Constant::Pi) / 2;
+        let quotient = Float::with_val(PRECISION, &target / &half_pi).round();
+        let remainder = target - quotient.clone() * &half_pi;
+        let quadrant = quotient.to_integer().unwrap().mod_u(4) as i32;
+        (quadrant, remainder)
+    }
+
+    fn check<T: ToyFloat>(target: T) -> bool {
+        let (quadrant, hi, lo) = rem_pio2(target);
+        let (expected_quadrant, expected) = exact_rem_pio2(target.to_big(PRECISION));
+        let actual = hi.to_f64() + lo.to_f64();
+        (quadrant & 3) == expected_quadrant &&
+            relative_error(actual, expected.to_f64()) <= RELATIVE_ERROR
+    }
+
+    #[test]
+    fn basic() {
+        assert_eq!(rem_pio2(0.5), (0, 0.5, 0.0));
+        assert!(check(3.0));
+        assert!(check(-3.0));
+        assert!(check(1e6));
+        assert!(check(1e22));
+        assert!(check(::std::f64::MAX));
+        assert!(check(6381956970095103.0 * 2.0f64.powi(797)));
+        assert!(check(1e30f32));
+    }
+
+    #[test]
+    fn extended() {
+        let huge = ExtendedFloat::from(1e300) * ExtendedFloat::from(1e300);
+        assert!(check(huge));
+        assert!(check(-huge));
+        assert!(check(ExtendedFloat::from(12345.678)));
+    }
+
+    /// The remainder of a double-double should be accurate relative to itself,
+    /// even for the double that comes closest to a multiple of pi/2
+    #[test]
+    fn double_double() {
+        let check = |hi: f64, lo: f64| {
+            let (quadrant, actual_hi, actual_lo) = rem_pio2_double_double(hi, lo);
+            let (expected_quadrant, expected) = exact_rem_pio2(Float::with_val(PRECISION, hi) + lo);
+            let error = (Float::with_val(PRECISION, actual_hi) + actual_lo - &expected) / &expected;
+            assert_eq!(quadrant & 3, expected_quadrant, "Wrong quadrant for {} + {}", hi, lo);
+            assert!(error.to_f64().abs() <= DOUBLE_DOUBLE_ERROR, "Failed {} + {}", hi, lo);
+        };
+        let worst_case = 6381956970095103.0 * 2.0f64.powi(797);
+        check(worst_case, 0.0);
+        check(-worst_case, worst_case * 1e-17);
+        check(3.0, 1e-17);
+        check(1e22, -1.0);
+        check(::std::f64::MAX, 1e290);
+    }
+
+    #[quickcheck]
+    fn matches_exact(target: Finite) -> bool {
+        let Finite(target) = target;
+        check(target)
+    }
+}
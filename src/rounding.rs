@@ -0,0 +1,251 @@
+//! Correctly rounded variants of the core functions, which round in a specified direction.
+//!
+//! The regular functions always round to nearest (and aren't always correct in the last place),
+//! so they can't be used to compute guaranteed bounds like interval arithmetic needs.
+//! These compute the result in arbitrary precision instead, so they're much slower.
+use std::cmp::Ordering;
+
+use rug::Float;
+pub use rug::float::Round;
+
+use float::ToyFloat;
+use bits::{next_up, next_down};
+
+/// Computes the square root, rounded in the specified direction
+pub fn sqrt_round<T: ToyFloat>(target: T, round: Round) -> T {
+    if target.is_nan() || target == T::ZERO || target.is_infinite() && target.is_sign_positive() {
+        // This keeps the sign of negative zero, just like `sqrt_correctly_rounded`
+        return target
+    } else if target.is_sign_negative() {
+        return T::NAN
+    }
+    round_exact(|precision| {
+        Float::with_val_round(precision, target.to_big(T::MANTISSA_DIGITS).sqrt_ref(), Round::Down)
+    }, round)
+}
+
+/// Computes the binary logarithm, rounded in the specified direction
+pub fn log2_round<T: ToyFloat>(target: T, round: Round) -> T {
+    if target.is_nan() || target < T::ZERO {
+        return T::NAN
+    } else if target == T::ZERO {
+        return T::NEG_INFINITY
+    } else if target.is_infinite() {
+        return T::INFINITY
+    }
+    round_exact(|precision| {
+        Float::with_val_round(precision, target.to_big(T::MANTISSA_DIGITS).log2_ref(), Round::Down)
+    }, round)
+}
+
+/// Computes the sine, rounded in the specified direction
+pub fn sin_round<T: ToyFloat>(x: T, round: Round) -> T {
+    if !x.is_finite() {
+        return T::NAN
+    } else if x == T::ZERO {
+        // This is exact and keeps the sign
+        return x
+    }
+    round_exact(|precision| {
+        Float::with_val_round(precision, x.to_big(T::MANTISSA_DIGITS).sin_ref(), Round::Down)
+    }, round)
+}
+
+/// Computes the cosine, rounded in the specified direction
+pub fn cos_round<T: ToyFloat>(x: T, round: Round) -> T {
+    if !x.is_finite() {
+        return T::NAN
+    }
+    round_exact(|precision| {
+        Float::with_val_round(precision, x.to_big(T::MANTISSA_DIGITS).cos_ref(), Round::Down)
+    }, round)
+}
+
+/// Rounds a finite exact value to the target type in the specified direction.
+///
+/// The closure gets a precision and must return the exact value rounded down to that precision,
+/// along with how it compares to the exact value (like rug's `_round` methods).
+///
+/// Every value of the target type (and every midpoint between two of them) fits in that precision,
+/// so none of them can lie strictly between the rounded value and the exact one.
+/// That means comparing against the rounded value gives the same answer as comparing against the exact one,
+/// except that being equal to it only means the exact value is equal if the ordering says so.
+fn round_exact<T, F>(compute: F, round: Round) -> T
+    where T: ToyFloat, F: FnOnce(u32) -> (Float, Ordering) {
+    let precision = T::MANTISSA_DIGITS + 2;
+    let (value, ordering) = compute(precision);
+    debug_assert!(value.is_finite() && ordering != Ordering::Greater);
+    let exact = ordering == Ordering::Equal;
+    // Find the largest value of the target type that's less than or equal to the exact value
+    let mut lower = T::from_big(&value);
+    if lower.to_big(precision) > value {
+        // This also steps down from infinity when the result overflows
+        lower = next_down(lower);
+    }
+    if exact && lower.to_big(precision) == value {
+        return lower
+    }
+    let upper = next_up(lower);
+    match round {
+        Round::Down => lower,
+        Round::Up => upper,
+        Round::Zero => if value.is_sign_negative() { upper } else { lower },
+        Round::Nearest => {
+            let (lower_bound, upper_bound) = (bound(lower), bound(upper));
+            let midpoint = Float::with_val(precision, &lower_bound + &upper_bound) >> 1;
+            match value.partial_cmp(&midpoint).unwrap() {
+                Ordering::Less => lower,
+                // The value is only exactly on the midpoint if it's exact, otherwise it's a bit past
+                Ordering::Equal if exact => {
+                    // Break ties to the even significand, where infinity counts as even
+                    if lower.to_packed_bits() & 1 == 0 { lower } else { upper }
+                },
+                Ordering::Equal | Ordering::Greater => upper,
+            }
+        },
+        // rug's enum is non-exhaustive, so there might be modes we don't know about
+        _ => panic!("Unsupported rounding mode: {:?}", round),
+    }
+}
+
+/// Converts the value to an arbitrary precision float for finding midpoints.
+///
+/// Infinity becomes the next power of two past the largest finite value,
+/// so that results round to infinity once they're at least halfway there (just like IEEE).
+fn bound<T: ToyFloat>(value: T) -> Float {
+    if value.is_infinite() {
+        let limit = Float::with_val(T::MANTISSA_DIGITS, 1) << (T::EXPONENT_BIAS + 1);
+        if value.is_sign_negative() { -limit } else { limit }
+    } else {
+        value.to_big(T::MANTISSA_DIGITS)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{sqrt_round, log2_round, sin_round, cos_round, Round};
+    use std::cmp::Ordering;
+    use rug::Float;
+    use bits::{next_up, next_down};
+    use sqrt::sqrt_correctly_rounded;
+    use utils::{Subnormal, Finite};
+    use extended_float::ExtendedFloat;
+    use float::ToyFloat;
+
+    const ROUNDING_MODES: [Round; 4] = [Round::Nearest, Round::Zero, Round::Up, Round::Down];
+
+    /// Computes the result with MPFR's own rounding,
+    /// which is only correct for doubles as long as the result isn't subnormal.
+    fn expected(function: fn(&mut Float, Round) -> Ordering, x: f64, round: Round) -> f64 {
+        let mut result = Float::with_val(53, x);
+        function(&mut result, round);
+        result.to_f64()
+    }
+
+    /// Checks that the directed results bracket the exact value and agree with each other
+    fn check_bounds<T: ToyFloat>(function: fn(T, Round) -> T, x: T, exact: &Float) -> bool {
+        let down = function(x, Round::Down);
+        let up = function(x, Round::Up);
+        let nearest = function(x, Round::Nearest);
+        let zero = function(x, Round::Zero);
+        let precision = T::MANTISSA_DIGITS;
+        let adjacent = if down == up { down.to_big(precision) == *exact } else { next_up(down) == up };
+        down.to_big(precision) <= *exact && up.to_big(precision) >= *exact && adjacent &&
+            (nearest == down || nearest == up) &&
+            zero == if exact.is_sign_negative() { up } else { down }
+    }
+
+    #[test]
+    fn basic() {
+        for &round in &ROUNDING_MODES {
+            assert_eq!(sqrt_round(4.0, round), 2.0);
+            assert_eq!(sqrt_round(-0.0f64, round).to_bits(), (-0.0f64).to_bits());
+            assert!(sqrt_round(-1.0, round).is_nan());
+            assert_eq!(log2_round(8.0, round), 3.0);
+            assert_eq!(log2_round(1.0f64, round).to_bits(), 0);
+            assert_eq!(log2_round(0.0, round), ::std::f64::NEG_INFINITY);
+            assert_eq!(sin_round(-0.0f64, round).to_bits(), (-0.0f64).to_bits());
+            assert_eq!(cos_round(0.0, round), 1.0);
+            assert!(sin_round(::std::f64::INFINITY, round).is_nan());
+        }
+        // The nearest double to sqrt(2) is slightly too large
+        assert_eq!(sqrt_round(2.0, Round::Up), ::std::f64::consts::SQRT_2);
+        assert_eq!(sqrt_round(2.0, Round::Down), next_down(::std::f64::consts::SQRT_2));
+        // cos(x) is just below one, so only rounding down (or toward zero) moves off of it
+        assert_eq!(cos_round(1e-10, Round::Nearest), 1.0);
+        assert_eq!(cos_round(1e-10, Round::Up), 1.0);
+        assert!(cos_round(1e-10, Round::Down) < 1.0);
+        assert_eq!(cos_round(1e-10, Round::Zero), cos_round(1e-10, Round::Down));
+        // sin(x) is just closer to zero than x
+        assert_eq!(sin_round(1e-200, Round::Nearest), 1e-200);
+        assert!(sin_round(1e-200, Round::Zero) < 1e-200);
+        assert_eq!(sin_round(-1e-200, Round::Zero), -sin_round(1e-200, Round::Zero));
+        assert_eq!(sin_round(-1e-200, Round::Down), -1e-200);
+    }
+
+    #[test]
+    fn subnormal() {
+        let tiny = f64::from_bits(1);
+        // sin(tiny) is only barely below tiny, so it's nowhere near the midpoint to zero
+        assert_eq!(sin_round(tiny, Round::Nearest), tiny);
+        assert_eq!(sin_round(tiny, Round::Up), tiny);
+        assert_eq!(sin_round(tiny, Round::Down), 0.0);
+        assert_eq!(sin_round(-tiny, Round::Down), -tiny);
+        assert_eq!(sin_round(-tiny, Round::Up).to_bits(), (-0.0f64).to_bits());
+        assert_eq!(sqrt_round(tiny, Round::Nearest), 2.2227587494850775e-162);
+    }
+
+    #[test]
+    fn extended() {
+        for &x in &[0.5, 2.0, 3.0, 10.0, 1e10] {
+            let x = ExtendedFloat::from(x);
+            let big = x.to_big(64);
+            assert!(check_bounds(sqrt_round, x, &Float::with_val(256, big.sqrt_ref())));
+            assert!(check_bounds(log2_round, x, &Float::with_val(256, big.log2_ref())));
+            assert!(check_bounds(sin_round, x, &Float::with_val(256, big.sin_ref())));
+            assert!(check_bounds(cos_round, x, &Float::with_val(256, big.cos_ref())));
+        }
+    }
+
+    #[quickcheck]
+    fn sqrt_matches_mpfr(target: Finite) -> bool {
+        let target = target.0.abs();
+        sqrt_round(target, Round::Nearest) == sqrt_correctly_rounded(target) &&
+            ROUNDING_MODES.iter().all(|&round| {
+                sqrt_round(target, round) == expected(Float::sqrt_round, target, round)
+            })
+    }
+
+    #[quickcheck]
+    fn log2_matches_mpfr(target: Finite) -> bool {
+        let target = target.0.abs();
+        ROUNDING_MODES.iter().all(|&round| log2_round(target, round) == expected(Float::log2_round, target, round))
+    }
+
+    #[quickcheck]
+    fn trig_matches_mpfr(x: f64) -> bool {
+        let big = x * 1e5;
+        ROUNDING_MODES.iter().all(|&round| {
+            sin_round(x, round) == expected(Float::sin_round, x, round) &&
+                cos_round(x, round) == expected(Float::cos_round, x, round) &&
+                sin_round(big, round) == expected(Float::sin_round, big, round) &&
+                cos_round(big, round) == expected(Float::cos_round, big, round)
+        })
+    }
+
+    #[quickcheck]
+    fn subnormal_bounds(x: Subnormal) -> bool {
+        let big = Float::with_val(53, x.0);
+        check_bounds(sin_round, x.0, &Float::with_val(256, big.sin_ref())) &&
+            check_bounds(sqrt_round, x.0, &Float::with_val(256, big.sqrt_ref()))
+    }
+
+    #[quickcheck]
+    fn single_bounds(x: f32) -> bool {
+        let big = Float::with_val(24, x);
+        let positive = Float::with_val(24, x.abs());
+        check_bounds(sin_round, x, &Float::with_val(256, big.sin_ref())) &&
+            check_bounds(cos_round, x, &Float::with_val(256, big.cos_ref())) &&
+            check_bounds(sqrt_round, x.abs(), &Float::with_val(256, positive.sqrt_ref()))
+    }
+}
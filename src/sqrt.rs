@@ -1,8 +1,15 @@
-use std::f64::NAN;
+use std::cmp::Ordering;
 use std::num::FpCategory;
 
+use rug::Float;
+
+use float::ToyFloat;
+
+/// Enough precision to exactly square the midpoint between two doubles
+const PRECISION: u32 = 128;
+
 #[inline]
-pub fn approximate_sqrt(target: f64) -> f64 {
+pub fn approximate_sqrt<T: ToyFloat>(target: T) -> T {
     /*
      * In order to find a good initial estimate,
      * we simply divide the exponent by two.
@@ -14,15 +21,15 @@ pub fn approximate_sqrt(target: f64) -> f64 {
      *
      * See http://bits.stephan-brumme.com/squareRoot.html for details
      */
-    let mut bits = target.to_bits();
-    bits += 1023 << 52;
+    let mut bits = target.to_packed_bits();
+    bits += (T::EXPONENT_BIAS as u128) << T::FRACTION_BITS;
     bits >>= 1;
-    f64::from_bits(bits)
+    T::from_packed_bits(bits)
 }
 
-pub fn sqrt(target: f64) -> f64 {
+pub fn sqrt<T: ToyFloat>(target: T) -> T {
     if !target.is_nan() && !target.is_sign_positive() {
-        return NAN;
+        return T::NAN;
     }
     match target.classify() {
         FpCategory::Nan | FpCategory::Infinite | FpCategory::Zero => {
@@ -31,7 +38,16 @@ pub fn sqrt(target: f64) -> f64 {
              */
             return target
         },
-        FpCategory::Subnormal => unimplemented!("subnormal numbers"),
+        FpCategory::Subnormal => {
+            /*
+             * The exponent trick in `approximate_sqrt` falls apart for subnormals,
+             * since they don't have an implicit leading bit.
+             * Scaling by an even power of two brings them into the normal range,
+             * and then we can exactly halve the power afterwards since sqrt(2^2k) = 2^k.
+             */
+            let shift = (T::MANTISSA_DIGITS as i32 + 1) & !1;
+            return sqrt(target * T::exp2i(shift)) * T::exp2i(-shift / 2)
+        },
         FpCategory::Normal => {},
     }
     /*
@@ -45,9 +61,227 @@ pub fn sqrt(target: f64) -> f64 {
      */
     let initial_estimate = approximate_sqrt(target);
     let mut last = initial_estimate;
-    const HALF: f64 = 1.0/2.0;
-    for _ in 0..3 {
-        let current = HALF * (last + target / last);
+    // Each iteration roughly doubles the correct bits, so extended precision needs one more
+    let iterations = if T::MANTISSA_DIGITS > 53 { 4 } else { 3 };
+    for _ in 0..iterations {
+        let current = T::HALF * (last + target / last);
+        last = current;
+    }
+    last
+}
+
+/// Computes the IEEE-754 correctly rounded square root.
+///
+/// This starts with the result of the regular `sqrt`,
+/// then nudges it an ulp at a time until an exact residual proves it's the nearest double.
+pub fn sqrt_correctly_rounded(target: f64) -> f64 {
+    match target.classify() {
+        // IEEE requires sqrt(-0) == -0
+        FpCategory::Nan | FpCategory::Zero => return target,
+        _ if target.is_sign_negative() => return ::std::f64::NAN,
+        FpCategory::Infinite => return target,
+        FpCategory::Subnormal | FpCategory::Normal => {}
+    }
+    /*
+     * The regular sqrt is only accurate to around 40 bits,
+     * so one more Newton iteration gets us within an ulp or so of the right answer.
+     */
+    let mut result = sqrt(target);
+    result = 0.5 * (result + target / result);
+    loop {
+        /*
+         * Since the result is always positive and finite,
+         * incrementing the bits gives the next representable double.
+         */
+        match rounding_error(target, result) {
+            Ordering::Less => result = f64::from_bits(result.to_bits() + 1),
+            Ordering::Greater => result = f64::from_bits(result.to_bits() - 1),
+            Ordering::Equal => return result,
+        }
+    }
+}
+
+/// Checks whether `result` is the correctly rounded square root of `target`,
+/// returning `Less` if it's too small and `Greater` if it's too large.
+///
+/// The correctly rounded result is the unique double where `target` falls between
+/// the squares of the midpoints to its neighbors.
+/// Those midpoints have 54 significant bits, so their squares are exact at our precision.
+/// The squared midpoint also always has an odd 107-bit significand,
+/// so it can never be equal to `target` and we never need to break a tie.
+fn rounding_error(target: f64, result: f64) -> Ordering {
+    debug_assert!(result.is_finite() && result > 0.0);
+    let exact = Float::with_val(PRECISION, target);
+    let below = f64::from_bits(result.to_bits() - 1);
+    let above = f64::from_bits(result.to_bits() + 1);
+    let lower_midpoint = (Float::with_val(PRECISION, result) + below) / 2;
+    let upper_midpoint = (Float::with_val(PRECISION, result) + above) / 2;
+    if upper_midpoint.square() < exact {
+        Ordering::Less
+    } else if lower_midpoint.square() > exact {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    }
+}
+
+/// The number of bits we can count on being correct in the bit-level estimates below.
+///
+/// The worst case relative error of the tuned `approximate_rsqrt` is 3.4% (4.8 bits),
+/// and the cube root estimate is off by at most 5.8% (4.1 bits).
+const ESTIMATE_BITS: u32 = 4;
+/// The fraction of the mantissa field that's subtracted from the reciprocal sqrt magic number,
+/// as a 64-bit fixed point fraction.
+///
+/// This is taken from the tuned double precision constant 0x5fe6eb50c7b537a9,
+/// which minimizes the worst case relative error.
+const RSQRT_TUNING: u64 = 0x114a_f384_ac85_7000;
+/// The maximum number of steps `nth_root` takes before giving up on convergence.
+const MAX_ROOT_ITERATIONS: usize = 100;
+
+/// Determines how many refinement steps we need to reach full precision,
+/// given each step multiplies the number of correct bits by the `order` of convergence.
+fn refinement_steps<T: ToyFloat>(order: u32) -> u32 {
+    let mut bits = ESTIMATE_BITS;
+    let mut steps = 0;
+    while bits < T::MANTISSA_DIGITS + 2 {
+        bits *= order;
+        steps += 1;
+    }
+    steps
+}
+
+#[inline]
+pub fn approximate_rsqrt<T: ToyFloat>(target: T) -> T {
+    /*
+     * This is the actual Quake fast reciprocal sqrt.
+     * Subtracting half the bits from a magic constant
+     * simultaneously halves and negates the exponent,
+     * while the mantissa bits give a rough linear interpolation.
+     * The magic number starts as `1.5 * bias` in the exponent field,
+     * which for f32 is 0x5f400000.
+     * Left untuned that's off by almost 9%, so like Quake's 0x5f3759df
+     * we subtract a little from the mantissa field to balance the error.
+     */
+    let tuning = (RSQRT_TUNING as u128) >> (64 - T::FRACTION_BITS);
+    let magic = ((3 * ((T::EXPONENT_BIAS as u128) << T::FRACTION_BITS)) >> 1) - tuning;
+    T::from_packed_bits(magic - (target.to_packed_bits() >> 1))
+}
+
+/// Computes the reciprocal square root `1/sqrt(x)`
+pub fn rsqrt<T: ToyFloat>(target: T) -> T {
+    match target.classify() {
+        FpCategory::Nan => return target,
+        FpCategory::Zero => {
+            return if target.is_sign_negative() { T::NEG_INFINITY } else { T::INFINITY }
+        },
+        _ if target.is_sign_negative() => return T::NAN,
+        FpCategory::Infinite => return T::ZERO,
+        FpCategory::Subnormal => {
+            // See `sqrt` for why we scale by an even power of two
+            let shift = (T::MANTISSA_DIGITS as i32 + 1) & !1;
+            return rsqrt(target * T::exp2i(shift)) * T::exp2i(shift / 2)
+        },
+        FpCategory::Normal => {},
+    }
+    /*
+     * Newton's method for `1/y^2 - x` conveniently doesn't need any division,
+     * which is the whole reason Quake used it.
+     */
+    let three_halves = T::ONE + T::HALF;
+    let half_target = T::HALF * target;
+    let mut last = approximate_rsqrt(target);
+    for _ in 0..refinement_steps::<T>(2) {
+        last = last * (three_halves - half_target * last * last);
+    }
+    last
+}
+
+/// Approximates the n-th root by dividing the unbiased exponent by `n`,
+/// which is the generalization of `approximate_sqrt`.
+#[inline]
+fn approximate_nth_root<T: ToyFloat>(target: T, n: u32) -> T {
+    debug_assert!(target.is_normal() && target.is_sign_positive());
+    let bias = (T::EXPONENT_BIAS as i128) << T::FRACTION_BITS;
+    let bits = target.to_packed_bits() as i128;
+    T::from_packed_bits(((bits - bias) / n as i128 + bias) as u128)
+}
+
+/// Computes the cube root, which unlike the square root is defined for negative numbers.
+pub fn cbrt<T: ToyFloat>(target: T) -> T {
+    match target.classify() {
+        FpCategory::Nan | FpCategory::Infinite | FpCategory::Zero => return target,
+        FpCategory::Subnormal => {
+            // Same trick as `sqrt`, except we need a multiple of three
+            let shift = 3 * ((T::MANTISSA_DIGITS as i32 + 2) / 3);
+            return cbrt(target * T::exp2i(shift)) * T::exp2i(-shift / 3)
+        },
+        FpCategory::Normal => {},
+    }
+    if target.is_sign_negative() {
+        return -cbrt(-target);
+    }
+    /*
+     * Halley's method converges cubically for the cube root,
+     * and the update `y * (y^3 + 2x) / (2y^3 + x)` only needs the ratio `y^3 / x`.
+     * Computing that ratio as `y * y * (y / x)` avoids overflowing near the top of the range.
+     */
+    let mut last = approximate_nth_root(target, 3);
+    for _ in 0..refinement_steps::<T>(3) {
+        let ratio = last * last * (last / target);
+        last = last * (ratio + T::TWO) / (T::TWO * ratio + T::ONE);
+    }
+    last
+}
+
+/// Computes the n-th root of the specified number.
+///
+/// Negative values of `n` give the reciprocal of the root,
+/// and odd roots of negative numbers are negative.
+pub fn nth_root<T: ToyFloat>(target: T, n: i32) -> T {
+    if n < 0 {
+        T::ONE / nth_root_unsigned(target, n.wrapping_neg() as u32)
+    } else {
+        nth_root_unsigned(target, n as u32)
+    }
+}
+fn nth_root_unsigned<T: ToyFloat>(target: T, n: u32) -> T {
+    match n {
+        0 => return T::NAN,
+        1 => return target,
+        2 => return sqrt(target),
+        3 => return cbrt(target),
+        _ => {}
+    }
+    match target.classify() {
+        FpCategory::Nan | FpCategory::Zero => return target,
+        _ if target.is_sign_negative() => {
+            // Odd roots of negative numbers are negative, but even roots don't exist
+            return if n & 1 == 1 { -nth_root_unsigned(-target, n) } else { T::NAN }
+        },
+        FpCategory::Infinite => return target,
+        FpCategory::Subnormal if n <= (T::EXPONENT_BIAS / 2) as u32 => {
+            let shift = n as i32 * ((T::MANTISSA_DIGITS as i32 + n as i32 - 1) / n as i32);
+            return nth_root_unsigned(target * T::exp2i(shift), n) * T::exp2i(-shift / n as i32)
+        },
+        FpCategory::Subnormal | FpCategory::Normal => {},
+    }
+    /*
+     * Newton's method for `y^n - x` gives `((n - 1)y + x / y^(n - 1)) / n`,
+     * which is the same as the sqrt iteration when `n = 2`.
+     * The error constant grows with `n` so we can't use a fixed number of iterations,
+     * and instead just run until it stops changing.
+     */
+    let n_float = T::from_u64(n as u64);
+    let n_minus_one = T::from_u64((n - 1) as u64);
+    let mut last = if target.is_normal() {
+        approximate_nth_root(target, n)
+    } else {
+        T::ONE
+    };
+    for _ in 0..MAX_ROOT_ITERATIONS {
+        let current = (n_minus_one * last + target / last.powi((n - 1) as i32)) / n_float;
+        if current == last { break }
         last = current;
     }
     last
@@ -55,20 +289,148 @@ pub fn sqrt(target: f64) -> f64 {
 
 #[cfg(test)]
 mod test {
-    use super::sqrt;
-    use utils::NearlyEquals;
-    const ALLOWED_ERROR: f64 = 1e-10;
+    use super::{sqrt, sqrt_correctly_rounded, rounding_error, rsqrt, cbrt, nth_root};
+    use std::cmp::Ordering;
+    use accuracy::ulp_distance;
+    use utils::Subnormal;
+    use extended_float::ExtendedFloat;
+    /// Three Newton iterations give about 40 bits of relative accuracy
+    const SQRT_ALLOWED_ULPS: u64 = 1 << 13;
+    /// `rsqrt` and `cbrt` iterate to full precision, so only rounding error is left
+    const ROOT_ALLOWED_ULPS: u64 = 4;
+    /// `nth_root` also iterates to full precision,
+    /// but the expected values from `powf(1/n)` are off by a few ulps from rounding `1/n`.
+    const NTH_ROOT_ALLOWED_ULPS: u64 = 256;
+
     #[test]
     fn basic() {
-        assert_nearly_equals!(sqrt(18.0), 18.0f64.sqrt(), ALLOWED_ERROR)
+        assert_within_ulps!(sqrt(18.0), 18.0f64.sqrt(), SQRT_ALLOWED_ULPS)
+    }
+    #[test]
+    fn subnormal() {
+        let smallest = f64::from_bits(1);
+        assert_within_ulps!(sqrt(smallest), smallest.sqrt(), SQRT_ALLOWED_ULPS);
+        let largest = f64::from_bits(0xfffffffffffff);
+        assert_within_ulps!(sqrt(largest), largest.sqrt(), SQRT_ALLOWED_ULPS);
+        assert!(sqrt(f32::from_bits(1)).is_normal());
+    }
+    #[test]
+    fn roots() {
+        assert_within_ulps!(rsqrt(4.0), 0.5, ROOT_ALLOWED_ULPS);
+        assert_eq!(rsqrt(0.0), ::std::f64::INFINITY);
+        assert_eq!(rsqrt(-0.0), ::std::f64::NEG_INFINITY);
+        assert_eq!(rsqrt(::std::f64::INFINITY), 0.0);
+        assert!(rsqrt(-1.0).is_nan());
+        assert_within_ulps!(cbrt(27.0), 3.0, ROOT_ALLOWED_ULPS);
+        assert_within_ulps!(cbrt(-8.0), -2.0, ROOT_ALLOWED_ULPS);
+        assert_eq!(cbrt(::std::f64::NEG_INFINITY), ::std::f64::NEG_INFINITY);
+        assert_within_ulps!(nth_root(32.0, 5), 2.0, NTH_ROOT_ALLOWED_ULPS);
+        assert_within_ulps!(nth_root(-32.0, 5), -2.0, NTH_ROOT_ALLOWED_ULPS);
+        assert_within_ulps!(nth_root(16.0, -4), 0.5, NTH_ROOT_ALLOWED_ULPS);
+        assert!(nth_root(-16.0, 4).is_nan());
+        assert!(nth_root(16.0, 0).is_nan());
+        assert_within_ulps!(nth_root(2.0, ::std::i32::MIN), 2.0f64.powf(-1.0 / 2147483648.0), NTH_ROOT_ALLOWED_ULPS);
+        let subnormal = f64::from_bits(12345);
+        assert_within_ulps!(rsqrt(subnormal), 1.0 / subnormal.sqrt(), ROOT_ALLOWED_ULPS);
+        assert_within_ulps!(cbrt(subnormal), subnormal.cbrt(), ROOT_ALLOWED_ULPS);
+        assert_within_ulps!(nth_root(subnormal, 7), subnormal.powf(1.0 / 7.0), NTH_ROOT_ALLOWED_ULPS);
+    }
+    #[test]
+    fn generic() {
+        assert_eq!(sqrt(25.0f32), 5.0);
+        assert_eq!(sqrt(ExtendedFloat::from(25.0)), ExtendedFloat::from(5.0));
     }
 
     #[quickcheck]
     fn sqrt_matches_std(target: f64) -> bool {
         if target.is_sign_positive() {
-            sqrt(target).nearly_equals(target.sqrt(), ALLOWED_ERROR)
+            ulp_distance(sqrt(target), target.sqrt()) <= SQRT_ALLOWED_ULPS
         } else {
             true
         }
     }
+
+    #[quickcheck]
+    fn subnormal_sqrt_matches_std(target: Subnormal) -> bool {
+        let Subnormal(target) = target;
+        ulp_distance(sqrt(target), target.sqrt()) <= SQRT_ALLOWED_ULPS
+    }
+
+    /// Sweeps every exponent (including subnormals) with a spread of mantissas,
+    /// verifying the results both with the exact residual and bit-for-bit against the stdlib.
+    #[test]
+    fn correctly_rounded_exponent_sweep() {
+        const MANTISSA_MASK: u64 = 0xfffffffffffff;
+        const SAMPLES_PER_EXPONENT: usize = 64;
+        // Simple LCG so the sweep is deterministic
+        let mut state = 0x853c49e6748fea9bu64;
+        for exponent in 0..0x7ffu64 {
+            let mut mantissas = vec![0, 1, MANTISSA_MASK, MANTISSA_MASK - 1, 1 << 51];
+            for _ in 0..SAMPLES_PER_EXPONENT {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                mantissas.push(state >> 12);
+            }
+            for mantissa in mantissas {
+                let target = f64::from_bits((exponent << 52) | (mantissa & MANTISSA_MASK));
+                if target == 0.0 { continue }
+                let result = sqrt_correctly_rounded(target);
+                assert_eq!(
+                    rounding_error(target, result), Ordering::Equal,
+                    "Incorrectly rounded sqrt({:e}) = {:e}", target, result
+                );
+                assert_eq!(
+                    result.to_bits(), target.sqrt().to_bits(),
+                    "Failed sqrt({:e}): {:e} != {:e}", target, result, target.sqrt()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn correctly_rounded_special() {
+        assert!(sqrt_correctly_rounded(-1.0).is_nan());
+        assert!(sqrt_correctly_rounded(::std::f64::NAN).is_nan());
+        assert_eq!(sqrt_correctly_rounded(::std::f64::INFINITY), ::std::f64::INFINITY);
+        assert_eq!(sqrt_correctly_rounded(0.0), 0.0);
+        assert!(sqrt_correctly_rounded(-0.0).is_sign_negative());
+        assert_eq!(sqrt_correctly_rounded(2.0), ::std::f64::consts::SQRT_2);
+        assert_eq!(sqrt_correctly_rounded(::std::f64::MAX), ::std::f64::MAX.sqrt());
+    }
+
+    #[quickcheck]
+    fn correctly_rounded_matches_std(target: f64) -> bool {
+        let (result, expected) = (sqrt_correctly_rounded(target), target.sqrt());
+        result.to_bits() == expected.to_bits() || (result.is_nan() && expected.is_nan())
+    }
+
+    #[quickcheck]
+    fn rsqrt_matches_std(target: f64) -> bool {
+        if target >= 0.0 {
+            ulp_distance(rsqrt(target), 1.0 / target.sqrt()) <= ROOT_ALLOWED_ULPS
+        } else {
+            rsqrt(target).is_nan()
+        }
+    }
+
+    #[quickcheck]
+    fn cbrt_matches_std(target: f64) -> bool {
+        ulp_distance(cbrt(target), target.cbrt()) <= ROOT_ALLOWED_ULPS
+    }
+
+    #[quickcheck]
+    fn nth_root_matches_std(target: f64, n: u8) -> bool {
+        if n == 0 {
+            return true
+        }
+        let expected = target.abs().powf(1.0 / n as f64);
+        // Square roots just use `sqrt`, which isn't iterated to full precision
+        let allowed = if n == 2 { SQRT_ALLOWED_ULPS } else { NTH_ROOT_ALLOWED_ULPS };
+        if target.is_sign_positive() {
+            ulp_distance(nth_root(target, n as i32), expected) <= allowed
+        } else if n & 1 == 1 {
+            ulp_distance(nth_root(target, n as i32), -expected) <= allowed
+        } else {
+            nth_root(target, n as i32).is_nan()
+        }
+    }
 }
\ No newline at end of file
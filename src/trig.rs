@@ -1,49 +1,229 @@
 //! Basic trigonometric functions
-use std::f64;
+use float::ToyFloat;
 
-const PRECISION: u32 = 128;
+/// The worst error of `sin` and `cos` for doubles in ulps, which `interval` widens their results by.
+///
+/// Results near multiples of pi/2 are only as accurate as the range reduction,
+/// and the tests allow a relative error of `1e-13` there, which is just over 900 ulps.
+pub const ERROR_ULPS: u32 = 1 << 10;
 
 /// Returns a tuple of the sine and cosine
 /// of the specified number in degrees radian.
-pub fn sin_cos(x: f64) -> (f64, f64) {
+pub fn sin_cos<T: ToyFloat>(x: T) -> (T, T) {
     (sin(x), cos(x))
 }
 
-pub fn sin(mut x: f64) -> f64 {
+pub fn sin<T: ToyFloat>(x: T) -> T {
+    if !x.is_finite() {
+        return T::NAN;
+    }
+    // Reduce to [-pi/4, pi/4] and then pick the right function for the quadrant
+    let (quadrant, hi, lo) = ::reduce::rem_pio2(x);
+    match quadrant & 3 {
+        0 => sin_reduced(hi, lo),
+        1 => cos_reduced(hi, lo),
+        2 => -sin_reduced(hi, lo),
+        _ => -cos_reduced(hi, lo),
+    }
+}
+
+
+pub fn cos<T: ToyFloat>(x: T) -> T {
+    if !x.is_finite() {
+        return T::NAN;
+    }
+    // Reduce to [-pi/4, pi/4] and then pick the right function for the quadrant
+    let (quadrant, hi, lo) = ::reduce::rem_pio2(x);
+    match quadrant & 3 {
+        0 => cos_reduced(hi, lo),
+        1 => -sin_reduced(hi, lo),
+        2 => -cos_reduced(hi, lo),
+        _ => sin_reduced(hi, lo),
+    }
+}
+
+/// Computes `sin(hi + lo)` for a reduced argument in `[-pi/4, pi/4]`
+fn sin_reduced<T: ToyFloat>(hi: T, lo: T) -> T {
     // Sine is an odd function
+    let result = if hi.is_sign_negative() { -sin0(-hi) } else { sin0(hi) };
+    if lo == T::ZERO {
+        return result;
+    }
+    // The low part is tiny, so a first order correction is plenty (sin'(x) = cos(x) ~ 1 - x^2/2)
+    result + lo * (T::ONE - T::HALF * hi * hi)
+}
+
+/// Computes `cos(hi + lo)` for a reduced argument in `[-pi/4, pi/4]`
+fn cos_reduced<T: ToyFloat>(hi: T, lo: T) -> T {
+    // Cosine is an even function, and cos'(x) = -sin(x) ~ -x
+    cos0(hi.abs()) - lo * hi
+}
+
+pub fn tan<T: ToyFloat>(x: T) -> T {
+    if !x.is_finite() {
+        return T::NAN;
+    }
+    /*
+     * Shifting by pi/2 turns sin/cos into cos/-sin,
+     * so the odd quadrants give the negative reciprocal.
+     */
+    let (quadrant, hi, lo) = ::reduce::rem_pio2(x);
+    let (sin, cos) = (sin_reduced(hi, lo), cos_reduced(hi, lo));
+    if quadrant & 1 == 0 {
+        sin / cos
+    } else {
+        -cos / sin
+    }
+}
+
+pub fn atan<T: ToyFloat>(x: T) -> T {
+    if x.is_nan() {
+        return x;
+    }
+    // Arctangent is an odd function
     if x.is_sign_negative() {
-        return -sin(-x);
-    }
-    // Reduce to [0, 2pi]
-    x %= 2.0 * ::std::f64::consts::PI;
-    if x <= ::std::f64::consts::FRAC_PI_2 {
-        sin0(x)
-    } else if x <= ::std::f64::consts::PI {
-        sin0(::std::f64::consts::PI - x)
-    } else if x <= 3.0 * ::std::f64::consts::FRAC_PI_2 {
-        -sin0(x - ::std::f64::consts::PI)
+        return -atan(-x);
+    }
+    if x <= T::ONE {
+        atan1(x)
     } else {
-        -sin0(::std::f64::consts::PI * 2.0 - x)
+        // atan(x) = pi/2 - atan(1/x), which also handles infinity
+        T::FRAC_PI_2 - atan1(T::ONE / x)
     }
 }
 
+/// Computes the arctangent of the specified `y / x`, using the signs to pick the quadrant.
+pub fn atan2<T: ToyFloat>(y: T, x: T) -> T {
+    if y.is_nan() || x.is_nan() {
+        return T::NAN;
+    }
+    /*
+     * First we find the angle in the first quadrant [0, pi/2],
+     * then reflect it across the y-axis if x is negative,
+     * and finally across the x-axis if y is negative.
+     * The signs of zero are significant here,
+     * so atan2(0, -0) == pi and atan2(-0, 0) == -0.
+     */
+    let angle = if y == T::ZERO {
+        T::ZERO
+    } else if x == T::ZERO {
+        T::FRAC_PI_2
+    } else if y.is_infinite() && x.is_infinite() {
+        T::FRAC_PI_2 * T::HALF
+    } else {
+        atan(y.abs() / x.abs())
+    };
+    let angle = if x.is_sign_negative() { T::PI - angle } else { angle };
+    if y.is_sign_negative() { -angle } else { angle }
+}
 
-pub fn cos(mut x: f64) -> f64 {
-    // Cosine is an even function
-    x = x.abs();
-    // Reduce to [0, 2pi]
-    x %= 2.0 * ::std::f64::consts::PI;
-    if x <= ::std::f64::consts::FRAC_PI_2 {
-        cos0(x)
-    } else if x <= ::std::f64::consts::PI {
-        -cos0(::std::f64::consts::PI - x)
-    } else if x <= 3.0 * ::std::f64::consts::FRAC_PI_2 {
-        -cos0(x - ::std::f64::consts::PI)
+pub fn asin<T: ToyFloat>(x: T) -> T {
+    if x.is_nan() || x.abs() > T::ONE {
+        return T::NAN;
+    }
+    // Arcsine is an odd function
+    if x.is_sign_negative() {
+        return -asin(-x);
+    }
+    if x <= T::HALF {
+        asin0(x)
+    } else {
+        // asin(x) = pi/2 - 2 * asin(sqrt((1 - x) / 2))
+        T::FRAC_PI_2 - T::TWO * asin0(half_angle_root(x))
+    }
+}
+
+pub fn acos<T: ToyFloat>(x: T) -> T {
+    if x.is_nan() || x.abs() > T::ONE {
+        return T::NAN;
+    }
+    if x.abs() <= T::HALF {
+        // acos(x) = pi/2 - asin(x)
+        if x.is_sign_negative() {
+            T::FRAC_PI_2 + asin0(-x)
+        } else {
+            T::FRAC_PI_2 - asin0(x)
+        }
+    } else if x.is_sign_positive() {
+        // acos(x) = 2 * asin(sqrt((1 - x) / 2))
+        T::TWO * asin0(half_angle_root(x))
     } else {
-        cos0(::std::f64::consts::PI * 2.0 - x)
+        // acos(x) = pi - acos(-x)
+        T::PI - T::TWO * asin0(half_angle_root(-x))
     }
 }
 
+/// Computes `sqrt((1 - x) / 2)`, which is the sine of half the angle whose cosine is `x`.
+fn half_angle_root<T: ToyFloat>(x: T) -> T {
+    let target = (T::ONE - x) * T::HALF;
+    if target == T::ZERO {
+        return target;
+    }
+    // One more Newton iteration brings `sqrt` up to full precision
+    let root = ::sqrt::sqrt(target);
+    T::HALF * (root + target / root)
+}
+
+/// The value of `tan(pi/12)`, the cutoff for using `atan0` directly
+const TAN_PI_12: f64 = 0.2679491924311227;
+const SQRT_3: f64 = 1.7320508075688772;
+// Enough for the Taylor series to converge on [0, tan(pi/12)]
+const ATAN_APPROX_ORDER: usize = 31;
+// Enough for the Taylor series to converge on [0, 1/2]
+const ASIN_APPROX_ORDER: usize = 51;
+
+/// Computes the arctangent of `x` in `[0, 1]`
+fn atan1<T: ToyFloat>(x: T) -> T {
+    debug_assert!(x.is_sign_positive() && x <= T::ONE);
+    if x <= T::from_f64(TAN_PI_12) {
+        return atan0(x);
+    }
+    /*
+     * Use the addition formula for tangent with pi/6,
+     * where atan(x) = pi/6 + atan((x * sqrt(3) - 1) / (x + sqrt(3))).
+     * This maps [tan(pi/12), 1] into [-tan(pi/12), tan(pi/12)].
+     */
+    let sqrt_3 = T::from_f64(SQRT_3);
+    let frac_pi_6 = T::PI / T::from_i32(6);
+    let reduced = (x * sqrt_3 - T::ONE) / (x + sqrt_3);
+    if reduced.is_sign_negative() {
+        frac_pi_6 - atan0(-reduced)
+    } else {
+        frac_pi_6 + atan0(reduced)
+    }
+}
+
+fn atan0<T: ToyFloat>(x: T) -> T {
+    // Taylor expansion at zero is sum of ((-1)^n x^(2n+1))/(2n+1)
+    debug_assert!(x.is_sign_positive() && x <= T::from_f64(TAN_PI_12));
+    let mut n = 0usize;
+    let mut result = T::ZERO;
+    while 2 * n + 1 < ATAN_APPROX_ORDER {
+        let mut term = x.powi((2 * n + 1) as i32) / T::from_u64((2 * n + 1) as u64);
+        if n & 1 == 1 {
+            term = -term;
+        }
+        result += term;
+        n += 1;
+    }
+    result
+}
+
+fn asin0<T: ToyFloat>(x: T) -> T {
+    // Taylor expansion at zero is sum of ((2n)! x^(2n+1))/(4^n (n!)^2 (2n+1))
+    debug_assert!(x.is_sign_positive() && x <= T::HALF);
+    let mut n = 0usize;
+    let mut result = T::ZERO;
+    // The central binomial coefficient `(2n)!/(4^n (n!)^2)`, updated incrementally
+    let mut coefficient = T::ONE;
+    while 2 * n + 1 < ASIN_APPROX_ORDER {
+        result += x.powi((2 * n + 1) as i32) * coefficient / T::from_u64((2 * n + 1) as u64);
+        coefficient *= T::from_u64((2 * n + 1) as u64) / T::from_u64((2 * n + 2) as u64);
+        n += 1;
+    }
+    result
+}
+
 // Determined empirically
 const APPROX_ORDER: usize = 18;
 const RECIP_FACT: [f64; 22] = [
@@ -53,13 +233,13 @@ const RECIP_FACT: [f64; 22] = [
     1.1470745597729725e-11, 7.647163731819816e-13, 4.779477332387385e-14, 2.8114572543455206e-15,
     1.5619206968586225e-16, 8.22063524662433e-18, 4.110317623312165e-19, 1.9572941063391263e-20
 ];
-fn sin0(x: f64) -> f64 {
+fn sin0<T: ToyFloat>(x: T) -> T {
     // Taylor expansion at zero is sum of ((-1)^n)x^(2n+1))/(2n+1)!
-    debug_assert!(x.is_sign_positive() && x <= ::std::f64::consts::FRAC_PI_2);
+    debug_assert!(x.is_sign_positive() && x <= T::FRAC_PI_2);
     let mut n = 0usize;
-    let mut result = 0.0;
+    let mut result = T::ZERO;
     while 2 * n + 1 < APPROX_ORDER {
-        let mut term = x.powi((2 * n + 1) as i32)*RECIP_FACT[2 * n + 1];
+        let mut term = x.powi((2 * n + 1) as i32) * T::from_f64(RECIP_FACT[2 * n + 1]);
         if n & 1 == 1 {
             term = -term;
         }
@@ -69,13 +249,13 @@ fn sin0(x: f64) -> f64 {
     result
 }
 
-fn cos0(x: f64) -> f64 {
+fn cos0<T: ToyFloat>(x: T) -> T {
     // Taylor expansion at zero is sum of ((-1)^k x^(2k))/((2k)!)
-    debug_assert!(x.is_sign_positive() && x <= ::std::f64::consts::FRAC_PI_2);
+    debug_assert!(x.is_sign_positive() && x <= T::FRAC_PI_2);
     let mut n = 0usize;
-    let mut result = 0.0;
+    let mut result = T::ZERO;
     while 2 * n < APPROX_ORDER {
-        let mut term = x.powi((2 * n) as i32)*RECIP_FACT[2 * n];
+        let mut term = x.powi((2 * n) as i32) * T::from_f64(RECIP_FACT[2 * n]);
         if n & 1 == 1 {
             term = -term;
         }
@@ -88,37 +268,144 @@ fn cos0(x: f64) -> f64 {
 
 #[cfg(test)]
 mod test {
-    use super::{sin, cos, sin_cos};
-    use utils::NearlyEquals;
+    use super::{sin, cos, sin_cos, tan, atan, atan2, asin, acos};
+    use accuracy::ulp_distance;
+    use utils::Finite;
     use std::f64::consts::FRAC_PI_4;
-    const ALLOWED_ERROR: f64 = 1e-12;
+    use extended_float::ExtendedFloat;
+    use rug::Float;
+    use rug::float::Constant;
+    /// The Taylor series lose a few ulps summing their terms, especially for the inverse functions
+    const ALLOWED_ULPS: u64 = 32;
+    /// Results near multiples of pi/2 are only as accurate as the range reduction
+    const RELATIVE_ERROR: f64 = 1e-13;
 
     #[test]
     fn basic() {
         assert_eq!(sin(0.0), 0.0);
         assert_eq!(cos(0.0), 1.0);
-        assert_nearly_equals!(
+        assert_within_ulps!(
             sin(FRAC_PI_4),
             (2.0f64).sqrt() / 2.0,
-            ALLOWED_ERROR
+            ALLOWED_ULPS
         );
-        assert_nearly_equals!(
+        assert_within_ulps!(
             sin_cos(1.0),
             (1.0f64).sin_cos(),
-            ALLOWED_ERROR
+            ALLOWED_ULPS
+        );
+    }
+
+    #[test]
+    fn inverse() {
+        use std::f64::consts::{PI, FRAC_PI_2};
+        assert_within_ulps!(tan(FRAC_PI_4), 1.0, ALLOWED_ULPS);
+        assert_within_ulps!(atan(1.0), FRAC_PI_4, ALLOWED_ULPS);
+        assert_eq!(atan(::std::f64::INFINITY), FRAC_PI_2);
+        assert_eq!(asin(1.0), FRAC_PI_2);
+        assert_eq!(asin(-0.0).to_bits(), (-0.0f64).to_bits());
+        assert_within_ulps!(acos(-1.0), PI, ALLOWED_ULPS);
+        assert_eq!(acos(1.0), 0.0);
+        assert!(asin(1.5).is_nan());
+        assert!(acos(-1.5).is_nan());
+        assert_eq!(atan2(0.0, -0.0), PI);
+        assert_eq!(atan2(-0.0, 0.0).to_bits(), (-0.0f64).to_bits());
+        assert_eq!(atan2(1.0, 0.0), FRAC_PI_2);
+        assert_within_ulps!(atan2(-1.0, -1.0), -3.0 * FRAC_PI_4, ALLOWED_ULPS);
+        assert_within_ulps!(
+            atan2(::std::f64::INFINITY, ::std::f64::NEG_INFINITY),
+            3.0 * FRAC_PI_4,
+            ALLOWED_ULPS
+        );
+        assert_eq!(atan2(-1.0, ::std::f64::NEG_INFINITY), -PI);
+    }
+
+    #[test]
+    fn generic() {
+        assert_eq!(sin(0.0f32), 0.0);
+        assert_eq!(cos(0.0f32), 1.0);
+        assert_within_ulps!(sin(1.0f32), (1.0f32).sin(), ALLOWED_ULPS);
+        assert_within_ulps!(
+            f64::from(cos(ExtendedFloat::from(1.0))),
+            (1.0f64).cos(),
+            ALLOWED_ULPS
         );
     }
 
     #[quickcheck]
     fn matches_std(target: f64) {
         if target.is_sign_positive() {
-            assert_nearly_equals!(
+            assert_within_ulps!(
                 sin_cos(target),
                 target.sin_cos(),
-                ALLOWED_ERROR,
+                ALLOWED_ULPS,
                 "Failed {}",
                 target
             )
         }
     }
+
+    #[quickcheck]
+    fn matches_std_everywhere(target: Finite) {
+        let Finite(target) = target;
+        assert_within_ulps!(
+            sin_cos(target),
+            target.sin_cos(),
+            ALLOWED_ULPS,
+            "Failed {:e}",
+            target
+        )
+    }
+
+    /// Checks the doubles closest to multiples of pi/2 all the way up to 1e300,
+    /// where the results are tiny and all the precision comes from the range reduction.
+    #[test]
+    fn near_multiples_of_pi_2() {
+        let half_pi = Float::with_val(2048, Constant::Pi) / 2;
+        let mut multiple = Float::with_val(2048, 1);
+        while multiple < 1e300 {
+            for offset in 0..3 {
+                let target = Float::with_val(2048, &multiple + offset) * &half_pi;
+                let target = target.to_f64();
+                assert_within_relative_error!(
+                    sin_cos(target),
+                    target.sin_cos(),
+                    RELATIVE_ERROR,
+                    "Failed {:e}",
+                    target
+                );
+            }
+            multiple *= 10;
+        }
+        // The double that comes closest to a multiple of pi/2
+        let worst_case = 6381956970095103.0 * 2.0f64.powi(797);
+        assert_within_relative_error!(sin(worst_case), worst_case.sin(), RELATIVE_ERROR);
+        assert_within_relative_error!(cos(worst_case), worst_case.cos(), RELATIVE_ERROR);
+    }
+
+    #[quickcheck]
+    fn tan_matches_std(target: Finite) -> bool {
+        let Finite(target) = target;
+        ulp_distance(tan(target), target.tan()) <= ALLOWED_ULPS
+    }
+
+    #[quickcheck]
+    fn atan_matches_std(target: f64) -> bool {
+        ulp_distance(atan(target), target.atan()) <= ALLOWED_ULPS
+    }
+
+    #[quickcheck]
+    fn atan2_matches_std(y: f64, x: f64) -> bool {
+        ulp_distance(atan2(y, x), y.atan2(x)) <= ALLOWED_ULPS
+    }
+
+    #[quickcheck]
+    fn asin_acos_matches_std(target: f64) -> bool {
+        // Only the fractional part is in the domain, but the rest should still give NaN
+        let fraction = target.fract();
+        ulp_distance(asin(target), target.asin()) <= ALLOWED_ULPS &&
+            ulp_distance(acos(target), target.acos()) <= ALLOWED_ULPS &&
+            ulp_distance(asin(fraction), fraction.asin()) <= ALLOWED_ULPS &&
+            ulp_distance(acos(fraction), fraction.acos()) <= ALLOWED_ULPS
+    }
 }
\ No newline at end of file